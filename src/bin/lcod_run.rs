@@ -6,7 +6,7 @@ use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::{anyhow, Context, Result};
 use clap::{ArgAction, Parser, ValueEnum};
@@ -14,10 +14,13 @@ use dirs::home_dir;
 use flate2::read::GzDecoder;
 use hex;
 use humantime::format_duration;
-use lcod_kernel_rs::compose::{parse_compose, run_compose, Step};
+use lcod_kernel_rs::checkpoint::{compose_fingerprint, CheckpointSession};
+use lcod_kernel_rs::compose::{run_compose_with_signature, Step};
 use lcod_kernel_rs::compose_contracts::register_compose_contracts;
+use lcod_kernel_rs::compose_loader::load_compose_document;
 use lcod_kernel_rs::core::register_core;
 use lcod_kernel_rs::flow::register_flow;
+use lcod_kernel_rs::fs_policy::FsPolicy;
 use lcod_kernel_rs::http::register_http_contracts;
 use lcod_kernel_rs::registry::Registry;
 use lcod_kernel_rs::tooling::{
@@ -27,7 +30,6 @@ use lcod_kernel_rs::CancelledError;
 use lcod_kernel_rs::Context as KernelContext;
 use serde::Deserialize;
 use serde_json::{json, Map, Value};
-use serde_yaml;
 use sha2::{Digest, Sha256};
 use tar::Archive;
 use tempfile::{Builder as TempDirBuilder, TempDir};
@@ -135,6 +137,34 @@ struct CliOptions {
     /// Abort execution after the given duration (e.g. "30s", "2m")
     #[arg(long = "timeout", value_parser = humantime::parse_duration, value_name = "DURATION")]
     timeout: Option<Duration>,
+
+    /// Persist the state after each top-level step so the run can be resumed
+    #[arg(long = "checkpoint", action = ArgAction::SetTrue)]
+    checkpoint: bool,
+
+    /// Resume a checkpointed run from its last completed step
+    #[arg(long = "resume", value_name = "RUN_ID", conflicts_with = "checkpoint")]
+    resume: Option<String>,
+
+    /// Override the checkpoint directory (defaults to .lcod/checkpoints next to the compose)
+    #[arg(long = "checkpoint-dir")]
+    checkpoint_dir: Option<PathBuf>,
+
+    /// Value of a secret masked in the resumed checkpoint, as PATH=VALUE (repeatable)
+    #[arg(long = "resume-secret", value_name = "PATH=VALUE", requires = "resume")]
+    resume_secrets: Vec<String>,
+
+    /// Restrict filesystem reads to this root (repeatable; write roots stay readable)
+    #[arg(long = "allow-read", value_name = "PATH")]
+    allow_read: Vec<PathBuf>,
+
+    /// Restrict filesystem writes to this root (repeatable)
+    #[arg(long = "allow-write", value_name = "PATH")]
+    allow_write: Vec<PathBuf>,
+
+    /// Reject paths that leave the allowed roots through a symlink
+    #[arg(long = "deny-symlink-escapes", action = ArgAction::SetTrue)]
+    deny_symlink_escapes: bool,
 }
 
 fn main() {
@@ -234,29 +264,43 @@ fn run() -> Result<()> {
         run_resolver_pipeline(&registry, &compose_dir, &lock_path)?;
     }
 
-    let initial_state = load_input_state(opts.input)?;
+    let initial_state = load_input_state(opts.input.clone())?;
     let manifest_metadata = load_manifest_metadata(compose_path);
     let (state_map, wrapped_input) = ensure_object_state(initial_state);
     if wrapped_input {
         eprintln!("warning: input payload is not an object; wrapping under {{\"input\": ...}}");
     }
     let sanitized_state = sanitize_input_state(state_map, manifest_metadata.as_ref());
-    let compose_steps = load_compose(compose_path)?;
+    let document = load_compose_document(compose_path)?;
+    document.register_imports(&registry);
+    let compose_steps = document.steps;
+    let signature = document.signature;
 
+    registry.set_fs_policy(load_fs_policy(&opts, &compose_dir));
     let mut ctx = registry.context_with_cancellation(cancellation.clone());
+    if let Some(session) =
+        open_checkpoint_session(&opts, &compose_dir, &compose_holder, &compose_steps)?
+    {
+        ctx.set_checkpoint_session(Some(session));
+    }
 
     let state = Value::Object(sanitized_state);
 
-    let result = match run_compose(&mut ctx, &compose_steps, state) {
-        Ok(value) => value,
-        Err(err) if err.is::<CancelledError>() => {
-            eprintln!("Execution cancelled");
-            std::process::exit(130);
-        }
-        Err(err) => return Err(err.context("Compose execution failed")),
-    };
+    let result =
+        match run_compose_with_signature(&mut ctx, &compose_steps, signature.as_ref(), state) {
+            Ok(value) => value,
+            Err(err) if err.is::<CancelledError>() => {
+                eprintln!("Execution cancelled");
+                std::process::exit(130);
+            }
+            Err(err) => {
+                let message = ctx.secrets().redact_str(&format!("{err:#}"));
+                return Err(anyhow!(message).context("Compose execution failed"));
+            }
+        };
 
-    let projected = project_outputs(result, manifest_metadata.as_ref());
+    let mut projected = project_outputs(result, manifest_metadata.as_ref());
+    ctx.secrets().redact_in_place(&mut projected);
     println!("{}", serde_json::to_string_pretty(&projected)?);
     Ok(())
 }
@@ -961,6 +1005,69 @@ fn determine_cache_dir(
     Ok(compose_dir.join(".lcod").join("cache"))
 }
 
+fn open_checkpoint_session(
+    opts: &CliOptions,
+    compose_dir: &Path,
+    compose_holder: &ComposeHandle,
+    steps: &[Step],
+) -> Result<Option<CheckpointSession>> {
+    if !opts.checkpoint && opts.resume.is_none() {
+        return Ok(None);
+    }
+    let dir = match &opts.checkpoint_dir {
+        Some(explicit) => explicit.clone(),
+        None if compose_holder.is_temporary() => env::current_dir()
+            .context("Unable to locate current directory for checkpoints")?
+            .join(".lcod")
+            .join("checkpoints"),
+        None => compose_dir.join(".lcod").join("checkpoints"),
+    };
+    let fingerprint = compose_fingerprint(steps)?;
+    let session = match &opts.resume {
+        Some(run_id) => {
+            let secrets = resume_secret_values(opts)?;
+            let session =
+                CheckpointSession::resume_with_secrets(&dir, run_id, &fingerprint, &secrets)?;
+            let checkpoint = session.checkpoint();
+            if !checkpoint.live_handles.is_empty() {
+                eprintln!(
+                    "warning: checkpoint holds process-local handles that cannot be restored: {}",
+                    checkpoint.live_handles.join(", ")
+                );
+            }
+            eprintln!("Resuming run {run_id} from step {}", checkpoint.next_step);
+            session
+        }
+        None => {
+            let run_id = generate_run_id();
+            let session = CheckpointSession::start(&dir, &run_id, &fingerprint)?;
+            eprintln!("Checkpointing run {run_id} to {}", session.path().display());
+            session
+        }
+    };
+    Ok(Some(session))
+}
+
+fn resume_secret_values(opts: &CliOptions) -> Result<Map<String, Value>> {
+    opts.resume_secrets
+        .iter()
+        .map(|entry| {
+            let (path, value) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow!("--resume-secret expects PATH=VALUE, got `{entry}`"))?;
+            Ok((path.to_string(), Value::String(value.to_string())))
+        })
+        .collect()
+}
+
+fn generate_run_id() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    format!("run-{millis}-{}", std::process::id())
+}
+
 fn load_input_state(source: Option<String>) -> Result<Value> {
     let payload = match source {
         None => Value::Object(Default::default()),
@@ -1165,7 +1272,8 @@ fn candidate_spec_paths() -> Vec<PathBuf> {
 
 fn run_resolver_pipeline(registry: &Registry, project_path: &Path, lock_path: &Path) -> Result<()> {
     let compose_path = resolver_compose_path()?;
-    let steps = load_compose(&compose_path)?;
+    let document = load_compose_document(&compose_path)?;
+    document.register_imports(registry);
     let mut ctx = registry.context();
     let state = json!({
         "projectPath": lcod_kernel_rs::core::path::path_to_string(project_path),
@@ -1173,8 +1281,13 @@ fn run_resolver_pipeline(registry: &Registry, project_path: &Path, lock_path: &P
         "outputPath": lcod_kernel_rs::core::path::path_to_string(lock_path),
     });
 
-    let result = run_compose(&mut ctx, &steps, state)
-        .with_context(|| "Resolver pipeline execution failed")?;
+    let result = run_compose_with_signature(
+        &mut ctx,
+        &document.steps,
+        document.signature.as_ref(),
+        state,
+    )
+    .with_context(|| "Resolver pipeline execution failed")?;
 
     if let Some(warnings) = result.get("warnings").and_then(Value::as_array) {
         if !warnings.is_empty() {
@@ -1286,184 +1399,6 @@ fn add_resolver_candidates(buffer: &mut Vec<PathBuf>, root: &Path) {
     );
 }
 
-fn load_compose(path: &Path) -> Result<Vec<Step>> {
-    let text = fs::read_to_string(path)
-        .with_context(|| format!("unable to read compose file {}", path.display()))?;
-    let raw: Value = if path
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .map(|ext| matches!(ext.to_ascii_lowercase().as_str(), "yaml" | "yml"))
-        .unwrap_or(false)
-    {
-        serde_yaml::from_str(&text)
-            .with_context(|| format!("invalid compose YAML {}", path.display()))?
-    } else {
-        serde_json::from_str(&text)
-            .with_context(|| format!("invalid compose JSON {}", path.display()))?
-    };
-
-    let compose_value = match raw {
-        Value::Object(mut map) => map
-            .remove("compose")
-            .ok_or_else(|| anyhow!("compose root missing in {}", path.display()))?,
-        Value::Array(array) => Value::Array(array),
-        _ => {
-            return Err(anyhow!(
-                "compose document must be an array or object with compose root"
-            ))
-        }
-    };
-
-    let mut canonical = compose_value;
-    if let Some(context) = load_manifest_context(path.parent().unwrap_or(Path::new(".")))? {
-        canonicalize_value(&mut canonical, &context);
-    }
-
-    parse_compose(&canonical)
-        .with_context(|| format!("invalid compose structure in {}", path.display()))
-}
-
-#[derive(Clone, Debug)]
-struct ManifestContext {
-    base_path: Option<String>,
-    version: Option<String>,
-    alias_map: serde_json::Map<String, Value>,
-}
-
-fn load_manifest_context(dir: &Path) -> Result<Option<ManifestContext>> {
-    let manifest_path = dir.join("lcp.toml");
-    if !manifest_path.exists() {
-        return Ok(None);
-    }
-    let raw = fs::read_to_string(&manifest_path)
-        .with_context(|| format!("unable to read manifest {}", manifest_path.display()))?;
-    let manifest: TomlValue = raw
-        .parse()
-        .with_context(|| format!("invalid manifest TOML {}", manifest_path.display()))?;
-
-    let manifest_id = manifest.get("id").and_then(TomlValue::as_str);
-    let base_path = manifest_id
-        .and_then(|id| id.strip_prefix("lcod://"))
-        .and_then(|rest| rest.split('@').next())
-        .map(|s| s.to_string())
-        .or_else(|| {
-            let ns = manifest
-                .get("namespace")
-                .and_then(TomlValue::as_str)
-                .unwrap_or("");
-            let name = manifest
-                .get("name")
-                .and_then(TomlValue::as_str)
-                .unwrap_or("");
-            let joined = [ns, name]
-                .iter()
-                .filter(|segment| !segment.is_empty())
-                .copied()
-                .collect::<Vec<_>>()
-                .join("/");
-            if joined.is_empty() {
-                None
-            } else {
-                Some(joined)
-            }
-        });
-
-    let version = manifest
-        .get("version")
-        .and_then(TomlValue::as_str)
-        .map(|s| s.to_string())
-        .or_else(|| {
-            manifest_id
-                .and_then(|id| id.split('@').nth(1))
-                .map(|s| s.to_string())
-        });
-
-    let alias_map = manifest
-        .get("workspace")
-        .and_then(TomlValue::as_table)
-        .and_then(|table| table.get("scopeAliases"))
-        .and_then(TomlValue::as_table)
-        .map(|aliases| {
-            aliases
-                .iter()
-                .filter_map(|(key, value)| value.as_str().map(|alias| (key.clone(), alias.into())))
-                .collect::<serde_json::Map<String, Value>>()
-        })
-        .unwrap_or_default();
-
-    Ok(Some(ManifestContext {
-        base_path,
-        version,
-        alias_map,
-    }))
-}
-
-fn canonicalize_value(value: &mut Value, context: &ManifestContext) {
-    match value {
-        Value::Array(items) => {
-            for item in items {
-                canonicalize_value(item, context);
-            }
-        }
-        Value::Object(map) => canonicalize_object(map, context),
-        _ => {}
-    }
-}
-
-fn canonicalize_object(map: &mut serde_json::Map<String, Value>, context: &ManifestContext) {
-    if let Some(Value::String(call)) = map.get_mut("call") {
-        if let Some(canonical) = canonicalize_id(call, context) {
-            *call = canonical;
-        }
-    }
-    if let Some(children) = map.get_mut("children") {
-        canonicalize_value(children, context);
-    }
-    for (_key, value) in map.iter_mut() {
-        canonicalize_value(value, context);
-    }
-}
-
-fn canonicalize_id(raw: &str, context: &ManifestContext) -> Option<String> {
-    if raw.starts_with("lcod://") {
-        return Some(raw.to_string());
-    }
-    let trimmed = raw.trim_start_matches("./");
-    if trimmed.is_empty() {
-        return Some(raw.to_string());
-    }
-    let mut segments = trimmed
-        .split('/')
-        .filter(|segment| !segment.is_empty())
-        .collect::<Vec<_>>();
-    if segments.is_empty() {
-        return Some(raw.to_string());
-    }
-    let alias = segments.remove(0);
-    let mapped = context
-        .alias_map
-        .get(alias)
-        .and_then(Value::as_str)
-        .unwrap_or(alias);
-
-    let mut parts = Vec::new();
-    if let Some(base) = &context.base_path {
-        if !base.is_empty() {
-            parts.push(base.clone());
-        }
-    }
-    if !mapped.is_empty() {
-        parts.push(mapped.to_string());
-    }
-    for segment in segments {
-        parts.push(segment.to_string());
-    }
-
-    let version = context.version.as_deref().unwrap_or("0.0.0");
-    let id = format!("lcod://{}@{}", parts.join("/"), version);
-    Some(id)
-}
-
 fn load_manifest_metadata(compose_path: &Path) -> Option<ManifestMetadata> {
     let manifest_path = compose_path.parent()?.join("lcp.toml");
     let raw = fs::read_to_string(&manifest_path).ok()?;
@@ -1477,6 +1412,28 @@ fn load_manifest_metadata(compose_path: &Path) -> Option<ManifestMetadata> {
     }
 }
 
+/// Combines the `[sandbox.fs]` table of the compose's `lcp.toml` with the
+/// `--allow-read`/`--allow-write`/`--deny-symlink-escapes` flags.
+fn load_fs_policy(opts: &CliOptions, compose_dir: &Path) -> FsPolicy {
+    let manifest = fs::read_to_string(compose_dir.join("lcp.toml"))
+        .ok()
+        .and_then(|raw| raw.parse::<TomlValue>().ok());
+    let mut policy = manifest
+        .as_ref()
+        .and_then(|value| FsPolicy::from_manifest(value, compose_dir))
+        .unwrap_or_default();
+    for root in &opts.allow_read {
+        policy = policy.allow_read(root);
+    }
+    for root in &opts.allow_write {
+        policy = policy.allow_write(root);
+    }
+    if opts.deny_symlink_escapes {
+        policy = policy.deny_symlink_escapes(true);
+    }
+    policy
+}
+
 fn extract_manifest_keys(section: Option<&TomlValue>) -> Vec<String> {
     section
         .and_then(TomlValue::as_table)
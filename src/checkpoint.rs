@@ -0,0 +1,419 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context as AnyhowContext, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::compose::Step;
+use crate::registry::Context;
+use crate::secrets::SecretStore;
+use crate::tooling::log_kernel_warn;
+
+const FOREACH_CONTRACT_ID: &str = "lcod://flow/foreach@1";
+/// Prefix of redacted paths pointing into `foreach.results`.
+const FOREACH_RESULTS_PATH: &str = "$foreach";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForeachCheckpoint {
+    pub step: usize,
+    pub next_index: usize,
+    pub results: Vec<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+    pub run_id: String,
+    pub compose_hash: String,
+    pub next_step: usize,
+    pub state: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub foreach: Option<ForeachCheckpoint>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub live_handles: Vec<String>,
+    /// Paths (`$.key`, or `$foreach.<index>` for foreach results) whose
+    /// values held registered secrets and were masked before saving.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redacted: Vec<String>,
+    #[serde(default)]
+    pub completed: bool,
+}
+
+/// Persists the top-level state of a compose run so that it can be resumed
+/// after the process dies. Attach it with `Context::set_checkpoint_session`.
+pub struct CheckpointSession {
+    path: PathBuf,
+    checkpoint: Checkpoint,
+    resumed: bool,
+    active_step: Option<(usize, String)>,
+    reported_handles: Vec<String>,
+    /// Values supplied for redacted paths, registered as secrets on resume.
+    restored_secrets: Vec<String>,
+}
+
+impl CheckpointSession {
+    pub fn start(dir: &Path, run_id: &str, compose_hash: &str) -> Result<Self> {
+        let path = checkpoint_path(dir, run_id)?;
+        if path.exists() {
+            return Err(anyhow!(
+                "checkpoint for run '{run_id}' already exists at {}; resume it instead",
+                path.display()
+            ));
+        }
+        fs::create_dir_all(dir)
+            .with_context(|| format!("unable to create checkpoint directory {}", dir.display()))?;
+        let session = Self {
+            path,
+            checkpoint: Checkpoint {
+                run_id: run_id.to_string(),
+                compose_hash: compose_hash.to_string(),
+                next_step: 0,
+                state: Map::new(),
+                foreach: None,
+                live_handles: Vec::new(),
+                redacted: Vec::new(),
+                completed: false,
+            },
+            resumed: false,
+            active_step: None,
+            reported_handles: Vec::new(),
+            restored_secrets: Vec::new(),
+        };
+        // Written up front so that a run dying in its first step can still be
+        // resumed (from the start).
+        session.persist()?;
+        Ok(session)
+    }
+
+    pub fn resume(dir: &Path, run_id: &str, compose_hash: &str) -> Result<Self> {
+        Self::resume_with_secrets(dir, run_id, compose_hash, &Map::new())
+    }
+
+    /// Resumes a run whose checkpoint masked secrets: `secrets` maps every
+    /// path listed in `Checkpoint::redacted` to the original value, and the
+    /// run is refused while any of them is missing.
+    pub fn resume_with_secrets(
+        dir: &Path,
+        run_id: &str,
+        compose_hash: &str,
+        secrets: &Map<String, Value>,
+    ) -> Result<Self> {
+        let path = checkpoint_path(dir, run_id)?;
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("no checkpoint found for run '{run_id}'"))?;
+        let mut checkpoint: Checkpoint = serde_json::from_str(&text)
+            .with_context(|| format!("invalid checkpoint file {}", path.display()))?;
+        if checkpoint.compose_hash != compose_hash {
+            return Err(anyhow!(
+                "checkpoint for run '{run_id}' was recorded for a different compose"
+            ));
+        }
+        let missing: Vec<&str> = checkpoint
+            .redacted
+            .iter()
+            .filter(|path| !secrets.contains_key(path.as_str()))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            return Err(anyhow!(
+                "checkpoint for run '{run_id}' masked secret values at {}; supply them again to resume",
+                missing.join(", ")
+            ));
+        }
+        let mut restored_secrets = Vec::new();
+        for redacted in std::mem::take(&mut checkpoint.redacted) {
+            let value = secrets[&redacted].clone();
+            restored_secrets.extend(value.as_str().map(str::to_string));
+            checkpoint.restore(&redacted, value)?;
+        }
+        Ok(Self {
+            path,
+            checkpoint,
+            resumed: true,
+            active_step: None,
+            reported_handles: Vec::new(),
+            restored_secrets,
+        })
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.checkpoint.run_id
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn checkpoint(&self) -> &Checkpoint {
+        &self.checkpoint
+    }
+
+    /// Returns the step index and state to restart from when resuming.
+    pub(crate) fn take_resume_point(&mut self) -> Option<(usize, Map<String, Value>)> {
+        if !self.resumed {
+            return None;
+        }
+        self.resumed = false;
+        Some((self.checkpoint.next_step, self.checkpoint.state.clone()))
+    }
+
+    pub(crate) fn take_restored_secrets(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.restored_secrets)
+    }
+
+    pub(crate) fn begin_step(&mut self, index: usize, call: &str) {
+        self.active_step = Some((index, call.to_string()));
+    }
+
+    pub(crate) fn complete_step(
+        &mut self,
+        next_step: usize,
+        state: Map<String, Value>,
+        live_handles: Vec<String>,
+        redacted: Vec<String>,
+    ) -> Result<()> {
+        self.active_step = None;
+        self.checkpoint.next_step = next_step;
+        self.checkpoint.state = state;
+        self.checkpoint.foreach = None;
+        self.checkpoint.live_handles = live_handles;
+        self.checkpoint.redacted = redacted;
+        self.persist()
+    }
+
+    pub(crate) fn complete_run(&mut self) -> Result<()> {
+        self.checkpoint.completed = true;
+        self.persist()
+    }
+
+    fn active_foreach_step(&self) -> Option<usize> {
+        match &self.active_step {
+            Some((index, call)) if call == FOREACH_CONTRACT_ID => Some(*index),
+            _ => None,
+        }
+    }
+
+    fn persist(&self) -> Result<()> {
+        let bytes = serde_json::to_vec_pretty(&self.checkpoint)?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        {
+            let mut file = File::create(&tmp_path)
+                .with_context(|| format!("unable to write checkpoint {}", tmp_path.display()))?;
+            file.write_all(&bytes)?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("unable to write checkpoint {}", self.path.display()))
+    }
+}
+
+impl Checkpoint {
+    /// Puts `value` back at a path recorded in `redacted`.
+    fn restore(&mut self, path: &str, value: Value) -> Result<()> {
+        let missing = || anyhow!("redacted checkpoint path {path} no longer exists");
+        let (in_results, rest) = match path.strip_prefix(FOREACH_RESULTS_PATH) {
+            Some(rest) => (true, rest),
+            None => (false, path.strip_prefix('$').unwrap_or(path)),
+        };
+        let mut segments = rest.split('.').skip(1);
+        let first = segments.next().ok_or_else(missing)?;
+        let mut slot = if in_results {
+            let index = first.parse::<usize>().ok();
+            self.foreach
+                .as_mut()
+                .zip(index)
+                .and_then(|(progress, index)| progress.results.get_mut(index))
+        } else {
+            self.state.get_mut(first)
+        };
+        for segment in segments {
+            slot = match slot {
+                Some(Value::Object(map)) => map.get_mut(segment),
+                Some(Value::Array(items)) => segment
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| items.get_mut(index)),
+                _ => None,
+            };
+        }
+        *slot.ok_or_else(missing)? = value;
+        Ok(())
+    }
+}
+
+fn checkpoint_path(dir: &Path, run_id: &str) -> Result<PathBuf> {
+    let valid = !run_id.is_empty()
+        && run_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !run_id.starts_with('.');
+    if !valid {
+        return Err(anyhow!("invalid checkpoint run id: {run_id}"));
+    }
+    Ok(dir.join(format!("{run_id}.json")))
+}
+
+/// Stable fingerprint of a compose, used to refuse resuming a run against a
+/// different document.
+pub fn compose_fingerprint(steps: &[Step]) -> Result<String> {
+    let value = serde_json::to_value(steps)?;
+    let bytes = serde_json::to_vec(&value)?;
+    Ok(hex::encode(Sha256::digest(&bytes)))
+}
+
+/// Lists the state paths holding stream or HTTP host handles. Those only live
+/// in the current process and cannot be restored from a checkpoint.
+pub fn find_live_handles(ctx: &Context, state: &Map<String, Value>) -> Vec<String> {
+    let mut found = Vec::new();
+    for (key, value) in state {
+        collect_live_handles(ctx, value, format!("$.{key}"), &mut found);
+    }
+    found
+}
+
+fn collect_live_handles(ctx: &Context, value: &Value, path: String, found: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            if ctx.streams().contains_handle(value) || ctx.has_http_host(value) {
+                found.push(path);
+                return;
+            }
+            for (key, child) in map {
+                collect_live_handles(ctx, child, format!("{path}.{key}"), found);
+            }
+        }
+        Value::Array(items) => {
+            for (index, child) in items.iter().enumerate() {
+                collect_live_handles(ctx, child, format!("{path}.{index}"), found);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn report_live_handles(ctx: &mut Context, handles: &[String]) {
+    let Some(session) = ctx.checkpoint_session_mut() else {
+        return;
+    };
+    let fresh: Vec<String> = handles
+        .iter()
+        .filter(|path| !session.reported_handles.contains(path))
+        .cloned()
+        .collect();
+    if fresh.is_empty() {
+        return;
+    }
+    session.reported_handles.extend(fresh.iter().cloned());
+    let run_id = session.run_id().to_string();
+    let _ = log_kernel_warn(
+        Some(ctx),
+        "checkpoint.live_handles",
+        Some(json!({ "runId": run_id, "paths": fresh })),
+        Some(json!({ "logger": "kernel.checkpoint" })),
+    );
+}
+
+/// Checkpoints end up on disk, so registered secrets are masked first; the
+/// path of every masked value is recorded so `resume` can ask for it back
+/// instead of continuing with the redaction marker.
+fn redact_into(secrets: &SecretStore, value: &mut Value, path: String, masked: &mut Vec<String>) {
+    match value {
+        Value::String(text) => {
+            let redacted = secrets.redact_str(text);
+            if redacted != *text {
+                *text = redacted;
+                masked.push(path);
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                redact_into(secrets, item, format!("{path}.{index}"), masked);
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                redact_into(secrets, item, format!("{path}.{key}"), masked);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn record_step(
+    ctx: &mut Context,
+    next_step: usize,
+    state: &Map<String, Value>,
+) -> Result<()> {
+    let handles = find_live_handles(ctx, state);
+    if !handles.is_empty() {
+        report_live_handles(ctx, &handles);
+    }
+    let mut state = state.clone();
+    let mut redacted = Vec::new();
+    if !ctx.secrets().is_empty() {
+        for (key, value) in state.iter_mut() {
+            redact_into(ctx.secrets(), value, format!("$.{key}"), &mut redacted);
+        }
+    }
+    match ctx.checkpoint_session_mut() {
+        Some(session) => session.complete_step(next_step, state, handles, redacted),
+        None => Ok(()),
+    }
+}
+
+fn owns_foreach(ctx: &Context) -> bool {
+    ctx.scope_depth() == 1
+}
+
+/// Progress of the foreach running as the active top-level step, restored
+/// from a checkpoint (`next_index`, collected results).
+pub(crate) fn take_foreach_resume(ctx: &mut Context) -> Option<(usize, Vec<Value>)> {
+    if !owns_foreach(ctx) {
+        return None;
+    }
+    let session = ctx.checkpoint_session_mut()?;
+    let step = session.active_foreach_step()?;
+    match session.checkpoint.foreach.take() {
+        Some(progress) if progress.step == step => Some((progress.next_index, progress.results)),
+        _ => None,
+    }
+}
+
+pub(crate) fn record_foreach_iteration(
+    ctx: &mut Context,
+    next_index: usize,
+    results: &[Value],
+) -> Result<()> {
+    if !owns_foreach(ctx) {
+        return Ok(());
+    }
+    let mut results = results.to_vec();
+    let mut masked = Vec::new();
+    if !ctx.secrets().is_empty() {
+        for (index, result) in results.iter_mut().enumerate() {
+            let path = format!("{FOREACH_RESULTS_PATH}.{index}");
+            redact_into(ctx.secrets(), result, path, &mut masked);
+        }
+    }
+    let Some(session) = ctx.checkpoint_session_mut() else {
+        return Ok(());
+    };
+    let Some(step) = session.active_foreach_step() else {
+        return Ok(());
+    };
+    session.checkpoint.foreach = Some(ForeachCheckpoint {
+        step,
+        next_index,
+        results,
+    });
+    session
+        .checkpoint
+        .redacted
+        .retain(|path| !path.starts_with(FOREACH_RESULTS_PATH));
+    session.checkpoint.redacted.extend(masked);
+    session.persist()
+}
@@ -1,16 +1,16 @@
 use std::collections::HashMap;
-use std::env;
-use std::fs;
-use std::path::PathBuf;
-use std::sync::OnceLock;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Instant;
 
-use anyhow::{anyhow, Context as AnyhowContext, Result};
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Map, Number, Value};
+use serde_json::{Map, Number, Value};
+use sha2::{Digest, Sha256};
 
-use crate::registry::{Context, Registry, SlotExecutor};
-use crate::tooling::{log_kernel_error, log_kernel_info, register_tooling};
+use crate::checkpoint;
+use crate::compose_signature::ComposeSignature;
+use crate::registry::{Context, SlotExecutor};
+use crate::tooling::{log_kernel_error, log_kernel_info};
 
 const SPREAD_KEY: &str = "__lcod_spreads__";
 const OPTIONAL_FLAG: &str = "__lcod_optional__";
@@ -18,6 +18,9 @@ const STATE_SENTINEL: &str = "__lcod_state__";
 const RESULT_SENTINEL: &str = "__lcod_result__";
 const SCRIPT_CONTRACT_ID: &str = "lcod://tooling/script@1";
 pub const RAW_INPUT_KEY: &str = "__lcod_input__";
+const PARSED_COMPOSE_CACHE_LIMIT: usize = 256;
+
+static PARSED_COMPOSE_CACHE: OnceLock<Mutex<HashMap<String, Arc<Vec<Step>>>>> = OnceLock::new();
 
 #[derive(Copy, Clone)]
 enum MappingKind {
@@ -47,286 +50,80 @@ pub enum StepChildren {
     Map(HashMap<String, Vec<Step>>),
 }
 
-fn merge_map_with_fallback(target: &mut Map<String, Value>, source: &Map<String, Value>) {
-    for (key, source_value) in source {
-        match target.get_mut(key) {
-            Some(target_value) => {
-                if let Value::Object(target_obj) = target_value {
-                    if let Value::Object(source_obj) = source_value {
-                        merge_map_with_fallback(target_obj, source_obj);
-                        continue;
-                    }
-                }
-                if target_value.is_null() && !source_value.is_null() {
-                    *target_value = source_value.clone();
-                }
-            }
-            None => {
-                if !source_value.is_null() {
-                    target.insert(key.clone(), source_value.clone());
-                }
-            }
-        }
-    }
+fn suffix_path(suffix: &str) -> String {
+    format!("$.{}", suffix.trim_start_matches('.'))
 }
 
-fn merge_step_with_fallback(normalized: &mut Step, fallback: &Step) {
-    merge_map_with_fallback(&mut normalized.inputs, &fallback.inputs);
-    merge_map_with_fallback(&mut normalized.out, &fallback.out);
-
-    match (&mut normalized.children, &fallback.children) {
-        (Some(StepChildren::List(target_steps)), Some(StepChildren::List(source_steps))) => {
-            for (target, source) in target_steps.iter_mut().zip(source_steps.iter()) {
-                merge_step_with_fallback(target, source);
-            }
-        }
-        (Some(StepChildren::Map(target_map)), Some(StepChildren::Map(source_map))) => {
-            for (slot, target_steps) in target_map.iter_mut() {
-                if let Some(source_steps) = source_map.get(slot) {
-                    for (target, source) in target_steps.iter_mut().zip(source_steps.iter()) {
-                        merge_step_with_fallback(target, source);
-                    }
-                }
-            }
-        }
-        _ => {}
-    }
-    match (&mut normalized.slots, &fallback.slots) {
-        (Some(StepChildren::List(target_steps)), Some(StepChildren::List(source_steps))) => {
-            for (target, source) in target_steps.iter_mut().zip(source_steps.iter()) {
-                merge_step_with_fallback(target, source);
-            }
-        }
-        (Some(StepChildren::Map(target_map)), Some(StepChildren::Map(source_map))) => {
-            for (slot, target_steps) in target_map.iter_mut() {
-                if let Some(source_steps) = source_map.get(slot) {
-                    for (target, source) in target_steps.iter_mut().zip(source_steps.iter()) {
-                        merge_step_with_fallback(target, source);
-                    }
-                }
-            }
-        }
-        _ => {}
-    }
-}
-
-fn default_suffix_path(suffix: &str) -> String {
-    let trimmed = suffix.trim_start_matches('.');
-    if trimmed.is_empty() {
-        String::new()
-    } else {
-        format!("$.{trimmed}")
+fn normalize_spread_source(raw: &Value, kind: MappingKind) -> Value {
+    match raw {
+        Value::String(s) if s == "=" => match kind {
+            MappingKind::Input => Value::String(STATE_SENTINEL.to_string()),
+            MappingKind::Output => Value::String(RESULT_SENTINEL.to_string()),
+        },
+        Value::String(s) => Value::String(s.clone()),
+        Value::Object(obj) => obj
+            .get("source")
+            .and_then(Value::as_str)
+            .or_else(|| obj.get("path").and_then(Value::as_str))
+            .map(|s| Value::String(s.to_string()))
+            .unwrap_or_else(|| Value::String("$.".to_string())),
+        _ => Value::String("$.".to_string()),
     }
 }
 
-fn normalize_spread_source(raw: &Value, suffix: Option<&str>, kind: MappingKind) -> Value {
-    match raw {
+fn normalize_spread_entry(entry: &Value, suffix: Option<&str>, kind: MappingKind) -> Option<Value> {
+    let mut descriptor = Map::new();
+    match entry {
+        Value::Null => return None,
         Value::String(s) => {
-            if s == "=" {
-                if let Some(sfx) = suffix.filter(|s| !s.is_empty()) {
-                    Value::String(default_suffix_path(sfx))
-                } else {
-                    match kind {
-                        MappingKind::Input => Value::String(STATE_SENTINEL.to_string()),
-                        MappingKind::Output => Value::String(RESULT_SENTINEL.to_string()),
-                    }
-                }
-            } else {
-                Value::String(s.clone())
-            }
-        }
-        Value::Object(obj) => {
-            if let Some(source) = obj.get("source").or_else(|| obj.get("path")) {
-                return normalize_spread_source(source, suffix, kind);
-            }
-            if let Some(sfx) = suffix.filter(|s| !s.is_empty()) {
-                Value::String(default_suffix_path(sfx))
-            } else {
-                match kind {
-                    MappingKind::Input => Value::String(STATE_SENTINEL.to_string()),
-                    MappingKind::Output => Value::String(RESULT_SENTINEL.to_string()),
-                }
-            }
-        }
-        _ => {
-            if let Some(sfx) = suffix.filter(|s| !s.is_empty()) {
-                Value::String(default_suffix_path(sfx))
-            } else {
-                match kind {
-                    MappingKind::Input => Value::String(STATE_SENTINEL.to_string()),
-                    MappingKind::Output => Value::String(RESULT_SENTINEL.to_string()),
-                }
-            }
+            let source = match suffix {
+                Some(sfx) if s == "=" => Value::String(suffix_path(sfx)),
+                _ => normalize_spread_source(entry, kind),
+            };
+            descriptor.insert("source".to_string(), source);
         }
-    }
-}
-
-fn normalize_spread_entries(raw: &Value, suffix: Option<&str>, kind: MappingKind) -> Vec<Value> {
-    match raw {
-        Value::Array(items) => items
-            .iter()
-            .flat_map(|entry| normalize_spread_entries(entry, suffix, kind))
-            .collect(),
         Value::Object(obj) => {
-            let mut descriptor = Map::new();
-            let source_value = obj
+            let explicit = obj
                 .get("source")
-                .or_else(|| obj.get("path"))
-                .map(|entry| normalize_spread_source(entry, suffix, kind))
-                .unwrap_or_else(|| {
-                    normalize_spread_source(&Value::String("=".to_string()), suffix, kind)
-                });
-            descriptor.insert("source".to_string(), source_value);
+                .filter(|value| !value.is_null())
+                .or_else(|| obj.get("path").filter(|value| !value.is_null()));
+            let source = match (explicit, suffix) {
+                (Some(value), _) => normalize_spread_source(value, kind),
+                (None, Some(sfx)) => Value::String(suffix_path(sfx)),
+                (None, None) => normalize_spread_source(&Value::String("=".to_string()), kind),
+            };
+            descriptor.insert("source".to_string(), source);
             if let Some(optional) = obj.get("optional").and_then(Value::as_bool) {
                 descriptor.insert("optional".to_string(), Value::Bool(optional));
             }
             if let Some(pick) = obj.get("pick").and_then(Value::as_array) {
-                let selections: Vec<Value> = pick
+                let selections = pick
                     .iter()
-                    .filter_map(|item| item.as_str().map(|s| Value::String(s.to_string())))
+                    .map(|item| match item {
+                        Value::String(s) => Value::String(s.clone()),
+                        other => Value::String(other.to_string()),
+                    })
                     .collect();
-                if !selections.is_empty() {
-                    descriptor.insert("pick".to_string(), Value::Array(selections));
-                }
+                descriptor.insert("pick".to_string(), Value::Array(selections));
             }
-            vec![Value::Object(descriptor)]
-        }
-        Value::String(_) | Value::Null | Value::Bool(_) | Value::Number(_) => {
-            let source = normalize_spread_source(raw, suffix, kind);
-            vec![Value::Object(Map::from_iter([(
-                "source".to_string(),
-                source,
-            )]))]
         }
-    }
-}
-
-static NORMALIZER_STEPS: OnceLock<Vec<Step>> = OnceLock::new();
-static NORMALIZER_REGISTRY: OnceLock<Registry> = OnceLock::new();
-
-fn impl_set_passthrough(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
-    Ok(input)
-}
-
-fn normalizer_candidates() -> Vec<PathBuf> {
-    let mut paths = Vec::new();
-    if let Ok(path) = env::var("LCOD_SPEC_PATH") {
-        if !path.is_empty() {
-            paths.push(
-                PathBuf::from(path)
-                    .join("tooling")
-                    .join("compose")
-                    .join("normalize")
-                    .join("compose.yaml"),
-            );
-        }
-    }
-    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    paths.push(
-        manifest_dir
-            .join("..")
-            .join("lcod-spec")
-            .join("tooling")
-            .join("compose")
-            .join("normalize")
-            .join("compose.yaml"),
-    );
-    paths.push(
-        manifest_dir
-            .join("resources")
-            .join("normalize")
-            .join("compose.yaml"),
-    );
-    paths
-}
-
-fn normalizer_compose_path() -> PathBuf {
-    let candidates = normalizer_candidates();
-    for candidate in &candidates {
-        if candidate.is_file() {
-            return candidate.clone();
+        _ => {
+            descriptor.insert("source".to_string(), normalize_spread_source(entry, kind));
         }
     }
-    candidates
-        .last()
-        .cloned()
-        .unwrap_or_else(|| PathBuf::from("resources/normalize/compose.yaml"))
+    Some(Value::Object(descriptor))
 }
 
-fn load_normalizer_steps() -> Result<&'static Vec<Step>> {
-    if let Some(steps) = NORMALIZER_STEPS.get() {
-        return Ok(steps);
-    }
-    let compose_path = normalizer_compose_path();
-    let text = fs::read_to_string(&compose_path).with_context(|| {
-        format!(
-            "unable to read compose normalizer: {}",
-            compose_path.display()
-        )
-    })?;
-    let doc: Value = serde_yaml::from_str(&text).with_context(|| {
-        format!(
-            "invalid YAML in compose normalizer: {}",
-            compose_path.display()
-        )
-    })?;
-    let compose_value = doc
-        .get("compose")
-        .cloned()
-        .ok_or_else(|| anyhow!("compose section missing in {}", compose_path.display()))?;
-    let steps: Vec<Step> = serde_json::from_value(compose_value).with_context(|| {
-        format!(
-            "compose normalizer has invalid structure: {}",
-            compose_path.display()
-        )
-    })?;
-    let _ = NORMALIZER_STEPS.set(steps);
-    NORMALIZER_STEPS
-        .get()
-        .ok_or_else(|| anyhow!("normalizer steps not initialized"))
-}
-
-fn normalizer_registry() -> Result<Registry> {
-    if let Some(registry) = NORMALIZER_REGISTRY.get() {
-        return Ok(registry.clone());
-    }
-    let registry = Registry::new();
-    register_tooling(&registry);
-    registry.register("lcod://impl/set@1", impl_set_passthrough);
-    let _ = NORMALIZER_REGISTRY.set(registry.clone());
-    Ok(NORMALIZER_REGISTRY
-        .get()
-        .cloned()
-        .ok_or_else(|| anyhow!("normalizer registry not initialized"))?)
-}
-
-fn normalize_via_component(compose: &Value) -> Result<Value> {
-    if !compose.is_array() {
-        return Ok(compose.clone());
-    }
-    let steps = load_normalizer_steps()?;
-    let registry = normalizer_registry()?;
-    let mut ctx = registry.context();
-    let initial_state = json!({ "compose": compose.clone() });
-    let result = run_compose(&mut ctx, steps.as_slice(), initial_state)?;
-    let normalized = result
-        .as_object()
-        .and_then(|map| map.get("compose"))
-        .cloned()
-        .ok_or_else(|| anyhow!("compose normalizer did not produce a compose output"))?;
-    let arr = normalized
-        .as_array()
-        .ok_or_else(|| anyhow!("compose normalizer returned a non-array compose output"))?;
-    let original_len = compose
-        .as_array()
-        .map(|items| items.len())
-        .unwrap_or_default();
-    if original_len > 0 && arr.is_empty() {
-        return Err(anyhow!(
-            "compose normalizer returned an empty compose for a non-empty input"
-        ));
+fn normalize_spread_entries(raw: &Value, suffix: Option<&str>, kind: MappingKind) -> Vec<Value> {
+    match raw {
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|entry| normalize_spread_entry(entry, suffix, kind))
+            .collect(),
+        other => normalize_spread_entry(other, suffix, kind)
+            .into_iter()
+            .collect(),
     }
-    Ok(normalized)
 }
 
 fn normalize_value(value: &Value, key: &str, kind: MappingKind, depth: usize) -> Value {
@@ -728,6 +525,14 @@ impl SlotExecutor for ComposeSlotHandler {
     fn into_fallback(self: Box<Self>) -> Option<Box<dyn SlotExecutor + 'static>> {
         self.fallback
     }
+
+    fn fork(&self) -> Option<Box<dyn SlotExecutor + 'static>> {
+        Some(Box::new(Self {
+            slots: self.slots.clone(),
+            parent_state: self.parent_state.clone(),
+            fallback: self.fallback.as_ref().and_then(|fallback| fallback.fork()),
+        }))
+    }
 }
 
 fn apply_outputs(state: &mut Map<String, Value>, mappings: &Map<String, Value>, output: &Value) {
@@ -926,11 +731,27 @@ fn log_step_error(ctx: &mut Context, step: &Step, payload: Value) {
 fn run_steps(
     ctx: &mut Context,
     steps: &[Step],
+    state: Map<String, Value>,
+    slot: &Map<String, Value>,
+) -> Result<Map<String, Value>> {
+    run_steps_from(ctx, steps, 0, state, slot, false)
+}
+
+fn run_steps_from(
+    ctx: &mut Context,
+    steps: &[Step],
+    start: usize,
     mut state: Map<String, Value>,
     slot: &Map<String, Value>,
+    checkpointed: bool,
 ) -> Result<Map<String, Value>> {
-    for (index, step) in steps.iter().enumerate() {
+    for (index, step) in steps.iter().enumerate().skip(start) {
         ctx.ensure_not_cancelled()?;
+        if checkpointed {
+            if let Some(session) = ctx.checkpoint_session_mut() {
+                session.begin_step(index, &step.call);
+            }
+        }
         if step.call == SCRIPT_CONTRACT_ID {
             // no-op: retained escalation point for future diagnostics
         }
@@ -1015,6 +836,9 @@ fn run_steps(
                     step,
                     compose_step_success_data(index, duration_ms, &output),
                 );
+                if checkpointed {
+                    checkpoint::record_step(ctx, index + 1, &state)?;
+                }
             }
             Err(err) => {
                 log_step_error(ctx, step, compose_step_error_data(index, duration_ms, &err));
@@ -1032,21 +856,80 @@ pub fn run_compose(ctx: &mut Context, steps: &[Step], initial_state: Value) -> R
         _ => Map::new(),
     };
     state_map.insert(RAW_INPUT_KEY.to_string(), initial_state);
-    let final_state = run_steps(ctx, steps, state_map, &Map::new())?;
+    let checkpointed = ctx.scope_depth() == 0 && ctx.checkpoint_session_mut().is_some();
+    let mut start = 0;
+    if let Some((next_step, saved_state)) = ctx
+        .checkpoint_session_mut()
+        .filter(|_| checkpointed)
+        .and_then(|session| session.take_resume_point())
+    {
+        start = next_step;
+        state_map = saved_state;
+        let restored = ctx
+            .checkpoint_session_mut()
+            .map(|session| session.take_restored_secrets())
+            .unwrap_or_default();
+        for secret in restored {
+            ctx.register_secret(&secret);
+        }
+    }
+    let final_state = run_steps_from(ctx, steps, start, state_map, &Map::new(), checkpointed)?;
+    if checkpointed {
+        if let Some(session) = ctx.checkpoint_session_mut() {
+            session.complete_run()?;
+        }
+    }
     Ok(Value::Object(final_state))
 }
 
-pub fn parse_compose(value: &Value) -> Result<Vec<Step>> {
-    if let Ok(normalized_value) = normalize_via_component(value) {
-        if let Ok(mut steps) = serde_json::from_value::<Vec<Step>>(normalized_value.clone()) {
-            if let Ok(fallback_steps) = serde_json::from_value::<Vec<Step>>(value.clone()) {
-                for (normalized, fallback) in steps.iter_mut().zip(fallback_steps.iter()) {
-                    merge_step_with_fallback(normalized, fallback);
-                }
-            }
-            return Ok(steps);
+/// Runs a compose after validating its initial state against the declared
+/// header, then checks the declared outputs on the final state.
+pub fn run_compose_with_signature(
+    ctx: &mut Context,
+    steps: &[Step],
+    signature: Option<&ComposeSignature>,
+    initial_state: Value,
+) -> Result<Value> {
+    let Some(signature) = signature else {
+        return run_compose(ctx, steps, initial_state);
+    };
+    let state = signature.apply_inputs(initial_state)?;
+    let result = run_compose(ctx, steps, state)?;
+    signature.check_outputs(&result)?;
+    Ok(result)
+}
+
+fn compose_cache_key(value: &Value) -> Result<String> {
+    let bytes = serde_json::to_vec(value)?;
+    Ok(hex::encode(Sha256::digest(&bytes)))
+}
+
+fn parsed_compose_cache() -> &'static Mutex<HashMap<String, Arc<Vec<Step>>>> {
+    PARSED_COMPOSE_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Normalizes a compose document (`tooling/compose/normalize@1` semantics) and
+/// returns shared steps, reusing previous results for identical content.
+pub fn parse_compose_cached(value: &Value) -> Result<Arc<Vec<Step>>> {
+    let key = compose_cache_key(value)?;
+    if let Some(steps) = parsed_compose_cache()
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(&key).cloned())
+    {
+        return Ok(steps);
+    }
+    let raw: Vec<Step> = serde_json::from_value(value.clone())?;
+    let steps = Arc::new(raw.into_iter().map(normalize_step).collect::<Vec<_>>());
+    if let Ok(mut cache) = parsed_compose_cache().lock() {
+        if cache.len() >= PARSED_COMPOSE_CACHE_LIMIT {
+            cache.clear();
         }
+        cache.insert(key, Arc::clone(&steps));
     }
-    let steps: Vec<Step> = serde_json::from_value(value.clone())?;
-    Ok(steps.into_iter().map(normalize_step).collect())
+    Ok(steps)
+}
+
+pub fn parse_compose(value: &Value) -> Result<Vec<Step>> {
+    parse_compose_cached(value).map(|steps| steps.as_ref().clone())
 }
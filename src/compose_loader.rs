@@ -0,0 +1,406 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context as AnyhowContext, Result};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use toml::Value as TomlValue;
+
+use crate::compose::{parse_compose, run_compose_with_signature, Step};
+use crate::compose_signature::ComposeSignature;
+use crate::registry::{Context, Registry};
+
+/// Package scope used to expand relative component ids (`./helper`,
+/// `alias/name`) into canonical `lcod://` ids.
+#[derive(Debug, Clone, Default)]
+pub struct IdScope {
+    pub base_path: String,
+    pub version: String,
+    pub alias_map: HashMap<String, String>,
+}
+
+impl IdScope {
+    pub fn from_manifest(
+        manifest: &TomlValue,
+        inherited_aliases: &HashMap<String, String>,
+    ) -> Self {
+        let mut alias_map = inherited_aliases.clone();
+        if let Some(package_aliases) = manifest
+            .get("workspace")
+            .and_then(TomlValue::as_table)
+            .and_then(|w| w.get("scopeAliases"))
+            .and_then(TomlValue::as_table)
+        {
+            for (key, value) in package_aliases {
+                if let Some(alias) = value.as_str() {
+                    alias_map.insert(key.to_string(), alias.to_string());
+                }
+            }
+        }
+
+        let manifest_id = manifest.get("id").and_then(TomlValue::as_str);
+        let base_path = manifest_id
+            .and_then(id_path)
+            .map(str::to_string)
+            .unwrap_or_else(|| {
+                let ns = manifest
+                    .get("namespace")
+                    .and_then(TomlValue::as_str)
+                    .unwrap_or("");
+                let name = manifest
+                    .get("name")
+                    .and_then(TomlValue::as_str)
+                    .unwrap_or("");
+                [ns, name]
+                    .iter()
+                    .filter(|segment| !segment.is_empty())
+                    .copied()
+                    .collect::<Vec<_>>()
+                    .join("/")
+            });
+
+        let version = manifest
+            .get("version")
+            .and_then(TomlValue::as_str)
+            .map(str::to_string)
+            .or_else(|| manifest_id.and_then(id_version).map(str::to_string))
+            .unwrap_or_else(|| "0.0.0".to_string());
+
+        Self {
+            base_path,
+            version,
+            alias_map,
+        }
+    }
+
+    /// Loads the scope of the `lcp.toml` sitting in `dir`, if any.
+    pub fn from_manifest_dir(dir: &Path) -> Result<Option<Self>> {
+        let manifest_path = dir.join("lcp.toml");
+        if !manifest_path.is_file() {
+            return Ok(None);
+        }
+        let raw = fs::read_to_string(&manifest_path)
+            .with_context(|| format!("unable to read manifest {}", manifest_path.display()))?;
+        let manifest: TomlValue = raw
+            .parse()
+            .with_context(|| format!("invalid manifest TOML {}", manifest_path.display()))?;
+        Ok(Some(Self::from_manifest(&manifest, &HashMap::new())))
+    }
+
+    pub fn canonicalize_id(&self, raw: &str) -> String {
+        if raw.starts_with("lcod://") {
+            return raw.to_string();
+        }
+        let trimmed = raw.trim_start_matches("./");
+        let segments: Vec<&str> = trimmed.split('/').filter(|s| !s.is_empty()).collect();
+        let Some((alias, rest)) = segments.split_first() else {
+            return raw.to_string();
+        };
+        let mapped = self
+            .alias_map
+            .get(*alias)
+            .map(String::as_str)
+            .unwrap_or(alias);
+        let mut parts = Vec::new();
+        if !self.base_path.is_empty() {
+            parts.push(self.base_path.as_str());
+        }
+        if !mapped.is_empty() {
+            parts.push(mapped);
+        }
+        parts.extend(rest.iter().copied());
+        if parts.is_empty() {
+            return raw.to_string();
+        }
+        let version = if self.version.is_empty() {
+            "0.0.0"
+        } else {
+            self.version.as_str()
+        };
+        format!("lcod://{}@{}", parts.join("/"), version)
+    }
+}
+
+/// Rewrites the `call` ids of a compose: `imports:` aliases first, then the
+/// package scope for relative ids. Canonical ids are left untouched.
+#[derive(Debug, Clone, Default)]
+pub struct IdResolver {
+    scope: Option<IdScope>,
+    imports: HashMap<String, String>,
+}
+
+impl IdResolver {
+    pub fn new(scope: Option<IdScope>) -> Self {
+        Self {
+            scope,
+            imports: HashMap::new(),
+        }
+    }
+
+    pub fn with_import(mut self, alias: impl Into<String>, id: impl Into<String>) -> Self {
+        self.imports.insert(alias.into(), id.into());
+        self
+    }
+
+    pub fn canonicalize_id(&self, raw: &str) -> String {
+        if raw.starts_with("lcod://") {
+            return raw.to_string();
+        }
+        if let Some(id) = self.imports.get(raw) {
+            return id.clone();
+        }
+        match &self.scope {
+            Some(scope) => scope.canonicalize_id(raw),
+            None => raw.to_string(),
+        }
+    }
+
+    pub fn canonicalize_value(&self, value: &mut Value) {
+        match value {
+            Value::Array(items) => {
+                for item in items {
+                    self.canonicalize_value(item);
+                }
+            }
+            Value::Object(map) => {
+                if let Some(Value::String(call)) = map.get_mut("call") {
+                    *call = self.canonicalize_id(call);
+                }
+                for (key, val) in map.iter_mut() {
+                    if key != "call" {
+                        self.canonicalize_value(val);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A compose file imported by path; registered as a component under `id`.
+#[derive(Debug, Clone)]
+pub struct ImportedCompose {
+    pub id: String,
+    pub path: PathBuf,
+    pub steps: Arc<Vec<Step>>,
+    pub signature: Option<ComposeSignature>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ComposeDocument {
+    pub steps: Vec<Step>,
+    pub signature: Option<ComposeSignature>,
+    pub imports: Vec<ImportedCompose>,
+}
+
+impl ComposeDocument {
+    /// Registers the composes pulled in through `imports:` so that the
+    /// aliased calls resolve.
+    pub fn register_imports(&self, registry: &Registry) {
+        for imported in &self.imports {
+            let steps = Arc::clone(&imported.steps);
+            let signature = imported.signature.clone();
+            registry.register(
+                imported.id.clone(),
+                move |ctx: &mut Context, input: Value, _meta: Option<Value>| {
+                    run_compose_with_signature(ctx, &steps, signature.as_ref(), input)
+                },
+            );
+        }
+    }
+
+    /// Runs `body` with the imports registered on the context's registry and
+    /// unregisters them afterwards, restoring any entries they shadowed.
+    pub fn with_imports<T>(
+        &self,
+        ctx: &mut Context,
+        body: impl FnOnce(&mut Context) -> Result<T>,
+    ) -> Result<T> {
+        let registry = ctx.registry_clone();
+        let ids: Vec<String> = self
+            .imports
+            .iter()
+            .map(|import| import.id.clone())
+            .collect();
+        registry.with_restored_entries(&ids, || {
+            self.register_imports(&registry);
+            body(ctx)
+        })
+    }
+}
+
+/// Reads a YAML/JSON compose file, using the `lcp.toml` next to it (if any)
+/// to canonicalize relative ids.
+pub fn load_compose_document(path: &Path) -> Result<ComposeDocument> {
+    let mut visiting = HashSet::new();
+    load_document(path, None, &mut visiting)
+}
+
+/// Same as [`load_compose_document`] with an explicit package scope.
+pub fn load_compose_document_with_scope(path: &Path, scope: IdScope) -> Result<ComposeDocument> {
+    let mut visiting = HashSet::new();
+    load_document(path, Some(scope), &mut visiting)
+}
+
+/// Builds a document from an already parsed value. Relative `imports:` paths
+/// are resolved against `base_dir`.
+pub fn compose_document_from_value(
+    doc: Value,
+    base_dir: &Path,
+    scope: Option<IdScope>,
+) -> Result<ComposeDocument> {
+    let mut visiting = HashSet::new();
+    document_from_value(doc, base_dir, scope, &mut visiting)
+}
+
+fn load_document(
+    path: &Path,
+    scope: Option<IdScope>,
+    visiting: &mut HashSet<PathBuf>,
+) -> Result<ComposeDocument> {
+    let canonical = path
+        .canonicalize()
+        .with_context(|| format!("unable to read compose file: {}", path.display()))?;
+    if !visiting.insert(canonical.clone()) {
+        return Err(anyhow!(
+            "compose import cycle detected at {}",
+            canonical.display()
+        ));
+    }
+    let doc = read_document(&canonical)?;
+    let base_dir = canonical.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let scope = match scope {
+        Some(scope) => Some(scope),
+        None => IdScope::from_manifest_dir(&base_dir)?,
+    };
+    let document = document_from_value(doc, &base_dir, scope, visiting)
+        .with_context(|| format!("invalid compose structure in {}", canonical.display()));
+    visiting.remove(&canonical);
+    document
+}
+
+fn read_document(path: &Path) -> Result<Value> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("unable to read compose file: {}", path.display()))?;
+    let is_json = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+    if is_json {
+        serde_json::from_str(&text)
+            .with_context(|| format!("invalid compose JSON {}", path.display()))
+    } else {
+        serde_yaml::from_str(&text)
+            .with_context(|| format!("invalid compose YAML {}", path.display()))
+    }
+}
+
+fn document_from_value(
+    doc: Value,
+    base_dir: &Path,
+    scope: Option<IdScope>,
+    visiting: &mut HashSet<PathBuf>,
+) -> Result<ComposeDocument> {
+    let (mut compose_value, header) = match doc {
+        Value::Array(items) => (Value::Array(items), Map::new()),
+        Value::Object(mut map) => {
+            let compose = map
+                .remove("compose")
+                .ok_or_else(|| anyhow!("compose root missing"))?;
+            (compose, map)
+        }
+        _ => {
+            return Err(anyhow!(
+                "compose document must be an array or object with compose root"
+            ))
+        }
+    };
+    let header_value = Value::Object(header);
+    let signature = ComposeSignature::from_document(&header_value)?;
+
+    let mut resolver = IdResolver::new(scope);
+    let mut imports = Vec::new();
+    if let Some(entries) = header_value.get("imports") {
+        let entries = entries
+            .as_object()
+            .ok_or_else(|| anyhow!("compose imports must map aliases to ids or paths"))?;
+        for (alias, target) in entries {
+            let target = target
+                .as_str()
+                .ok_or_else(|| anyhow!("compose import `{alias}` must be a string"))?;
+            if target.starts_with("lcod://") {
+                resolver = resolver.with_import(alias.clone(), target);
+                continue;
+            }
+            if !is_compose_path(target) {
+                return Err(anyhow!(
+                    "compose import `{alias}` must be an lcod:// id or a relative compose path, got {target}"
+                ));
+            }
+            let path = base_dir.join(target);
+            let imported = load_document(&path, None, visiting)
+                .with_context(|| format!("unable to load compose import `{alias}`"))?;
+            let imported_doc = read_document(&path)?;
+            let id = imported_doc
+                .get("id")
+                .and_then(Value::as_str)
+                .filter(|id| id.starts_with("lcod://"))
+                .map(str::to_string)
+                .unwrap_or_else(|| synthesized_import_id(&path));
+            resolver = resolver.with_import(alias.clone(), id.clone());
+            imports.extend(imported.imports);
+            imports.push(ImportedCompose {
+                id,
+                path,
+                steps: Arc::new(imported.steps),
+                signature: imported.signature,
+            });
+        }
+    }
+
+    resolver.canonicalize_value(&mut compose_value);
+    let steps = parse_compose(&compose_value)?;
+    Ok(ComposeDocument {
+        steps,
+        signature,
+        imports,
+    })
+}
+
+fn is_compose_path(target: &str) -> bool {
+    target.starts_with("./")
+        || target.starts_with("../")
+        || [".yaml", ".yml", ".json"]
+            .iter()
+            .any(|ext| target.ends_with(ext))
+}
+
+fn synthesized_import_id(path: &Path) -> String {
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let digest = hex::encode(Sha256::digest(canonical.to_string_lossy().as_bytes()));
+    let stem = canonical
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("compose");
+    let name = if stem == "compose" {
+        canonical
+            .parent()
+            .and_then(Path::file_name)
+            .and_then(|s| s.to_str())
+            .unwrap_or(stem)
+    } else {
+        stem
+    };
+    format!("lcod://imports/{name}-{}@0.0.0", &digest[..12])
+}
+
+pub fn id_path(id: &str) -> Option<&str> {
+    id.strip_prefix("lcod://")?.split('@').next()
+}
+
+pub fn id_version(id: &str) -> Option<&str> {
+    id.split('@').nth(1)
+}
@@ -0,0 +1,241 @@
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamDecl {
+    pub name: String,
+    pub types: Vec<String>,
+    pub required: bool,
+    pub default: Option<Value>,
+}
+
+/// Optional `inputs`/`outputs` header declared next to the `compose` root.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComposeSignature {
+    pub inputs: Vec<ParamDecl>,
+    pub outputs: Vec<ParamDecl>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureSide {
+    Input,
+    Output,
+}
+
+#[derive(Debug)]
+pub struct SignatureError {
+    pub side: SignatureSide,
+    pub missing: Vec<String>,
+    pub mismatches: Vec<String>,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.side {
+            SignatureSide::Input => "input",
+            SignatureSide::Output => "output",
+        };
+        let mut parts = Vec::new();
+        if !self.missing.is_empty() {
+            parts.push(format!(
+                "missing required compose {label}(s): {}",
+                self.missing.join(", ")
+            ));
+        }
+        parts.extend(self.mismatches.iter().cloned());
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+impl ComposeSignature {
+    /// Reads the header of a compose document (or inline component
+    /// definition). Returns `None` when nothing is declared.
+    pub fn from_document(doc: &Value) -> Result<Option<Self>> {
+        let Some(map) = doc.as_object() else {
+            return Ok(None);
+        };
+        let signature = Self {
+            inputs: parse_decls(map.get("inputs"), "inputs")?,
+            outputs: parse_decls(map.get("outputs"), "outputs")?,
+        };
+        if signature.inputs.is_empty() && signature.outputs.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(signature))
+        }
+    }
+
+    /// Applies defaults and validates the initial state against the declared inputs.
+    pub fn apply_inputs(&self, input: Value) -> Result<Value> {
+        let mut state = match input {
+            Value::Object(map) => map,
+            Value::Null => Map::new(),
+            other if self.inputs.is_empty() => return Ok(other),
+            other => {
+                return Err(anyhow!(
+                    "compose input must be an object, got {}",
+                    type_label(&other)
+                ))
+            }
+        };
+        let mut missing = Vec::new();
+        let mut mismatches = Vec::new();
+        for decl in &self.inputs {
+            let present = state.get(&decl.name).filter(|value| !value.is_null());
+            match (present, &decl.default) {
+                (Some(value), _) => {
+                    if let Some(message) = decl.check("input", value) {
+                        mismatches.push(message);
+                    }
+                }
+                (None, Some(default)) => {
+                    state.insert(decl.name.clone(), default.clone());
+                }
+                (None, None) if decl.required => missing.push(decl.name.clone()),
+                (None, None) => {}
+            }
+        }
+        if missing.is_empty() && mismatches.is_empty() {
+            Ok(Value::Object(state))
+        } else {
+            Err(SignatureError {
+                side: SignatureSide::Input,
+                missing,
+                mismatches,
+            }
+            .into())
+        }
+    }
+
+    pub fn check_outputs(&self, output: &Value) -> Result<()> {
+        let mut missing = Vec::new();
+        let mut mismatches = Vec::new();
+        for decl in &self.outputs {
+            match output.get(&decl.name).filter(|value| !value.is_null()) {
+                Some(value) => {
+                    if let Some(message) = decl.check("output", value) {
+                        mismatches.push(message);
+                    }
+                }
+                None if decl.required => missing.push(decl.name.clone()),
+                None => {}
+            }
+        }
+        if missing.is_empty() && mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(SignatureError {
+                side: SignatureSide::Output,
+                missing,
+                mismatches,
+            }
+            .into())
+        }
+    }
+}
+
+impl ParamDecl {
+    fn check(&self, label: &str, value: &Value) -> Option<String> {
+        if self.types.is_empty() || self.types.iter().any(|ty| type_matches(ty, value)) {
+            return None;
+        }
+        Some(format!(
+            "compose {label} `{}` must be {}, got {}",
+            self.name,
+            self.types.join(" | "),
+            type_label(value)
+        ))
+    }
+}
+
+fn parse_decls(section: Option<&Value>, label: &str) -> Result<Vec<ParamDecl>> {
+    match section {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(names)) => names
+            .iter()
+            .map(|name| {
+                name.as_str()
+                    .map(|name| ParamDecl {
+                        name: name.to_string(),
+                        types: Vec::new(),
+                        required: false,
+                        default: None,
+                    })
+                    .ok_or_else(|| anyhow!("compose {label} must list names as strings"))
+            })
+            .collect(),
+        Some(Value::Object(entries)) => entries
+            .iter()
+            .map(|(name, spec)| parse_decl(name, spec, label))
+            .collect(),
+        Some(_) => Err(anyhow!(
+            "compose {label} must be an object or a list of names"
+        )),
+    }
+}
+
+fn parse_decl(name: &str, spec: &Value, label: &str) -> Result<ParamDecl> {
+    let mut decl = ParamDecl {
+        name: name.to_string(),
+        types: Vec::new(),
+        required: false,
+        default: None,
+    };
+    let type_value = match spec {
+        Value::String(_) => Some(spec),
+        Value::Object(map) => {
+            decl.required = map
+                .get("required")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            decl.default = map.get("default").cloned();
+            map.get("type")
+        }
+        _ => None,
+    };
+    decl.types = match type_value {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::String(ty)) => vec![ty.clone()],
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        Some(other) => {
+            return Err(anyhow!(
+                "compose {label} `{name}` has an invalid type declaration: {other}"
+            ))
+        }
+    };
+    Ok(decl)
+}
+
+fn type_matches(ty: &str, value: &Value) -> bool {
+    match ty {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "null" => value.is_null(),
+        // `any` and types this kernel does not know about are not enforced.
+        _ => true,
+    }
+}
+
+fn type_label(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(num) if num.is_i64() || num.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
@@ -1,17 +1,22 @@
 use std::env;
+use std::fs;
+use std::path::Path;
 
 use anyhow::{anyhow, Result};
 use serde_json::{json, Value};
 
+use crate::fs_policy::FsAccess;
 use crate::registry::{Context, Registry};
 
 const CONTRACT_ENV_GET: &str = "lcod://contract/core/env/get@1";
+const CONTRACT_SECRET_GET: &str = "lcod://contract/core/secret/get@1";
 
 pub fn register_env(registry: &Registry) {
     registry.register(CONTRACT_ENV_GET, env_get_contract);
+    registry.register(CONTRACT_SECRET_GET, secret_get_contract);
 }
 
-fn env_get_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+fn env_get_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
     let name = input
         .get("name")
         .and_then(Value::as_str)
@@ -45,12 +50,62 @@ fn env_get_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> R
         }
     }
 
+    let secret = input
+        .get("secret")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    if secret {
+        if let Some(value) = &final_value {
+            ctx.register_secret(value);
+        }
+    }
+
     Ok(json!({
         "exists": exists,
         "value": final_value
     }))
 }
 
+/// Reads a secret from an environment variable (`env`) or a file (`path`) and
+/// registers it so that it gets redacted from logs and reports.
+fn secret_get_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let env_name = input
+        .get("env")
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty());
+    let path = input
+        .get("path")
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty());
+    let required = input
+        .get("required")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+
+    if let Some(path) = path {
+        ctx.check_fs_access(Path::new(path), FsAccess::Read)?;
+    }
+    let value = match (env_name, path) {
+        (Some(name), None) => env::var(name).ok(),
+        (None, Some(path)) => match fs::read_to_string(path) {
+            Ok(content) => Some(content.trim_end_matches(['\r', '\n']).to_string()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => return Err(anyhow!("unable to read secret file `{path}`: {err}")),
+        },
+        _ => return Err(anyhow!("exactly one of `env` or `path` is required")),
+    };
+
+    let Some(value) = value else {
+        if required {
+            let source = env_name.or(path).unwrap_or_default();
+            return Err(anyhow!("secret `{source}` is not defined"));
+        }
+        return Ok(json!({ "exists": false, "value": Value::Null }));
+    };
+    ctx.register_secret(&value);
+    Ok(json!({ "exists": true, "value": value }))
+}
+
 fn expand_placeholders(value: &str) -> String {
     let mut result = String::with_capacity(value.len());
     let mut chars = value.chars().peekable();
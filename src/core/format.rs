@@ -0,0 +1,156 @@
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::registry::{Context, Registry};
+
+const CONTRACT_JSON: &str = "lcod://contract/core/format/json@1";
+const CONTRACT_YAML: &str = "lcod://contract/core/format/yaml@1";
+const CONTRACT_TOML: &str = "lcod://contract/core/format/toml@1";
+const CONTRACT_CSV: &str = "lcod://contract/core/format/csv@1";
+
+/// Serialisers mirroring `core/parse/*`: each takes a `value` (or `rows` for
+/// CSV) and returns `{ text, bytes }`.
+pub fn register_format(registry: &Registry) {
+    registry.register(CONTRACT_JSON, format_json_contract);
+    registry.register(CONTRACT_YAML, format_yaml_contract);
+    registry.register(CONTRACT_TOML, format_toml_contract);
+    registry.register(CONTRACT_CSV, format_csv_contract);
+}
+
+fn text_output(text: String) -> Value {
+    let bytes = text.len();
+    json!({ "text": text, "bytes": bytes })
+}
+
+fn input_value(input: &Value) -> Value {
+    input.get("value").cloned().unwrap_or(Value::Null)
+}
+
+fn format_json_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let value = input_value(&input);
+    let pretty = input
+        .get("pretty")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    let text = if pretty {
+        let indent = input.get("indent").and_then(Value::as_u64).unwrap_or(2) as usize;
+        let indent = " ".repeat(indent);
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+        let mut buffer = Vec::new();
+        let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+        value.serialize(&mut serializer)?;
+        String::from_utf8(buffer)?
+    } else {
+        serde_json::to_string(&value)?
+    };
+    Ok(text_output(text))
+}
+
+/// `documents` renders a multi-document stream separated by `---`.
+fn format_yaml_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let render = |value: &Value| {
+        serde_yaml::to_string(value).map_err(|err| anyhow!("unable to serialize YAML: {err}"))
+    };
+    let text = match input.get("documents").and_then(Value::as_array) {
+        Some(documents) => {
+            let mut text = String::new();
+            for document in documents {
+                text.push_str("---\n");
+                text.push_str(&render(document)?);
+            }
+            text
+        }
+        None => render(&input_value(&input))?,
+    };
+    Ok(text_output(text))
+}
+
+fn format_toml_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let toml_value = toml::Value::try_from(input_value(&input))
+        .map_err(|err| anyhow!("unable to convert value to TOML: {err}"))?;
+    let pretty = input.get("pretty").and_then(Value::as_bool).unwrap_or(true);
+    let text = if pretty {
+        toml::to_string_pretty(&toml_value)
+    } else {
+        toml::to_string(&toml_value)
+    }
+    .map_err(|err| anyhow!("unable to serialize TOML: {err}"))?;
+    Ok(text_output(text))
+}
+
+fn csv_cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(text)) => text.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Rows are arrays or objects. Object rows are laid out along `columns`
+/// (default: every key seen, sorted) and get a header line unless
+/// `header` is false; array rows only get one when `columns` is given.
+fn format_csv_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let rows = input
+        .get("rows")
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("missing or invalid `rows`"))?;
+    let byte_option = |key: &str, default: u8| {
+        input
+            .get(key)
+            .and_then(Value::as_str)
+            .and_then(|s| s.as_bytes().first().copied())
+            .unwrap_or(default)
+    };
+
+    let explicit_columns = input.get("columns").and_then(Value::as_array).map(|arr| {
+        arr.iter()
+            .filter_map(Value::as_str)
+            .map(String::from)
+            .collect::<Vec<_>>()
+    });
+    let object_rows = rows.iter().any(Value::is_object);
+    let columns = match explicit_columns {
+        Some(columns) => Some(columns),
+        None if object_rows => {
+            let seen: BTreeSet<&String> = rows
+                .iter()
+                .filter_map(Value::as_object)
+                .flat_map(|row| row.keys())
+                .collect();
+            Some(seen.into_iter().cloned().collect())
+        }
+        None => None,
+    };
+    let header = input
+        .get("header")
+        .and_then(Value::as_bool)
+        .unwrap_or(columns.is_some());
+
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(byte_option("delimiter", b','))
+        .quote(byte_option("quote", b'"'))
+        .flexible(columns.is_none())
+        .from_writer(Vec::new());
+    if let (true, Some(columns)) = (header, columns.as_ref()) {
+        writer.write_record(columns)?;
+    }
+    for (index, row) in rows.iter().enumerate() {
+        let record: Vec<String> = match row {
+            Value::Object(object) => columns
+                .iter()
+                .flatten()
+                .map(|column| csv_cell(object.get(column)))
+                .collect(),
+            Value::Array(cells) => cells.iter().map(|cell| csv_cell(Some(cell))).collect(),
+            _ => return Err(anyhow!("row {index} must be an array or an object")),
+        };
+        writer.write_record(&record)?;
+    }
+    let buffer = writer
+        .into_inner()
+        .map_err(|err| anyhow!("unable to flush CSV output: {err}"))?;
+    Ok(text_output(String::from_utf8(buffer)?))
+}
@@ -8,18 +8,33 @@ use base64::Engine as _;
 use humantime::format_rfc3339;
 use serde_json::{json, Map, Value};
 
+use crate::fs_policy::{resolve_path, FsAccess};
 use crate::registry::{Context, Registry};
 
 const CONTRACT_READ: &str = "lcod://contract/core/fs/read-file@1";
 const CONTRACT_READ_ALT: &str = "lcod://contract/core/fs/read_file@1";
 const CONTRACT_WRITE: &str = "lcod://contract/core/fs/write-file@1";
 const CONTRACT_WRITE_ALT: &str = "lcod://contract/core/fs/write_file@1";
+const CONTRACT_OPEN_READ: &str = "lcod://contract/core/fs/open-read@1";
+const CONTRACT_OPEN_READ_ALT: &str = "lcod://contract/core/fs/open_read@1";
+const CONTRACT_WRITE_STREAM: &str = "lcod://contract/core/fs/write-stream@1";
+const CONTRACT_WRITE_STREAM_ALT: &str = "lcod://contract/core/fs/write_stream@1";
 const CONTRACT_LIST: &str = "lcod://contract/core/fs/list-dir@1";
 const CONTRACT_LIST_ALT: &str = "lcod://contract/core/fs/list_dir@1";
 const CONTRACT_STAT: &str = "lcod://contract/core/fs/stat@1";
+const CONTRACT_MKDIR: &str = "lcod://contract/core/fs/mkdir@1";
+const CONTRACT_REMOVE: &str = "lcod://contract/core/fs/remove@1";
+const CONTRACT_COPY: &str = "lcod://contract/core/fs/copy@1";
+const CONTRACT_MOVE: &str = "lcod://contract/core/fs/move@1";
+const CONTRACT_EXISTS: &str = "lcod://contract/core/fs/exists@1";
+const CONTRACT_SYMLINK: &str = "lcod://contract/core/fs/symlink@1";
+const CONTRACT_READ_LINK: &str = "lcod://contract/core/fs/read-link@1";
+const CONTRACT_READ_LINK_ALT: &str = "lcod://contract/core/fs/read_link@1";
+const CONTRACT_CHMOD: &str = "lcod://contract/core/fs/chmod@1";
+const CONTRACT_TOUCH: &str = "lcod://contract/core/fs/touch@1";
 
 #[cfg(windows)]
-fn from_unix_path(input: &str) -> PathBuf {
+pub(crate) fn from_unix_path(input: &str) -> PathBuf {
     if input.is_empty() {
         return PathBuf::new();
     }
@@ -40,11 +55,11 @@ fn from_unix_path(input: &str) -> PathBuf {
 }
 
 #[cfg(not(windows))]
-fn from_unix_path(input: &str) -> PathBuf {
+pub(crate) fn from_unix_path(input: &str) -> PathBuf {
     PathBuf::from(input)
 }
 
-fn to_unix_path(path: &Path) -> String {
+pub(crate) fn to_unix_path(path: &Path) -> String {
     crate::core::path::path_to_string(path)
 }
 
@@ -53,9 +68,23 @@ pub fn register_fs(registry: &Registry) {
     registry.register(CONTRACT_READ_ALT, read_file_contract);
     registry.register(CONTRACT_WRITE, write_file_contract);
     registry.register(CONTRACT_WRITE_ALT, write_file_contract);
+    registry.register(CONTRACT_OPEN_READ, open_read_contract);
+    registry.register(CONTRACT_OPEN_READ_ALT, open_read_contract);
+    registry.register(CONTRACT_WRITE_STREAM, write_stream_contract);
+    registry.register(CONTRACT_WRITE_STREAM_ALT, write_stream_contract);
     registry.register(CONTRACT_LIST, list_dir_contract);
     registry.register(CONTRACT_LIST_ALT, list_dir_contract);
     registry.register(CONTRACT_STAT, stat_contract);
+    registry.register(CONTRACT_MKDIR, mkdir_contract);
+    registry.register(CONTRACT_REMOVE, remove_contract);
+    registry.register(CONTRACT_COPY, copy_contract);
+    registry.register(CONTRACT_MOVE, move_contract);
+    registry.register(CONTRACT_EXISTS, exists_contract);
+    registry.register(CONTRACT_SYMLINK, symlink_contract);
+    registry.register(CONTRACT_READ_LINK, read_link_contract);
+    registry.register(CONTRACT_READ_LINK_ALT, read_link_contract);
+    registry.register(CONTRACT_CHMOD, chmod_contract);
+    registry.register(CONTRACT_TOUCH, touch_contract);
 }
 
 fn value_as_str<'a>(value: &'a Value, key: &'static str) -> Result<&'a str> {
@@ -65,6 +94,19 @@ fn value_as_str<'a>(value: &'a Value, key: &'static str) -> Result<&'a str> {
         .ok_or_else(|| anyhow!("missing or invalid `{key}`"))
 }
 
+/// Reads the path under `key` and checks it against the context's filesystem
+/// policy.
+fn checked_path(
+    ctx: &Context,
+    input: &Value,
+    key: &'static str,
+    access: FsAccess,
+) -> Result<PathBuf> {
+    let path = from_unix_path(value_as_str(input, key)?);
+    ctx.check_fs_access(&path, access)?;
+    Ok(path)
+}
+
 fn optional_bool(value: &Value, key: &str, default: bool) -> bool {
     value.get(key).and_then(Value::as_bool).unwrap_or(default)
 }
@@ -78,13 +120,12 @@ fn to_rfc3339(time: SystemTime) -> Result<String> {
     Ok(formatted.to_string())
 }
 
-fn read_file_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
-    let path_str = value_as_str(&input, "path")?;
+fn read_file_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let path = checked_path(ctx, &input, "path", FsAccess::Read)?;
     let encoding = input
         .get("encoding")
         .and_then(Value::as_str)
         .unwrap_or("utf-8");
-    let path = from_unix_path(path_str);
 
     let metadata =
         fs::metadata(&path).with_context(|| format!("unable to stat file: {}", path.display()))?;
@@ -116,8 +157,8 @@ fn read_file_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) ->
     Ok(Value::Object(map))
 }
 
-fn write_file_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
-    let path_str = value_as_str(&input, "path")?;
+fn write_file_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let path = checked_path(ctx, &input, "path", FsAccess::Write)?;
     let encoding = input
         .get("encoding")
         .and_then(Value::as_str)
@@ -128,8 +169,12 @@ fn write_file_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -
         .ok_or_else(|| anyhow!("missing or invalid `data`"))?;
     let append = optional_bool(&input, "append", false);
     let create_parents = optional_bool(&input, "createParents", false);
+    let atomic = optional_bool(&input, "atomic", false);
+    let fsync = optional_bool(&input, "fsync", false);
+    if atomic && append {
+        return Err(anyhow!("`atomic` cannot be combined with `append`"));
+    }
 
-    let path = from_unix_path(path_str);
     if create_parents {
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).with_context(|| {
@@ -138,36 +183,37 @@ fn write_file_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -
         }
     }
 
-    let mut file = if append {
-        fs::OpenOptions::new().create(true).append(true).open(&path)
-    } else {
-        fs::OpenOptions::new()
-            .create(true)
-            .truncate(true)
-            .write(true)
-            .open(&path)
-    }
-    .with_context(|| format!("unable to open file: {}", path.display()))?;
-
-    let bytes = if encoding.eq_ignore_ascii_case("base64") {
-        let buf = base64::engine::general_purpose::STANDARD
+    let buf = if encoding.eq_ignore_ascii_case("base64") {
+        base64::engine::general_purpose::STANDARD
             .decode(data)
-            .map_err(|err| anyhow!("invalid base64 payload: {err}"))?;
-        file.write_all(&buf)
-            .with_context(|| format!("unable to write file: {}", path.display()))?;
-        buf.len()
+            .map_err(|err| anyhow!("invalid base64 payload: {err}"))?
     } else if encoding.eq_ignore_ascii_case("hex") {
-        let buf = hex::decode(data).map_err(|err| anyhow!("invalid hex payload: {err}"))?;
-        file.write_all(&buf)
-            .with_context(|| format!("unable to write file: {}", path.display()))?;
-        buf.len()
+        hex::decode(data).map_err(|err| anyhow!("invalid hex payload: {err}"))?
     } else {
-        file.write_all(data.as_bytes())
-            .with_context(|| format!("unable to write file: {}", path.display()))?;
-        data.len()
+        data.as_bytes().to_vec()
     };
+    let bytes = buf.len();
 
-    drop(file);
+    if atomic {
+        write_atomic(&path, &buf, fsync)?;
+    } else {
+        let mut file = if append {
+            fs::OpenOptions::new().create(true).append(true).open(&path)
+        } else {
+            fs::OpenOptions::new()
+                .create(true)
+                .truncate(true)
+                .write(true)
+                .open(&path)
+        }
+        .with_context(|| format!("unable to open file: {}", path.display()))?;
+        file.write_all(&buf)
+            .with_context(|| format!("unable to write file: {}", path.display()))?;
+        if fsync {
+            file.sync_all()
+                .with_context(|| format!("unable to sync file: {}", path.display()))?;
+        }
+    }
 
     let metadata =
         fs::metadata(&path).with_context(|| format!("unable to stat file: {}", path.display()))?;
@@ -182,14 +228,125 @@ fn write_file_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -
     Ok(Value::Object(map))
 }
 
-fn list_dir_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
-    let path_str = value_as_str(&input, "path")?;
+/// Writes `bytes` to a temporary sibling of `path` and renames it into place,
+/// so readers see either the previous content or the new one, never a mix.
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8], fsync: bool) -> Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut temp = tempfile::Builder::new()
+        .prefix(".lcod-write-")
+        .tempfile_in(parent)
+        .with_context(|| format!("unable to create temporary file in {}", parent.display()))?;
+    temp.write_all(bytes)
+        .with_context(|| format!("unable to write file: {}", path.display()))?;
+    if fsync {
+        temp.as_file()
+            .sync_all()
+            .with_context(|| format!("unable to sync file: {}", path.display()))?;
+    }
+    temp.persist(path).map_err(|err| {
+        anyhow!(err.error).context(format!("unable to replace {}", path.display()))
+    })?;
+    #[cfg(unix)]
+    if fsync {
+        if let Ok(dir) = fs::File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
+
+const DEFAULT_STREAM_CHUNK: usize = 64 * 1024;
+
+fn open_read_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let path = checked_path(ctx, &input, "path", FsAccess::Read)?;
+    let encoding = input
+        .get("encoding")
+        .and_then(Value::as_str)
+        .unwrap_or("base64");
+    let chunk_size = optional_usize(&input, "chunkSize").unwrap_or(DEFAULT_STREAM_CHUNK);
+
+    let file = fs::File::open(&path)
+        .with_context(|| format!("unable to open file: {}", path.display()))?;
+    let size = file
+        .metadata()
+        .with_context(|| format!("unable to stat file: {}", path.display()))?
+        .len();
+    let handle = ctx
+        .streams_mut()
+        .register_reader(file, encoding, chunk_size);
+
+    Ok(json!({ "stream": handle, "path": to_unix_path(&path), "size": size }))
+}
+
+fn write_stream_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let path = checked_path(ctx, &input, "path", FsAccess::Write)?;
+    let stream = input
+        .get("stream")
+        .ok_or_else(|| anyhow!("missing or invalid `stream`"))?;
+    let append = optional_bool(&input, "append", false);
+    let create_parents = optional_bool(&input, "createParents", false);
+    let close = optional_bool(&input, "close", true);
+    if !ctx.streams().contains_handle(stream) {
+        return Err(anyhow!("unknown stream handle: {stream}"));
+    }
+
+    if create_parents {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("unable to create parent directories for {}", path.display())
+            })?;
+        }
+    }
+    let mut options = fs::OpenOptions::new();
+    if append {
+        options.create(true).append(true);
+    } else {
+        options.create(true).truncate(true).write(true);
+    }
+    let mut file = options
+        .open(&path)
+        .with_context(|| format!("unable to open file: {}", path.display()))?;
+
+    let mut bytes = 0usize;
+    let mut chunks = 0u64;
+    while let Some(chunk) = ctx
+        .streams_mut()
+        .read_bytes(stream, Some(DEFAULT_STREAM_CHUNK))?
+    {
+        ctx.ensure_not_cancelled()?;
+        file.write_all(&chunk)
+            .with_context(|| format!("unable to write file: {}", path.display()))?;
+        bytes += chunk.len();
+        chunks += 1;
+    }
+    file.flush()
+        .with_context(|| format!("unable to write file: {}", path.display()))?;
+    drop(file);
+    if close {
+        ctx.streams_mut().close(stream)?;
+    }
+
+    let metadata =
+        fs::metadata(&path).with_context(|| format!("unable to stat file: {}", path.display()))?;
+    let mut map = Map::new();
+    map.insert("bytesWritten".to_string(), Value::Number(bytes.into()));
+    map.insert("chunks".to_string(), Value::Number(chunks.into()));
+    if let Some(ts) = metadata.modified().ok().and_then(|t| to_rfc3339(t).ok()) {
+        map.insert("mtime".to_string(), Value::String(ts));
+    }
+    Ok(Value::Object(map))
+}
+
+fn list_dir_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let root = checked_path(ctx, &input, "path", FsAccess::Read)?;
     let recursive = optional_bool(&input, "recursive", false);
     let include_hidden = optional_bool(&input, "includeHidden", false);
     let include_stats = optional_bool(&input, "includeStats", false);
     let max_depth = optional_usize(&input, "maxDepth").unwrap_or(usize::MAX);
 
-    let root = from_unix_path(path_str);
     let mut entries = Vec::new();
     walk_dir(
         &root,
@@ -277,11 +434,10 @@ fn walk_dir(
     Ok(())
 }
 
-fn stat_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
-    let path_str = value_as_str(&input, "path")?;
+fn stat_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
     let follow_symlinks = optional_bool(&input, "followSymlinks", true);
 
-    let mut target = from_unix_path(path_str);
+    let mut target = checked_path(ctx, &input, "path", FsAccess::Read)?;
     if !target.is_absolute() {
         let cwd = env::current_dir().context("unable to determine current directory")?;
         target = cwd.join(target);
@@ -327,6 +483,397 @@ fn stat_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Resu
     Ok(Value::Object(map))
 }
 
+fn mkdir_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let path = checked_path(ctx, &input, "path", FsAccess::Write)?;
+    let recursive = optional_bool(&input, "recursive", false);
+
+    let existed = path.is_dir();
+    if recursive {
+        fs::create_dir_all(&path)
+    } else {
+        fs::create_dir(&path)
+    }
+    .with_context(|| format!("unable to create directory: {}", path.display()))?;
+
+    Ok(json!({ "path": to_unix_path(&path), "created": !existed }))
+}
+
+fn remove_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let path = checked_path(ctx, &input, "path", FsAccess::Write)?;
+    let recursive = optional_bool(&input, "recursive", false);
+    let missing_ok = optional_bool(&input, "missingOk", false);
+
+    let metadata = match fs::symlink_metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound && missing_ok => {
+            return Ok(json!({ "path": to_unix_path(&path), "removed": false }));
+        }
+        Err(err) => return Err(anyhow!("unable to remove {}: {err}", path.display())),
+    };
+    let entry_type = if metadata.is_dir() {
+        if recursive {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_dir(&path)
+        }
+        .with_context(|| format!("unable to remove directory: {}", path.display()))?;
+        "directory"
+    } else {
+        fs::remove_file(&path)
+            .with_context(|| format!("unable to remove file: {}", path.display()))?;
+        if metadata.file_type().is_symlink() {
+            "symlink"
+        } else {
+            "file"
+        }
+    };
+
+    Ok(json!({ "path": to_unix_path(&path), "removed": true, "type": entry_type }))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Overwrite {
+    Error,
+    Replace,
+    Skip,
+}
+
+fn overwrite_policy(input: &Value) -> Result<Overwrite> {
+    match input.get("overwrite") {
+        None | Some(Value::Null) | Some(Value::Bool(false)) => Ok(Overwrite::Error),
+        Some(Value::Bool(true)) => Ok(Overwrite::Replace),
+        Some(Value::String(policy)) => match policy.as_str() {
+            "error" => Ok(Overwrite::Error),
+            "replace" => Ok(Overwrite::Replace),
+            "skip" => Ok(Overwrite::Skip),
+            other => Err(anyhow!(
+                "invalid `overwrite` policy `{other}` (expected error, replace or skip)"
+            )),
+        },
+        Some(other) => Err(anyhow!("invalid `overwrite` value: {other}")),
+    }
+}
+
+#[derive(Default)]
+struct CopyStats {
+    files: u64,
+    directories: u64,
+    skipped: u64,
+}
+
+/// A directory copied into its own subtree would keep finding the entries it
+/// just created.
+fn ensure_not_into_itself(from: &Path, to: &Path) -> Result<()> {
+    if !fs::symlink_metadata(from).is_ok_and(|meta| meta.is_dir()) {
+        return Ok(());
+    }
+    if resolve_path(to).starts_with(resolve_path(from)) {
+        return Err(anyhow!(
+            "cannot copy directory {} into itself: {}",
+            from.display(),
+            to.display()
+        ));
+    }
+    Ok(())
+}
+
+fn copy_entry(
+    from: &Path,
+    to: &Path,
+    recursive: bool,
+    policy: Overwrite,
+    stats: &mut CopyStats,
+) -> Result<()> {
+    let metadata = fs::symlink_metadata(from)
+        .with_context(|| format!("unable to stat path: {}", from.display()))?;
+    if metadata.is_dir() {
+        if !recursive {
+            return Err(anyhow!(
+                "{} is a directory; set `recursive` to copy it",
+                from.display()
+            ));
+        }
+        fs::create_dir_all(to)
+            .with_context(|| format!("unable to create directory: {}", to.display()))?;
+        stats.directories += 1;
+        let entries = fs::read_dir(from)
+            .with_context(|| format!("unable to read directory: {}", from.display()))?;
+        for entry in entries {
+            let entry = entry.with_context(|| {
+                format!("unable to process directory entry: {}", from.display())
+            })?;
+            copy_entry(
+                &entry.path(),
+                &to.join(entry.file_name()),
+                true,
+                policy,
+                stats,
+            )?;
+        }
+        return Ok(());
+    }
+
+    if fs::symlink_metadata(to).is_ok() {
+        match policy {
+            Overwrite::Error => {
+                return Err(anyhow!("destination already exists: {}", to.display()))
+            }
+            Overwrite::Skip => {
+                stats.skipped += 1;
+                return Ok(());
+            }
+            Overwrite::Replace => {
+                if to.is_dir() {
+                    return Err(anyhow!(
+                        "cannot replace directory {} with a file",
+                        to.display()
+                    ));
+                }
+                fs::remove_file(to)
+                    .with_context(|| format!("unable to replace file: {}", to.display()))?;
+            }
+        }
+    }
+    if metadata.file_type().is_symlink() {
+        let target = fs::read_link(from)
+            .with_context(|| format!("unable to read link: {}", from.display()))?;
+        create_symlink(&target, to)?;
+    } else {
+        fs::copy(from, to)
+            .with_context(|| format!("unable to copy {} to {}", from.display(), to.display()))?;
+    }
+    stats.files += 1;
+    Ok(())
+}
+
+fn copy_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let from = checked_path(ctx, &input, "from", FsAccess::Read)?;
+    let to = checked_path(ctx, &input, "to", FsAccess::Write)?;
+    let recursive = optional_bool(&input, "recursive", false);
+    let policy = overwrite_policy(&input)?;
+    if optional_bool(&input, "createParents", false) {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("unable to create parent directories for {}", to.display())
+            })?;
+        }
+    }
+
+    ensure_not_into_itself(&from, &to)?;
+    let mut stats = CopyStats::default();
+    copy_entry(&from, &to, recursive, policy, &mut stats)?;
+
+    Ok(json!({
+        "from": to_unix_path(&from),
+        "to": to_unix_path(&to),
+        "files": stats.files,
+        "directories": stats.directories,
+        "skipped": stats.skipped
+    }))
+}
+
+fn move_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let from = checked_path(ctx, &input, "from", FsAccess::Write)?;
+    let to = checked_path(ctx, &input, "to", FsAccess::Write)?;
+    let overwrite = optional_bool(&input, "overwrite", false);
+    if optional_bool(&input, "createParents", false) {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("unable to create parent directories for {}", to.display())
+            })?;
+        }
+    }
+    if !overwrite && fs::symlink_metadata(&to).is_ok() {
+        return Err(anyhow!("destination already exists: {}", to.display()));
+    }
+    ensure_not_into_itself(&from, &to)?;
+
+    let existed = fs::symlink_metadata(&to).is_ok();
+    match fs::rename(&from, &to) {
+        Ok(()) => {}
+        // Renames cannot cross filesystems: fall back to copy + remove.
+        Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {
+            let policy = if overwrite {
+                Overwrite::Replace
+            } else {
+                Overwrite::Error
+            };
+            if let Err(err) = copy_entry(&from, &to, true, policy, &mut CopyStats::default()) {
+                // Drop the partial copy; an existing destination is left as is.
+                if !existed {
+                    let _ = remove_entry(&to);
+                }
+                return Err(err.context(format!(
+                    "unable to move {} to {}",
+                    from.display(),
+                    to.display()
+                )));
+            }
+            remove_entry(&from)
+                .with_context(|| format!("unable to remove {} after copy", from.display()))?;
+        }
+        Err(err) => {
+            return Err(anyhow!(err).context(format!(
+                "unable to move {} to {}",
+                from.display(),
+                to.display()
+            )))
+        }
+    }
+
+    Ok(json!({ "from": to_unix_path(&from), "to": to_unix_path(&to) }))
+}
+
+fn remove_entry(path: &Path) -> std::io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+fn exists_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let path = checked_path(ctx, &input, "path", FsAccess::Read)?;
+    let follow_symlinks = optional_bool(&input, "followSymlinks", true);
+
+    let metadata = if follow_symlinks {
+        fs::metadata(&path)
+    } else {
+        fs::symlink_metadata(&path)
+    };
+    let entry_type = match metadata {
+        Ok(meta) if meta.is_dir() => Value::String("directory".to_string()),
+        Ok(meta) if meta.file_type().is_symlink() => Value::String("symlink".to_string()),
+        Ok(_) => Value::String("file".to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Value::Null,
+        Err(err) => return Err(anyhow!("unable to stat path: {}: {err}", path.display())),
+    };
+
+    Ok(json!({ "path": to_unix_path(&path), "exists": !entry_type.is_null(), "type": entry_type }))
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(target, link)
+        .with_context(|| format!("unable to create symlink: {}", link.display()))
+}
+
+#[cfg(windows)]
+fn create_symlink(target: &Path, link: &Path) -> Result<()> {
+    let resolved = link
+        .parent()
+        .map(|dir| dir.join(target))
+        .unwrap_or_else(|| target.to_path_buf());
+    if resolved.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+    .with_context(|| format!("unable to create symlink: {}", link.display()))
+}
+
+fn symlink_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let target = from_unix_path(value_as_str(&input, "target")?);
+    let path = checked_path(ctx, &input, "path", FsAccess::Write)?;
+    // The link must not become a way to read outside the sandbox.
+    let resolved_target = match path.parent() {
+        Some(parent) if target.is_relative() => parent.join(&target),
+        _ => target.clone(),
+    };
+    ctx.check_fs_access(&resolved_target, FsAccess::Read)?;
+    let overwrite = optional_bool(&input, "overwrite", false);
+
+    if let Ok(existing) = fs::symlink_metadata(&path) {
+        if !overwrite || !existing.file_type().is_symlink() {
+            return Err(anyhow!("destination already exists: {}", path.display()));
+        }
+        fs::remove_file(&path)
+            .with_context(|| format!("unable to replace symlink: {}", path.display()))?;
+    }
+    create_symlink(&target, &path)?;
+
+    Ok(json!({ "path": to_unix_path(&path), "target": to_unix_path(&target) }))
+}
+
+fn read_link_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let path = checked_path(ctx, &input, "path", FsAccess::Read)?;
+    let target =
+        fs::read_link(&path).with_context(|| format!("unable to read link: {}", path.display()))?;
+
+    Ok(json!({ "path": to_unix_path(&path), "target": to_unix_path(&target) }))
+}
+
+fn parse_mode(value: &Value) -> Result<u32> {
+    match value {
+        Value::Number(num) => num
+            .as_u64()
+            .and_then(|mode| u32::try_from(mode).ok())
+            .ok_or_else(|| anyhow!("invalid `mode`: {num}")),
+        // Strings are octal, as in `chmod 755`.
+        Value::String(text) => u32::from_str_radix(text.trim_start_matches("0o"), 8)
+            .map_err(|_| anyhow!("invalid octal `mode`: {text}")),
+        other => Err(anyhow!("invalid `mode`: {other}")),
+    }
+}
+
+fn chmod_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let path = checked_path(ctx, &input, "path", FsAccess::Write)?;
+    let mode = parse_mode(
+        input
+            .get("mode")
+            .ok_or_else(|| anyhow!("missing or invalid `mode`"))?,
+    )? & 0o7777;
+
+    let mut permissions = fs::metadata(&path)
+        .with_context(|| format!("unable to stat path: {}", path.display()))?
+        .permissions();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(mode);
+    }
+    #[cfg(not(unix))]
+    {
+        // Only the read-only flag maps onto non-Unix permissions.
+        permissions.set_readonly(mode & 0o222 == 0);
+    }
+    fs::set_permissions(&path, permissions)
+        .with_context(|| format!("unable to change permissions: {}", path.display()))?;
+
+    Ok(json!({ "path": to_unix_path(&path), "mode": format!("{mode:04o}") }))
+}
+
+fn touch_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let path = checked_path(ctx, &input, "path", FsAccess::Write)?;
+    let mtime = match input.get("mtime").and_then(Value::as_str) {
+        Some(text) => humantime::parse_rfc3339_weak(text)
+            .map_err(|err| anyhow!("invalid `mtime` {text}: {err}"))?,
+        None => SystemTime::now(),
+    };
+    if optional_bool(&input, "createParents", false) {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("unable to create parent directories for {}", path.display())
+            })?;
+        }
+    }
+
+    let created = !path.exists();
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("unable to open file: {}", path.display()))?;
+    file.set_modified(mtime)
+        .with_context(|| format!("unable to set mtime: {}", path.display()))?;
+
+    Ok(json!({
+        "path": to_unix_path(&path),
+        "created": created,
+        "mtime": to_rfc3339(mtime)?
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
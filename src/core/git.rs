@@ -8,6 +8,7 @@ use git2::{build::RepoBuilder, FetchOptions, Oid, Repository};
 use humantime::format_rfc3339;
 use serde_json::{json, Map, Value};
 
+use crate::fs_policy::FsAccess;
 use crate::registry::{Context, Registry};
 
 const CONTRACT_GIT_CLONE: &str = "lcod://contract/core/git/clone@1";
@@ -16,7 +17,7 @@ pub fn register_git(registry: &Registry) {
     registry.register(CONTRACT_GIT_CLONE, git_clone_contract);
 }
 
-fn git_clone_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+fn git_clone_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
     let url = input
         .get("url")
         .and_then(Value::as_str)
@@ -32,8 +33,14 @@ fn git_clone_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) ->
     let depth = input.get("depth").and_then(Value::as_u64);
     let requested_ref = input.get("ref").and_then(Value::as_str);
     let subdir = input.get("subdir").and_then(Value::as_str);
+    let keep = input.get("keep").and_then(Value::as_bool).unwrap_or(true);
 
-    let checkout_root = prepare_checkout_directory(dest)?;
+    let checkout_root = prepare_checkout_directory(ctx, dest)?;
+    // Checkouts are kept by default; `keep: false` turns a generated
+    // destination into scratch space removed with the enclosing scope.
+    if dest.is_none() && !keep {
+        ctx.track_temp_path(checkout_root.clone());
+    }
 
     let mut fetch_options = FetchOptions::new();
     if let Some(depth) = depth {
@@ -92,12 +99,13 @@ fn git_clone_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) ->
     Ok(Value::Object(output))
 }
 
-fn prepare_checkout_directory(dest: Option<&str>) -> Result<PathBuf> {
+fn prepare_checkout_directory(ctx: &Context, dest: Option<&str>) -> Result<PathBuf> {
     let workspace = std::env::temp_dir().join("lcod-git");
     fs::create_dir_all(&workspace)
         .with_context(|| format!("unable to prepare workspace at {}", workspace.display()))?;
     let path = if let Some(dest) = dest {
         let target = workspace.join(dest);
+        ctx.check_fs_access(&target, FsAccess::Write)?;
         if target.exists() {
             fs::remove_dir_all(&target).with_context(|| {
                 format!("unable to clear existing destination {}", target.display())
@@ -113,7 +121,9 @@ fn prepare_checkout_directory(dest: Option<&str>) -> Result<PathBuf> {
                 .unwrap_or_default()
                 .as_nanos()
         );
-        workspace.join(unique)
+        let target = workspace.join(unique);
+        ctx.check_fs_access(&target, FsAccess::Write)?;
+        target
     };
     if !path.exists() {
         fs::create_dir_all(&path)
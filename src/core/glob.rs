@@ -0,0 +1,344 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context as AnyhowContext, Result};
+use globset::{Glob, GlobBuilder, GlobMatcher, GlobSet, GlobSetBuilder};
+use humantime::format_rfc3339;
+use serde_json::{json, Map, Value};
+
+use super::fs::{from_unix_path, to_unix_path};
+use crate::fs_policy::FsAccess;
+use crate::registry::{Context, Registry};
+
+const CONTRACT_GLOB: &str = "lcod://contract/core/fs/glob@1";
+
+pub fn register_glob(registry: &Registry) {
+    registry.register(CONTRACT_GLOB, glob_contract);
+}
+
+/// Options for [`glob`]. Patterns are relative to the root, use `/` as
+/// separator and support `*`, `?`, `**`, `[...]` and `{a,b}`; a leading `!`
+/// excludes matches. `ignore` entries and `ignore_files` follow `.gitignore`
+/// rules and prune whole directories.
+#[derive(Clone, Debug, Default)]
+pub struct GlobOptions {
+    pub patterns: Vec<String>,
+    pub ignore: Vec<String>,
+    pub ignore_files: Vec<String>,
+    pub follow_symlinks: bool,
+    pub include_hidden: bool,
+    pub include_directories: bool,
+    pub max_depth: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct GlobMatch {
+    pub path: PathBuf,
+    /// Path relative to the glob root, `/`-separated.
+    pub relative: String,
+    pub entry_type: &'static str,
+}
+
+fn build_glob(pattern: &str) -> Result<Glob> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .backslash_escape(true)
+        .build()
+        .with_context(|| format!("invalid glob pattern `{pattern}`"))
+}
+
+#[derive(Debug)]
+struct IgnoreRule {
+    /// Directory (relative to the root) the rule applies to.
+    base: String,
+    matcher: GlobMatcher,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl IgnoreRule {
+    fn parse(line: &str, base: &str) -> Result<Option<Self>> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let dir_only = line.ends_with('/');
+        let trimmed = line.trim_end_matches('/');
+        // A slash anywhere but the end anchors the pattern to `base`.
+        let anchored = trimmed.contains('/');
+        let trimmed = trimmed.trim_start_matches('/');
+        if trimmed.is_empty() {
+            return Ok(None);
+        }
+        let pattern = if anchored {
+            trimmed.to_string()
+        } else {
+            format!("**/{trimmed}")
+        };
+        Ok(Some(Self {
+            base: base.to_string(),
+            matcher: build_glob(&pattern)?.compile_matcher(),
+            negated,
+            dir_only,
+        }))
+    }
+
+    fn matches(&self, relative: &str, is_dir: bool) -> Option<bool> {
+        if self.dir_only && !is_dir {
+            return None;
+        }
+        let scoped = if self.base.is_empty() {
+            relative
+        } else {
+            relative.strip_prefix(&self.base)?.strip_prefix('/')?
+        };
+        self.matcher.is_match(scoped).then_some(!self.negated)
+    }
+}
+
+fn is_ignored(rules: &[IgnoreRule], relative: &str, is_dir: bool) -> bool {
+    // The last matching rule wins, so `!pattern` can re-include entries.
+    rules
+        .iter()
+        .rev()
+        .find_map(|rule| rule.matches(relative, is_dir))
+        .unwrap_or(false)
+}
+
+struct Walker<'a> {
+    options: &'a GlobOptions,
+    include: GlobSet,
+    exclude: GlobSet,
+    rules: Vec<IgnoreRule>,
+    visited: HashSet<PathBuf>,
+    matches: Vec<GlobMatch>,
+}
+
+impl Walker<'_> {
+    fn load_ignore_files(&mut self, dir: &Path, relative: &str) -> Result<()> {
+        for name in &self.options.ignore_files {
+            let path = dir.join(name);
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            for line in content.lines() {
+                if let Some(rule) = IgnoreRule::parse(line, relative)
+                    .with_context(|| format!("in ignore file {}", path.display()))?
+                {
+                    self.rules.push(rule);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn walk(&mut self, dir: &Path, relative: &str, depth: usize) -> Result<()> {
+        let listing = match fs::read_dir(dir) {
+            Ok(listing) => listing,
+            // Unreadable subdirectories are skipped like ignored ones; only
+            // the root itself has to be readable.
+            Err(_) if depth > 0 => return Ok(()),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("unable to read directory: {}", dir.display()))
+            }
+        };
+        let rules_before = self.rules.len();
+        self.load_ignore_files(dir, relative)?;
+
+        let mut entries = listing
+            .collect::<std::io::Result<Vec<_>>>()
+            .with_context(|| format!("unable to process directory entry: {}", dir.display()))?;
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let Ok(name) = entry.file_name().into_string() else {
+                continue;
+            };
+            if !self.options.include_hidden && name.starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            let child = if relative.is_empty() {
+                name
+            } else {
+                format!("{relative}/{name}")
+            };
+            let file_type = entry
+                .file_type()
+                .with_context(|| format!("unable to get file type for {}", path.display()))?;
+            let (is_dir, entry_type) = if file_type.is_symlink() {
+                if self.options.follow_symlinks {
+                    // Broken links are reported as plain symlinks.
+                    let target_is_dir = fs::metadata(&path).is_ok_and(|meta| meta.is_dir());
+                    (
+                        target_is_dir,
+                        if target_is_dir {
+                            "directory"
+                        } else {
+                            "symlink"
+                        },
+                    )
+                } else {
+                    (false, "symlink")
+                }
+            } else if file_type.is_dir() {
+                (true, "directory")
+            } else {
+                (false, "file")
+            };
+
+            if is_ignored(&self.rules, &child, is_dir) {
+                continue;
+            }
+            if (self.options.include_directories || !is_dir)
+                && self.include.is_match(&child)
+                && !self.exclude.is_match(&child)
+            {
+                self.matches.push(GlobMatch {
+                    path: path.clone(),
+                    relative: child.clone(),
+                    entry_type,
+                });
+            }
+            let within_depth = self.options.max_depth.is_none_or(|max| depth < max);
+            if is_dir && within_depth {
+                // Guard against symlink cycles when following links.
+                let key = path.canonicalize().unwrap_or_else(|_| path.clone());
+                if self.visited.insert(key) {
+                    self.walk(&path, &child, depth + 1)?;
+                }
+            }
+        }
+
+        self.rules.truncate(rules_before);
+        Ok(())
+    }
+}
+
+/// Walks `root` and returns the entries matching `options`, sorted by their
+/// relative path.
+pub fn glob(root: &Path, options: &GlobOptions) -> Result<Vec<GlobMatch>> {
+    let mut include = GlobSetBuilder::new();
+    let mut exclude = GlobSetBuilder::new();
+    let mut has_include = false;
+    for pattern in &options.patterns {
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, pattern.as_str()),
+        };
+        let glob = build_glob(pattern.trim_start_matches("./"))?;
+        if negated {
+            exclude.add(glob);
+        } else {
+            include.add(glob);
+            has_include = true;
+        }
+    }
+    if !has_include {
+        return Err(anyhow!("at least one non-negated glob pattern is required"));
+    }
+
+    let mut rules = Vec::new();
+    for line in &options.ignore {
+        rules.extend(IgnoreRule::parse(line, "")?);
+    }
+    let mut walker = Walker {
+        options,
+        include: include.build()?,
+        exclude: exclude.build()?,
+        rules,
+        visited: HashSet::new(),
+        matches: Vec::new(),
+    };
+    walker
+        .visited
+        .insert(root.canonicalize().unwrap_or_else(|_| root.to_path_buf()));
+    walker.walk(root, "", 0)?;
+
+    let mut matches = walker.matches;
+    matches.sort_by(|a, b| a.relative.cmp(&b.relative));
+    Ok(matches)
+}
+
+fn string_list(input: &Value, key: &str) -> Result<Vec<String>> {
+    match input.get(key) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::String(single)) => Ok(vec![single.clone()]),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| anyhow!("`{key}` entries must be strings"))
+            })
+            .collect(),
+        Some(_) => Err(anyhow!("`{key}` must be a string or an array of strings")),
+    }
+}
+
+fn glob_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let root = match input.get("cwd").and_then(Value::as_str) {
+        Some(cwd) => from_unix_path(cwd),
+        None => env::current_dir().context("unable to determine current directory")?,
+    };
+    ctx.check_fs_access(&root, FsAccess::Read)?;
+    let flag = |key: &str| input.get(key).and_then(Value::as_bool).unwrap_or(false);
+    let options = GlobOptions {
+        patterns: string_list(&input, "patterns")?,
+        ignore: string_list(&input, "ignore")?,
+        ignore_files: string_list(&input, "ignoreFiles")?,
+        follow_symlinks: flag("followSymlinks"),
+        include_hidden: flag("includeHidden"),
+        include_directories: flag("includeDirectories"),
+        max_depth: input
+            .get("maxDepth")
+            .and_then(Value::as_u64)
+            .map(|depth| depth as usize),
+    };
+    let absolute = flag("absolute");
+    let include_stats = flag("includeStats");
+
+    // Matches that resolve outside the sandbox (through symlinks) are dropped
+    // rather than failing the whole listing.
+    let matches: Vec<GlobMatch> = glob(&root, &options)?
+        .into_iter()
+        .filter(|found| ctx.check_fs_access(&found.path, FsAccess::Read).is_ok())
+        .collect();
+    let render = |found: &GlobMatch| {
+        if absolute {
+            to_unix_path(&found.path)
+        } else {
+            found.relative.clone()
+        }
+    };
+    let paths: Vec<Value> = matches
+        .iter()
+        .map(|found| Value::String(render(found)))
+        .collect();
+    let mut out = Map::new();
+    out.insert("paths".to_string(), Value::Array(paths));
+    if include_stats {
+        let entries = matches
+            .iter()
+            .map(|found| {
+                let mut entry = json!({ "path": render(found), "type": found.entry_type });
+                if let Ok(metadata) = fs::metadata(&found.path) {
+                    entry["size"] = json!(metadata.len());
+                    if let Ok(modified) = metadata.modified() {
+                        entry["mtime"] = json!(format_rfc3339(modified).to_string());
+                    }
+                }
+                entry
+            })
+            .collect();
+        out.insert("entries".to_string(), Value::Array(entries));
+    }
+    Ok(Value::Object(out))
+}
@@ -1,68 +1,333 @@
-use std::fs;
+use std::fs::File;
+use std::io::{ErrorKind, Read};
 use std::path::Path;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context as AnyhowContext, Result};
 use base64::Engine as _;
-use serde_json::{json, Value};
-use sha2::{Digest, Sha256};
+use md5::Md5;
+use serde_json::{json, Map, Value};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
 
+use crate::fs_policy::FsAccess;
 use crate::registry::{Context, Registry};
 
 const CONTRACT_SHA256: &str = "lcod://contract/core/hash/sha256@1";
+const CONTRACT_DIGEST: &str = "lcod://contract/core/hash/digest@1";
+
+const CHUNK_SIZE: usize = 64 * 1024;
 
 pub fn register_hash(registry: &Registry) {
     registry.register(CONTRACT_SHA256, hash_sha256_contract);
+    registry.register(CONTRACT_DIGEST, hash_digest_contract);
 }
 
-fn hash_sha256_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
-    let (buffer, bytes) = read_buffer(&input)?;
-    let mut hasher = Sha256::new();
-    hasher.update(&buffer);
-    let digest = hasher.finalize();
-    let hex = hex::encode(&digest);
-    let base64 = base64::engine::general_purpose::STANDARD.encode(digest);
-    Ok(json!({
-        "hex": hex,
-        "base64": base64,
-        "bytes": bytes
-    }))
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+    Blake3,
+    Md5,
+}
+
+impl HashAlgorithm {
+    /// Accepts `sha256`, `SHA-256`, `sha_256` and so on.
+    pub fn parse(name: &str) -> Result<Self> {
+        let normalized: String = name
+            .chars()
+            .filter(|c| !matches!(c, '-' | '_'))
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match normalized.as_str() {
+            "sha1" => Ok(Self::Sha1),
+            "sha256" => Ok(Self::Sha256),
+            "sha384" => Ok(Self::Sha384),
+            "sha512" => Ok(Self::Sha512),
+            "blake3" => Ok(Self::Blake3),
+            "md5" => Ok(Self::Md5),
+            _ => Err(anyhow!("unsupported hash algorithm: {name}")),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sha1 => "sha1",
+            Self::Sha256 => "sha256",
+            Self::Sha384 => "sha384",
+            Self::Sha512 => "sha512",
+            Self::Blake3 => "blake3",
+            Self::Md5 => "md5",
+        }
+    }
+
+    fn block_size(self) -> usize {
+        match self {
+            Self::Sha384 | Self::Sha512 => 128,
+            _ => 64,
+        }
+    }
+
+    fn hasher(self) -> Hasher {
+        match self {
+            Self::Sha1 => Hasher::Sha1(Sha1::new()),
+            Self::Sha256 => Hasher::Sha256(Sha256::new()),
+            Self::Sha384 => Hasher::Sha384(Sha384::new()),
+            Self::Sha512 => Hasher::Sha512(Sha512::new()),
+            Self::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+            Self::Md5 => Hasher::Md5(Md5::new()),
+        }
+    }
 }
 
-fn read_buffer(input: &Value) -> Result<(Vec<u8>, usize)> {
+enum Hasher {
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha384(Sha384),
+    Sha512(Sha512),
+    Blake3(Box<blake3::Hasher>),
+    Md5(Md5),
+}
+
+impl Hasher {
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::Sha1(h) => h.update(bytes),
+            Hasher::Sha256(h) => h.update(bytes),
+            Hasher::Sha384(h) => h.update(bytes),
+            Hasher::Sha512(h) => h.update(bytes),
+            Hasher::Blake3(h) => {
+                h.update(bytes);
+            }
+            Hasher::Md5(h) => h.update(bytes),
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Hasher::Sha1(h) => h.finalize().to_vec(),
+            Hasher::Sha256(h) => h.finalize().to_vec(),
+            Hasher::Sha384(h) => h.finalize().to_vec(),
+            Hasher::Sha512(h) => h.finalize().to_vec(),
+            Hasher::Blake3(h) => h.finalize().as_bytes().to_vec(),
+            Hasher::Md5(h) => h.finalize().to_vec(),
+        }
+    }
+}
+
+/// Incremental digest, optionally keyed as an HMAC (RFC 2104).
+pub struct Digester {
+    algorithm: HashAlgorithm,
+    inner: Hasher,
+    outer_pad: Option<Vec<u8>>,
+}
+
+impl Digester {
+    pub fn new(algorithm: HashAlgorithm, key: Option<&[u8]>) -> Self {
+        let mut inner = algorithm.hasher();
+        let outer_pad = key.map(|key| {
+            let block = algorithm.block_size();
+            let mut padded = if key.len() > block {
+                let mut hasher = algorithm.hasher();
+                hasher.update(key);
+                hasher.finalize()
+            } else {
+                key.to_vec()
+            };
+            padded.resize(block, 0);
+            let inner_pad: Vec<u8> = padded.iter().map(|b| b ^ 0x36).collect();
+            inner.update(&inner_pad);
+            padded.iter().map(|b| b ^ 0x5c).collect()
+        });
+        Self {
+            algorithm,
+            inner,
+            outer_pad,
+        }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.inner.update(bytes);
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        let digest = self.inner.finalize();
+        match self.outer_pad {
+            Some(pad) => {
+                let mut outer = self.algorithm.hasher();
+                outer.update(&pad);
+                outer.update(&digest);
+                outer.finalize()
+            }
+            None => digest,
+        }
+    }
+}
+
+/// Integrity string: Subresource Integrity (`sha384-<base64>`) by default, or
+/// the hex form used by `lcp.lock` (`sha256-<hex>`) when `format` is `lock`.
+fn format_integrity(algorithm: HashAlgorithm, digest: &[u8], format: &str) -> Result<String> {
+    let encoded = match format {
+        "sri" => base64::engine::general_purpose::STANDARD.encode(digest),
+        "lock" => hex::encode(digest),
+        other => {
+            return Err(anyhow!(
+                "unsupported integrityFormat `{other}` (expected `sri` or `lock`)"
+            ))
+        }
+    };
+    Ok(format!("{}-{}", algorithm.name(), encoded))
+}
+
+fn decode_text(data: &str, encoding: &str) -> Result<Vec<u8>> {
+    match encoding.to_lowercase().as_str() {
+        "base64" => base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .map_err(|err| anyhow!("invalid base64 payload: {err}")),
+        "hex" => hex::decode(data).map_err(|err| anyhow!("invalid hex payload: {err}")),
+        _ => Ok(data.as_bytes().to_vec()),
+    }
+}
+
+/// Feeds `data`, `path` or `stream` into the digester chunk by chunk and
+/// returns the number of bytes hashed.
+fn feed_input(ctx: &mut Context, input: &Value, digester: &mut Digester) -> Result<u64> {
     if let Some(data) = input.get("data").and_then(Value::as_str) {
         let encoding = input
             .get("encoding")
             .and_then(Value::as_str)
             .unwrap_or("utf-8");
-        let buffer = match encoding.to_lowercase().as_str() {
-            "base64" => base64::engine::general_purpose::STANDARD
-                .decode(data)
-                .map_err(|err| anyhow!("invalid base64 payload: {err}"))?,
-            "hex" => hex::decode(data).map_err(|err| anyhow!("invalid hex payload: {err}"))?,
-            _ => data.as_bytes().to_vec(),
-        };
-        let len = buffer.len();
-        return Ok((buffer, len));
+        let buffer = decode_text(data, encoding)?;
+        digester.update(&buffer);
+        return Ok(buffer.len() as u64);
     }
 
     if let Some(path) = input.get("path").and_then(Value::as_str) {
-        let buf = fs::read(Path::new(path))
-            .map_err(|err| anyhow!("unable to read file `{path}`: {err}"))?;
-        let len = buf.len();
-        return Ok((buf, len));
+        ctx.check_fs_access(Path::new(path), FsAccess::Read)?;
+        let mut file = File::open(path).with_context(|| format!("unable to read file `{path}`"))?;
+        let mut chunk = vec![0; CHUNK_SIZE];
+        let mut total = 0u64;
+        loop {
+            let read = match file.read(&mut chunk) {
+                Ok(0) => return Ok(total),
+                Ok(read) => read,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(anyhow!("unable to read file `{path}`: {err}")),
+            };
+            digester.update(&chunk[..read]);
+            total += read as u64;
+        }
     }
 
-    Err(anyhow!("missing `data` or `path` for hash input"))
+    if let Some(stream) = input.get("stream") {
+        let mut total = 0u64;
+        while let Some(chunk) = ctx.streams_mut().read_bytes(stream, Some(CHUNK_SIZE))? {
+            ctx.ensure_not_cancelled()?;
+            digester.update(&chunk);
+            total += chunk.len() as u64;
+        }
+        if input
+            .get("closeStream")
+            .and_then(Value::as_bool)
+            .unwrap_or(true)
+        {
+            let _ = ctx.streams_mut().close(stream);
+        }
+        return Ok(total);
+    }
+
+    Err(anyhow!("missing `data`, `path` or `stream` for hash input"))
+}
+
+fn hash_sha256_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let mut digester = Digester::new(HashAlgorithm::Sha256, None);
+    let bytes = feed_input(ctx, &input, &mut digester)?;
+    let digest = digester.finalize();
+    Ok(json!({
+        "hex": hex::encode(&digest),
+        "base64": base64::engine::general_purpose::STANDARD.encode(&digest),
+        "bytes": bytes
+    }))
+}
+
+fn hash_digest_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let algorithm = HashAlgorithm::parse(
+        input
+            .get("algorithm")
+            .and_then(Value::as_str)
+            .unwrap_or("sha256"),
+    )?;
+    let key = match input.get("key").and_then(Value::as_str) {
+        Some(key) => {
+            let encoding = input
+                .get("keyEncoding")
+                .and_then(Value::as_str)
+                .unwrap_or("utf-8");
+            Some(decode_text(key, encoding)?)
+        }
+        None => None,
+    };
+
+    let integrity_format = input
+        .get("integrityFormat")
+        .and_then(Value::as_str)
+        .unwrap_or("sri");
+
+    let mut digester = Digester::new(algorithm, key.as_deref());
+    let bytes = feed_input(ctx, &input, &mut digester)?;
+    let digest = digester.finalize();
+
+    let mut out = Map::new();
+    out.insert("algorithm".to_string(), json!(algorithm.name()));
+    out.insert("hex".to_string(), json!(hex::encode(&digest)));
+    out.insert(
+        "base64".to_string(),
+        json!(base64::engine::general_purpose::STANDARD.encode(&digest)),
+    );
+    out.insert("bytes".to_string(), json!(bytes));
+    out.insert("hmac".to_string(), json!(key.is_some()));
+    if key.is_none() {
+        out.insert(
+            "integrity".to_string(),
+            json!(format_integrity(algorithm, &digest, integrity_format)?),
+        );
+    }
+    Ok(Value::Object(out))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn hex_digest(algorithm: HashAlgorithm, key: Option<&[u8]>, data: &[u8]) -> String {
+        let mut digester = Digester::new(algorithm, key);
+        digester.update(data);
+        hex::encode(digester.finalize())
+    }
+
+    #[test]
+    fn decode_text_utf8() {
+        assert_eq!(decode_text("hello", "utf-8").unwrap().len(), 5);
+    }
+
     #[test]
-    fn hash_data_utf8() {
-        let input = json!({ "data": "hello", "encoding": "utf-8" });
-        let (_buf, bytes) = read_buffer(&input).unwrap();
-        assert_eq!(bytes, 5);
+    fn hmac_matches_rfc_4231_vectors() {
+        let key = [0x0b; 20];
+        assert_eq!(
+            hex_digest(HashAlgorithm::Sha256, Some(&key), b"Hi There"),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        // Keys longer than the block size are hashed first.
+        let long_key = [0xaa; 131];
+        assert_eq!(
+            hex_digest(
+                HashAlgorithm::Sha512,
+                Some(&long_key),
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            ),
+            "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f352\
+             6b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598"
+        );
     }
 }
@@ -1,5 +1,8 @@
 use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
 use std::sync::{Arc, Mutex};
+use std::thread;
 use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Context as AnyhowContext, Result};
@@ -81,6 +84,14 @@ fn http_request_contract(ctx: &mut Context, input: Value, _meta: Option<Value>)
         }
     }
 
+    let response_mode = input
+        .get("responseMode")
+        .and_then(Value::as_str)
+        .unwrap_or("buffer");
+    if response_mode.eq_ignore_ascii_case("stream") {
+        return stream_response(ctx, easy, url);
+    }
+
     let response_body: Arc<Mutex<Vec<u8>>> = Arc::new(Mutex::new(Vec::new()));
     let header_map: Arc<Mutex<BTreeMap<String, Vec<String>>>> =
         Arc::new(Mutex::new(BTreeMap::new()));
@@ -127,38 +138,137 @@ fn http_request_contract(ctx: &mut Context, input: Value, _meta: Option<Value>)
         .map_err(|_| anyhow!("failed to acquire response body"))?
         .clone();
 
+    let mut output = response_head(status, header_map.clone(), duration_ms);
+    let (body_value, body_encoding) = encode_response_body(&header_map, &response_body);
+    output.insert("body".to_string(), body_value);
+    output.insert("bodyEncoding".to_string(), Value::String(body_encoding));
+
+    Ok(Value::Object(output))
+}
+
+fn response_head(
+    status: u16,
+    headers: BTreeMap<String, Vec<String>>,
+    duration_ms: f64,
+) -> Map<String, Value> {
     let mut output = Map::new();
     output.insert("status".to_string(), Value::Number(status.into()));
-    output.insert(
-        "headers".to_string(),
-        Value::Object(map_to_value(header_map.clone())),
-    );
+    output.insert("headers".to_string(), Value::Object(map_to_value(headers)));
     output.insert(
         "timings".to_string(),
         json!({
             "durationMs": Number::from_f64(duration_ms).unwrap_or_else(|| Number::from(0))
         }),
     );
+    output
+}
 
-    let response_mode = input
-        .get("responseMode")
-        .and_then(Value::as_str)
-        .unwrap_or("buffer");
+/// Status and headers of the response being received; a new status line
+/// (after a redirect) starts a fresh header block.
+#[derive(Clone, Default)]
+struct ResponseHead {
+    status: u16,
+    headers: BTreeMap<String, Vec<String>>,
+}
 
-    if response_mode.eq_ignore_ascii_case("stream") {
-        let chunks = chunk_bytes(&response_body);
-        let handle = ctx.streams_mut().register_chunks(chunks, "base64");
-        output.insert("stream".to_string(), handle);
-        output.insert(
-            "bodyEncoding".to_string(),
-            Value::String("base64".to_string()),
-        );
-    } else {
-        let (body_value, body_encoding) = encode_response_body(&header_map, &response_body);
-        output.insert("body".to_string(), body_value);
-        output.insert("bodyEncoding".to_string(), Value::String(body_encoding));
+type HeadSender = Arc<Mutex<Option<Sender<Result<ResponseHead>>>>>;
+
+fn announce_head(sender: &HeadSender, head: Result<ResponseHead>) {
+    if let Some(sender) = sender.lock().expect("response head lock poisoned").take() {
+        let _ = sender.send(head);
     }
+}
 
+/// Runs the transfer on a background thread and returns once the final
+/// headers are in. Body chunks are fed to a channel stream as curl delivers
+/// them; closing the stream or cancelling the context aborts the transfer.
+fn stream_response(ctx: &mut Context, mut easy: Easy, url: String) -> Result<Value> {
+    const BUFFERED_CHUNKS: usize = 16;
+    let head = Arc::new(Mutex::new(ResponseHead::default()));
+    let (head_tx, head_rx) = mpsc::channel();
+    let head_tx: HeadSender = Arc::new(Mutex::new(Some(head_tx)));
+    let (chunk_tx, chunk_rx) = mpsc::sync_channel::<Vec<u8>>(BUFFERED_CHUNKS);
+    let token = ctx.cancellation_token();
+    let stop = Arc::new(AtomicBool::new(false));
+
+    {
+        let head_ref = Arc::clone(&head);
+        easy.header_function(move |header| {
+            let Ok(text) = std::str::from_utf8(header) else {
+                return true;
+            };
+            let mut head = head_ref.lock().expect("response head lock poisoned");
+            if text.starts_with("HTTP/") {
+                let status = text
+                    .split_whitespace()
+                    .nth(1)
+                    .and_then(|code| code.parse().ok());
+                *head = ResponseHead {
+                    status: status.unwrap_or_default(),
+                    headers: BTreeMap::new(),
+                };
+            } else if let Some((name, value)) = text.split_once(':') {
+                let key = name.trim().to_ascii_lowercase();
+                head.headers
+                    .entry(key)
+                    .or_default()
+                    .push(value.trim().to_string());
+            }
+            true
+        })?;
+    }
+    {
+        let head_ref = Arc::clone(&head);
+        let head_tx = Arc::clone(&head_tx);
+        let stopped = Arc::clone(&stop);
+        easy.write_function(move |data| {
+            // Returning fewer bytes than offered makes curl abort the transfer.
+            if stopped.load(Ordering::SeqCst) || token.load(Ordering::SeqCst) {
+                return Ok(0);
+            }
+            let current = head_ref
+                .lock()
+                .expect("response head lock poisoned")
+                .clone();
+            announce_head(&head_tx, Ok(current));
+            if chunk_tx.send(data.to_vec()).is_err() {
+                return Ok(0);
+            }
+            Ok(data.len())
+        })?;
+    }
+
+    let start = Instant::now();
+    let transfer_url = url.clone();
+    thread::spawn(move || {
+        let outcome = easy
+            .perform()
+            .with_context(|| format!("HTTP request to {transfer_url} failed"))
+            .and_then(|_| Ok(easy.response_code()? as u16))
+            .map(|status| ResponseHead {
+                status,
+                headers: head
+                    .lock()
+                    .expect("response head lock poisoned")
+                    .headers
+                    .clone(),
+            });
+        // Only reached here when the body was empty or the transfer failed
+        // before the first chunk; otherwise the head was already announced.
+        announce_head(&head_tx, outcome);
+    });
+
+    let head = head_rx
+        .recv()
+        .map_err(|_| anyhow!("HTTP request to {url} ended without a response"))??;
+    let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let mut output = response_head(head.status, head.headers, duration_ms);
+    let handle = ctx.streams_mut().register_channel(chunk_rx, stop, "base64");
+    output.insert("stream".to_string(), handle);
+    output.insert(
+        "bodyEncoding".to_string(),
+        Value::String("base64".to_string()),
+    );
     Ok(Value::Object(output))
 }
 
@@ -316,17 +426,6 @@ fn encode_response_body(headers: &BTreeMap<String, Vec<String>>, bytes: &[u8]) -
     (Value::String(encoded), "base64".to_string())
 }
 
-fn chunk_bytes(bytes: &[u8]) -> Vec<Vec<u8>> {
-    const CHUNK_SIZE: usize = 64 * 1024;
-    if bytes.is_empty() {
-        return vec![Vec::new()];
-    }
-    bytes
-        .chunks(CHUNK_SIZE)
-        .map(|chunk| chunk.to_vec())
-        .collect()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -393,4 +492,44 @@ mod tests {
             .unwrap();
         assert_eq!(chunk["chunk"], json!("chunked payload"));
     }
+
+    #[test]
+    fn stream_returns_before_the_body_is_complete() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/slow", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            if let Ok((mut stream, _)) = listener.accept() {
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf);
+                let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nX-Part: head\r\n\r\nfirst;");
+                thread::sleep(Duration::from_millis(1500));
+                let _ = stream.write_all(b"second");
+            }
+        });
+
+        let registry = Registry::new();
+        register_http(&registry);
+        let mut ctx = registry.context();
+
+        let started = Instant::now();
+        let input = json!({ "url": url, "responseMode": "stream" });
+        let result = http_request_contract(&mut ctx, input, None).unwrap();
+        assert!(started.elapsed() < Duration::from_millis(1000));
+        assert_eq!(result["status"], json!(200));
+        assert_eq!(result["headers"]["x-part"], json!(["head"]));
+
+        let handle = result["stream"].clone();
+        let mut body = String::new();
+        loop {
+            let chunk = ctx
+                .streams_mut()
+                .read(&handle, None, Some("utf-8"))
+                .unwrap();
+            if chunk["done"] == json!(true) {
+                break;
+            }
+            body.push_str(chunk["chunk"].as_str().unwrap());
+        }
+        assert_eq!(body, "first;second");
+    }
 }
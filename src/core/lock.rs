@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context as AnyhowContext, Result};
+use serde_json::{json, Value};
+
+use super::fs::{from_unix_path, to_unix_path};
+use crate::fs_policy::FsAccess;
+use crate::registry::{CancelledError, Context, Registry};
+
+const CONTRACT_LOCK: &str = "lcod://contract/core/fs/lock@1";
+const CONTRACT_UNLOCK: &str = "lcod://contract/core/fs/unlock@1";
+
+pub fn register_lock(registry: &Registry) {
+    registry.register(CONTRACT_LOCK, lock_contract);
+    registry.register(CONTRACT_UNLOCK, unlock_contract);
+}
+
+#[derive(Debug)]
+pub struct LockTimeoutError {
+    pub path: PathBuf,
+    pub waited: Duration,
+}
+
+impl fmt::Display for LockTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "timed out after {} ms waiting for lock on {}",
+            self.waited.as_millis(),
+            self.path.display()
+        )
+    }
+}
+
+impl std::error::Error for LockTimeoutError {}
+
+/// Advisory lock on `<path>.lock`, released on drop. Locking a sidecar keeps
+/// the lock valid across atomic replacements of `path` itself; the sidecar is
+/// never removed, since unlinking it would let a waiter and a newcomer each
+/// lock a different file.
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+    lock_path: PathBuf,
+}
+
+impl FileLock {
+    pub fn acquire(
+        path: &Path,
+        shared: bool,
+        timeout: Option<Duration>,
+        cancellation: Option<&AtomicBool>,
+    ) -> Result<Self> {
+        let lock_path = lock_path_for(path);
+        if let Some(parent) = lock_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("unable to create lock directory {}", parent.display()))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("unable to open lock file {}", lock_path.display()))?;
+
+        let started = Instant::now();
+        let mut backoff = Duration::from_millis(5);
+        loop {
+            let attempt = if shared {
+                file.try_lock_shared()
+            } else {
+                file.try_lock()
+            };
+            match attempt {
+                Ok(()) => return Ok(Self { file, lock_path }),
+                Err(TryLockError::WouldBlock) => {}
+                Err(TryLockError::Error(err)) => {
+                    return Err(
+                        anyhow!(err).context(format!("unable to lock {}", lock_path.display()))
+                    )
+                }
+            }
+            if cancellation.is_some_and(|flag| flag.load(Ordering::SeqCst)) {
+                return Err(CancelledError.into());
+            }
+            let waited = started.elapsed();
+            if timeout.is_some_and(|limit| waited >= limit) {
+                return Err(LockTimeoutError {
+                    path: path.to_path_buf(),
+                    waited,
+                }
+                .into());
+            }
+            let pause = match timeout {
+                Some(limit) => backoff.min(limit.saturating_sub(waited)),
+                None => backoff,
+            };
+            thread::sleep(pause);
+            backoff = (backoff * 2).min(Duration::from_millis(100));
+        }
+    }
+
+    pub fn lock_path(&self) -> &Path {
+        &self.lock_path
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+pub fn lock_path_for(path: &Path) -> PathBuf {
+    let mut name = path
+        .file_name()
+        .map(|name| name.to_os_string())
+        .unwrap_or_else(|| OsString::from("dir"));
+    name.push(".lock");
+    path.with_file_name(name)
+}
+
+/// Locks taken through `core/fs/lock@1`, shared between a context and its
+/// forks; whatever is still held is released when the last one is dropped.
+#[derive(Clone, Debug, Default)]
+pub struct FileLocks {
+    held: Arc<Mutex<HashMap<String, FileLock>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl FileLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn insert(&self, lock: FileLock) -> String {
+        let id = format!("lock-{}", self.next_id.fetch_add(1, Ordering::SeqCst) + 1);
+        self.held
+            .lock()
+            .expect("file locks poisoned")
+            .insert(id.clone(), lock);
+        id
+    }
+
+    fn release(&self, id: &str) -> bool {
+        self.held
+            .lock()
+            .expect("file locks poisoned")
+            .remove(id)
+            .is_some()
+    }
+}
+
+fn lock_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let path = input
+        .get("path")
+        .and_then(Value::as_str)
+        .map(from_unix_path)
+        .ok_or_else(|| anyhow!("missing or invalid `path`"))?;
+    let shared = input
+        .get("shared")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    let access = if shared {
+        FsAccess::Read
+    } else {
+        FsAccess::Write
+    };
+    ctx.check_fs_access(&path, access)?;
+    let timeout = input
+        .get("timeoutMs")
+        .and_then(Value::as_u64)
+        .map(Duration::from_millis);
+
+    let started = Instant::now();
+    let token = ctx.cancellation_token();
+    let lock = FileLock::acquire(&path, shared, timeout, Some(token.as_ref()))?;
+    let lock_path = to_unix_path(lock.lock_path());
+    let id = ctx.file_locks().insert(lock);
+    Ok(json!({
+        "lock": id,
+        "path": to_unix_path(&path),
+        "lockPath": lock_path,
+        "shared": shared,
+        "waitedMs": started.elapsed().as_millis() as u64
+    }))
+}
+
+fn unlock_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let id = input
+        .get("lock")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("missing or invalid `lock`"))?;
+    Ok(json!({ "released": ctx.file_locks().release(id) }))
+}
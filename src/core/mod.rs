@@ -1,10 +1,13 @@
 pub mod array;
 pub mod env;
+pub mod format;
 pub mod fs;
 pub mod git;
+pub mod glob;
 pub mod hash;
 pub mod http;
 pub mod json;
+pub mod lock;
 pub mod number;
 pub mod object;
 pub mod parse;
@@ -13,7 +16,9 @@ pub mod runtime;
 pub mod state;
 pub mod streams;
 pub mod string;
+pub mod temp;
 pub mod value;
+pub mod watch;
 
 use crate::registry::Registry;
 
@@ -22,11 +27,16 @@ use crate::registry::Registry;
 /// to specific domains (e.g. filesystem, http, ...).
 pub fn register_core(registry: &Registry) {
     fs::register_fs(registry);
+    glob::register_glob(registry);
+    lock::register_lock(registry);
+    temp::register_temp(registry);
+    watch::register_watch(registry);
     env::register_env(registry);
     git::register_git(registry);
     hash::register_hash(registry);
     http::register_http(registry);
     parse::register_parse(registry);
+    format::register_format(registry);
     path::register_path(registry);
     streams::register_streams(registry);
     array::register_array(registry);
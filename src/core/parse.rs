@@ -1,26 +1,40 @@
 use std::fs;
+use std::io::{BufRead, BufReader};
 use std::path::Path;
 
 use anyhow::{anyhow, Result};
 use std::collections::HashMap;
 
 use csv::Trim;
+use serde::Deserialize;
 use serde_json::{json, Map, Value};
 
+use crate::fs_policy::FsAccess;
 use crate::registry::{Context, Registry};
+use crate::streams::{DetachedStream, RecordBatch, RecordSource};
 
 const CONTRACT_JSON: &str = "lcod://contract/core/parse/json@1";
 const CONTRACT_TOML: &str = "lcod://contract/core/parse/toml@1";
 const CONTRACT_CSV: &str = "lcod://contract/core/parse/csv@1";
+const CONTRACT_YAML: &str = "lcod://contract/core/parse/yaml@1";
+const CONTRACT_NDJSON: &str = "lcod://contract/core/parse/ndjson@1";
+const CONTRACT_NEXT: &str = "lcod://contract/core/parse/next@1";
+const CONTRACT_CLOSE: &str = "lcod://contract/core/parse/close@1";
+
+const DEFAULT_BATCH: usize = 1000;
 
 pub fn register_parse(registry: &Registry) {
     registry.register(CONTRACT_JSON, parse_json_contract);
     registry.register(CONTRACT_TOML, parse_toml_contract);
     registry.register(CONTRACT_CSV, parse_csv_contract);
+    registry.register(CONTRACT_YAML, parse_yaml_contract);
+    registry.register(CONTRACT_NDJSON, parse_ndjson_contract);
+    registry.register(CONTRACT_NEXT, parse_next_contract);
+    registry.register(CONTRACT_CLOSE, parse_close_contract);
 }
 
-fn parse_json_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
-    let (text, bytes) = read_text(&input)?;
+fn parse_json_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let (text, bytes) = read_text(ctx, &input)?;
     let value: Value =
         serde_json::from_str(&text).map_err(|err| anyhow!("JSON parse error: {err}"))?;
     Ok(json!({
@@ -30,8 +44,8 @@ fn parse_json_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -
     }))
 }
 
-fn parse_toml_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
-    let (text, bytes) = read_text(&input)?;
+fn parse_toml_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let (text, bytes) = read_text(ctx, &input)?;
     let value: toml::Value = text
         .parse()
         .map_err(|err| anyhow!("TOML parse error: {err}"))?;
@@ -42,8 +56,86 @@ fn parse_toml_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -
     }))
 }
 
-fn parse_csv_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
-    let (text, bytes) = read_text(&input)?;
+/// Aliases are always expanded; `mergeKeys: false` keeps `<<` merge keys as
+/// plain entries instead of merging the referenced mappings.
+fn parse_yaml_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let (text, bytes) = read_text(ctx, &input)?;
+    let multi_document = input
+        .get("multiDocument")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    let merge_keys = input
+        .get("mergeKeys")
+        .and_then(Value::as_bool)
+        .unwrap_or(true);
+
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(&text) {
+        let mut value = serde_yaml::Value::deserialize(document)
+            .map_err(|err| anyhow!("YAML parse error: {err}"))?;
+        if merge_keys {
+            value
+                .apply_merge()
+                .map_err(|err| anyhow!("YAML merge error: {err}"))?;
+        }
+        documents.push(yaml_to_json(value)?);
+    }
+
+    if multi_document {
+        return Ok(json!({
+            "value": documents.first().cloned().unwrap_or(Value::Null),
+            "documents": documents,
+            "bytes": bytes
+        }));
+    }
+    if documents.len() > 1 {
+        return Err(anyhow!(
+            "YAML input contains {} documents; set `multiDocument` to read them all",
+            documents.len()
+        ));
+    }
+    Ok(json!({
+        "value": documents.pop().unwrap_or(Value::Null),
+        "bytes": bytes
+    }))
+}
+
+/// JSON has string keys only, so scalar YAML keys (`1:`, `true:`) are
+/// rendered as strings and tags are dropped in favour of the tagged value.
+fn yaml_to_json(value: serde_yaml::Value) -> Result<Value> {
+    Ok(match value {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(flag) => Value::Bool(flag),
+        serde_yaml::Value::Number(number) => serde_json::to_value(number)?,
+        serde_yaml::Value::String(text) => Value::String(text),
+        serde_yaml::Value::Sequence(items) => {
+            Value::Array(items.into_iter().map(yaml_to_json).collect::<Result<_>>()?)
+        }
+        serde_yaml::Value::Mapping(mapping) => {
+            let mut object = Map::new();
+            for (key, item) in mapping {
+                let key = match key {
+                    serde_yaml::Value::String(text) => text,
+                    serde_yaml::Value::Null => "null".to_string(),
+                    serde_yaml::Value::Bool(flag) => flag.to_string(),
+                    serde_yaml::Value::Number(number) => number.to_string(),
+                    other => {
+                        return Err(anyhow!(
+                            "unsupported YAML mapping key: {}",
+                            serde_yaml::to_string(&other)?.trim_end()
+                        ))
+                    }
+                };
+                object.insert(key, yaml_to_json(item)?);
+            }
+            Value::Object(object)
+        }
+        serde_yaml::Value::Tagged(tagged) => yaml_to_json(tagged.value)?,
+    })
+}
+
+/// Delimiter, quote and trim settings shared by the text and stream modes.
+fn csv_reader_builder(input: &Value) -> csv::ReaderBuilder {
     let delimiter = input
         .get("delimiter")
         .and_then(Value::as_str)
@@ -56,6 +148,17 @@ fn parse_csv_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) ->
         .unwrap_or(b'"');
     let trim = input.get("trim").and_then(Value::as_bool).unwrap_or(false);
 
+    let mut reader_builder = csv::ReaderBuilder::new();
+    reader_builder
+        .delimiter(delimiter)
+        .quote(quote)
+        .trim(if trim { Trim::All } else { Trim::None });
+    reader_builder
+}
+
+/// `header` is either the list of column names or `true` to read them from
+/// the first record.
+fn csv_header(input: &Value) -> (Option<Vec<String>>, bool) {
     let header_value = input.get("header");
     let columns_from_header = header_value.and_then(Value::as_array).map(|arr| {
         arr.iter()
@@ -64,12 +167,28 @@ fn parse_csv_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) ->
             .collect::<Vec<_>>()
     });
     let use_header = header_value.and_then(Value::as_bool).unwrap_or(false);
+    (columns_from_header, use_header)
+}
 
-    let mut reader_builder = csv::ReaderBuilder::new();
-    reader_builder
-        .delimiter(delimiter)
-        .quote(quote)
-        .trim(if trim { Trim::All } else { Trim::None });
+fn parse_csv_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    if let Some(stream) = input.get("stream") {
+        let (columns, use_header) = csv_header(&input);
+        let reader = csv_reader_builder(&input)
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(ctx.streams_mut().detach(stream)?);
+        let cursor = CsvCursor {
+            reader,
+            columns,
+            read_header: use_header,
+        };
+        let handle = ctx.streams_mut().register_cursor(Box::new(cursor), "csv");
+        return Ok(json!({ "cursor": handle }));
+    }
+
+    let (text, bytes) = read_text(ctx, &input)?;
+    let (columns_from_header, use_header) = csv_header(&input);
+    let mut reader_builder = csv_reader_builder(&input);
 
     let rows_value;
     let columns_value;
@@ -131,12 +250,150 @@ fn parse_csv_contract(_ctx: &mut Context, input: Value, _meta: Option<Value>) ->
     Ok(Value::Object(result))
 }
 
-fn read_text(input: &Value) -> Result<(String, usize)> {
+/// Blank lines are skipped; a line that is not valid JSON becomes a warning
+/// instead of failing the whole input.
+fn parse_ndjson_line(line: &str, number: u64) -> std::result::Result<Option<Value>, String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str(line)
+        .map(Some)
+        .map_err(|err| format!("invalid JSON on line {number}: {err}"))
+}
+
+fn parse_ndjson_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    if let Some(stream) = input.get("stream") {
+        let cursor = NdjsonCursor {
+            reader: BufReader::new(ctx.streams_mut().detach(stream)?),
+            line: 0,
+        };
+        let handle = ctx
+            .streams_mut()
+            .register_cursor(Box::new(cursor), "ndjson");
+        return Ok(json!({ "cursor": handle }));
+    }
+
+    let (text, bytes) = read_text(ctx, &input)?;
+    let mut rows = Vec::new();
+    let mut warnings = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        match parse_ndjson_line(line, index as u64 + 1) {
+            Ok(Some(row)) => rows.push(row),
+            Ok(None) => {}
+            Err(warning) => warnings.push(warning),
+        }
+    }
+    let mut result = Map::new();
+    result.insert("rows".to_string(), Value::Array(rows));
+    result.insert("bytes".to_string(), json!(bytes));
+    if !warnings.is_empty() {
+        result.insert("warnings".to_string(), json!(warnings));
+    }
+    Ok(Value::Object(result))
+}
+
+/// Pulls the next batch (`max` rows, default 1000) from a cursor returned by
+/// `core/parse/ndjson@1` or `core/parse/csv@1` in stream mode.
+fn parse_next_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let cursor = input
+        .get("cursor")
+        .ok_or_else(|| anyhow!("missing `cursor`"))?;
+    let max = input
+        .get("max")
+        .and_then(Value::as_u64)
+        .map(|max| max as usize)
+        .unwrap_or(DEFAULT_BATCH);
+    ctx.ensure_not_cancelled()?;
+    ctx.streams_mut().next_records(cursor, max)
+}
+
+fn parse_close_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let cursor = input
+        .get("cursor")
+        .ok_or_else(|| anyhow!("missing `cursor`"))?;
+    ctx.streams_mut().close_cursor(cursor)
+}
+
+struct NdjsonCursor {
+    reader: BufReader<DetachedStream>,
+    line: u64,
+}
+
+impl RecordSource for NdjsonCursor {
+    fn next_batch(&mut self, max: usize) -> Result<RecordBatch> {
+        let mut batch = RecordBatch::default();
+        let mut buffer = Vec::new();
+        while batch.rows.len() < max {
+            buffer.clear();
+            if self.reader.read_until(b'\n', &mut buffer)? == 0 {
+                batch.done = true;
+                break;
+            }
+            self.line += 1;
+            let line = String::from_utf8_lossy(&buffer);
+            match parse_ndjson_line(&line, self.line) {
+                Ok(Some(row)) => batch.rows.push(row),
+                Ok(None) => {}
+                Err(warning) => batch.warnings.push(warning),
+            }
+        }
+        Ok(batch)
+    }
+}
+
+/// Records are read one at a time, so quoted fields spanning stream chunks
+/// are handled by the CSV reader's own buffering.
+struct CsvCursor {
+    reader: csv::Reader<DetachedStream>,
+    columns: Option<Vec<String>>,
+    read_header: bool,
+}
+
+impl RecordSource for CsvCursor {
+    fn next_batch(&mut self, max: usize) -> Result<RecordBatch> {
+        let mut batch = RecordBatch::default();
+        let mut record = csv::StringRecord::new();
+        while batch.rows.len() < max {
+            if !self.reader.read_record(&mut record)? {
+                batch.done = true;
+                break;
+            }
+            if self.read_header && self.columns.is_none() {
+                self.columns = Some(record.iter().map(String::from).collect());
+                continue;
+            }
+            let row = match &self.columns {
+                Some(columns) => Value::Object(
+                    columns
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, column)| {
+                            let value = record.get(idx).unwrap_or("");
+                            (column.clone(), Value::String(value.to_string()))
+                        })
+                        .collect(),
+                ),
+                None => Value::Array(
+                    record
+                        .iter()
+                        .map(|s| Value::String(s.to_string()))
+                        .collect(),
+                ),
+            };
+            batch.rows.push(row);
+        }
+        Ok(batch)
+    }
+}
+
+fn read_text(ctx: &Context, input: &Value) -> Result<(String, usize)> {
     if let Some(text) = input.get("text").and_then(Value::as_str) {
         let bytes = text.as_bytes().len();
         return Ok((text.to_string(), bytes));
     }
     if let Some(path) = input.get("path").and_then(Value::as_str) {
+        ctx.check_fs_access(Path::new(path), FsAccess::Read)?;
         let content = fs::read_to_string(Path::new(path))
             .map_err(|err| anyhow!("unable to read file `{path}`: {err}"))?;
         let bytes = content.as_bytes().len();
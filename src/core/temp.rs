@@ -0,0 +1,150 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Context as AnyhowContext, Result};
+use serde_json::{json, Value};
+
+use super::fs::{from_unix_path, to_unix_path};
+use crate::fs_policy::FsAccess;
+use crate::registry::{Context, Registry};
+
+const CONTRACT_TEMP_DIR: &str = "lcod://contract/core/fs/temp-dir@1";
+const CONTRACT_TEMP_DIR_ALT: &str = "lcod://contract/core/fs/temp_dir@1";
+const CONTRACT_TEMP_FILE: &str = "lcod://contract/core/fs/temp-file@1";
+const CONTRACT_TEMP_FILE_ALT: &str = "lcod://contract/core/fs/temp_file@1";
+
+pub fn register_temp(registry: &Registry) {
+    registry.register(CONTRACT_TEMP_DIR, temp_dir_contract);
+    registry.register(CONTRACT_TEMP_DIR_ALT, temp_dir_contract);
+    registry.register(CONTRACT_TEMP_FILE, temp_file_contract);
+    registry.register(CONTRACT_TEMP_FILE_ALT, temp_file_contract);
+}
+
+#[derive(Debug, Default)]
+struct Tracked {
+    entries: Vec<(Option<u64>, PathBuf)>,
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        for (_, path) in self.entries.drain(..).rev() {
+            remove_path(&path);
+        }
+    }
+}
+
+/// Temporary paths created during a run, each tagged with the scope that was
+/// innermost when it was created. Shared between a context and its forks;
+/// whatever is left is removed when the last of them is dropped.
+#[derive(Clone, Debug, Default)]
+pub struct TempPaths {
+    tracked: Arc<Mutex<Tracked>>,
+    next_scope: Arc<AtomicU64>,
+}
+
+impl TempPaths {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn track(&self, path: PathBuf, scope: Option<u64>) {
+        self.tracked
+            .lock()
+            .expect("temp paths poisoned")
+            .entries
+            .push((scope, path));
+    }
+
+    pub fn open_scope(&self) -> u64 {
+        self.next_scope.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Removes every path created while `scope` was innermost.
+    pub fn close_scope(&self, scope: u64) {
+        let released: Vec<PathBuf> = {
+            let mut tracked = self.tracked.lock().expect("temp paths poisoned");
+            let (released, kept) = tracked
+                .entries
+                .drain(..)
+                .partition(|(owner, _)| *owner == Some(scope));
+            tracked.entries = kept;
+            released.into_iter().map(|(_, path)| path).collect()
+        };
+        for path in released.iter().rev() {
+            remove_path(path);
+        }
+    }
+}
+
+fn remove_path(path: &Path) {
+    let _ = match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(_) => Ok(()),
+    };
+}
+
+struct TempRequest {
+    prefix: String,
+    suffix: String,
+    parent: PathBuf,
+    keep: bool,
+}
+
+fn parse_request(ctx: &Context, input: &Value) -> Result<TempRequest> {
+    let text = |key: &str| input.get(key).and_then(Value::as_str);
+    let parent = match text("dir") {
+        Some(dir) => from_unix_path(dir),
+        None => std::env::temp_dir(),
+    };
+    ctx.check_fs_access(&parent, FsAccess::Write)?;
+    Ok(TempRequest {
+        prefix: text("prefix").unwrap_or("lcod-").to_string(),
+        suffix: text("suffix").unwrap_or_default().to_string(),
+        parent,
+        keep: input.get("keep").and_then(Value::as_bool).unwrap_or(false),
+    })
+}
+
+fn finish(ctx: &Context, path: PathBuf, keep: bool) -> Value {
+    let rendered = to_unix_path(&path);
+    if !keep {
+        ctx.track_temp_path(path);
+    }
+    json!({ "path": rendered, "keep": keep })
+}
+
+fn temp_dir_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let request = parse_request(ctx, &input)?;
+    let dir = tempfile::Builder::new()
+        .prefix(&request.prefix)
+        .suffix(&request.suffix)
+        .tempdir_in(&request.parent)
+        .with_context(|| {
+            format!(
+                "unable to create temporary directory in {}",
+                request.parent.display()
+            )
+        })?;
+    Ok(finish(ctx, dir.keep(), request.keep))
+}
+
+fn temp_file_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let request = parse_request(ctx, &input)?;
+    let file = tempfile::Builder::new()
+        .prefix(&request.prefix)
+        .suffix(&request.suffix)
+        .tempfile_in(&request.parent)
+        .with_context(|| {
+            format!(
+                "unable to create temporary file in {}",
+                request.parent.display()
+            )
+        })?;
+    let (_, path) = file
+        .keep()
+        .map_err(|err| anyhow!(err.error).context("unable to keep temporary file"))?;
+    Ok(finish(ctx, path, request.keep))
+}
@@ -0,0 +1,316 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+use super::fs::{from_unix_path, to_unix_path};
+use crate::fs_policy::FsAccess;
+use crate::registry::{Context, Registry};
+
+const CONTRACT_WATCH: &str = "lcod://contract/core/fs/watch@1";
+
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(250);
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(100);
+const TICK: Duration = Duration::from_millis(20);
+
+pub fn register_watch(registry: &Registry) {
+    registry.register(CONTRACT_WATCH, watch_contract);
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct EntryState {
+    is_dir: bool,
+    len: u64,
+    modified: Option<SystemTime>,
+    /// Device and inode, used to pair a removal with a creation as a rename.
+    identity: Option<(u64, u64)>,
+}
+
+impl EntryState {
+    fn from_metadata(metadata: &fs::Metadata) -> Self {
+        #[cfg(unix)]
+        let identity = {
+            use std::os::unix::fs::MetadataExt;
+            Some((metadata.dev(), metadata.ino()))
+        };
+        #[cfg(not(unix))]
+        let identity = None;
+        Self {
+            is_dir: metadata.is_dir(),
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            identity,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        if self.is_dir {
+            "directory"
+        } else {
+            "file"
+        }
+    }
+}
+
+type Snapshot = BTreeMap<PathBuf, EntryState>;
+
+/// Polling watcher: compares successive snapshots of the watched paths, so
+/// it behaves the same on every platform and filesystem.
+struct Poller {
+    roots: Vec<PathBuf>,
+    recursive: bool,
+    interval: Duration,
+    debounce: Duration,
+    previous: Snapshot,
+}
+
+impl Poller {
+    fn new(roots: Vec<PathBuf>, recursive: bool, interval: Duration, debounce: Duration) -> Self {
+        let mut poller = Self {
+            roots,
+            recursive,
+            interval,
+            debounce,
+            previous: Snapshot::new(),
+        };
+        poller.previous = poller.snapshot();
+        poller
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let mut snapshot = Snapshot::new();
+        for root in &self.roots {
+            let Ok(metadata) = fs::metadata(root) else {
+                continue;
+            };
+            let is_dir = metadata.is_dir();
+            snapshot.insert(root.clone(), EntryState::from_metadata(&metadata));
+            if is_dir {
+                collect_entries(root, self.recursive, &mut snapshot);
+            }
+        }
+        snapshot
+    }
+
+    fn poll(&mut self) -> Vec<Value> {
+        let current = self.snapshot();
+        let events = diff(&self.previous, &current);
+        self.previous = current;
+        events
+    }
+
+    /// Waits for the next change, then keeps collecting until the paths have
+    /// been quiet for the debounce window. `None` once `cancelled` reports
+    /// true.
+    fn next_batch(&mut self, cancelled: &dyn Fn() -> bool) -> Option<Vec<Value>> {
+        let mut events = loop {
+            if !sleep_unless(self.interval, cancelled) {
+                return None;
+            }
+            let events = self.poll();
+            if !events.is_empty() {
+                break events;
+            }
+        };
+        let step = self.interval.min(self.debounce);
+        let mut quiet_since = Instant::now();
+        while quiet_since.elapsed() < self.debounce {
+            if !sleep_unless(step, cancelled) {
+                return None;
+            }
+            let more = self.poll();
+            if !more.is_empty() {
+                events.extend(more);
+                quiet_since = Instant::now();
+            }
+        }
+        Some(events)
+    }
+}
+
+fn collect_entries(dir: &Path, recursive: bool, snapshot: &mut Snapshot) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // Symlinked directories are recorded but not descended into, which
+        // keeps cycles out of the walk.
+        let Ok(link_meta) = fs::symlink_metadata(&path) else {
+            continue;
+        };
+        let metadata = fs::metadata(&path).unwrap_or(link_meta.clone());
+        snapshot.insert(path.clone(), EntryState::from_metadata(&metadata));
+        if recursive && link_meta.is_dir() {
+            collect_entries(&path, recursive, snapshot);
+        }
+    }
+}
+
+fn diff(previous: &Snapshot, current: &Snapshot) -> Vec<Value> {
+    let mut removed: Vec<(&PathBuf, &EntryState)> = previous
+        .iter()
+        .filter(|(path, _)| !current.contains_key(*path))
+        .collect();
+    let mut created: Vec<(&PathBuf, &EntryState)> = current
+        .iter()
+        .filter(|(path, _)| !previous.contains_key(*path))
+        .collect();
+
+    let mut events = Vec::new();
+    removed.retain(|(from, old)| {
+        let Some(identity) = old.identity else {
+            return true;
+        };
+        match created
+            .iter()
+            .position(|(_, new)| new.identity == Some(identity))
+        {
+            Some(index) => {
+                let (path, new) = created.remove(index);
+                events.push(json!({
+                    "type": "renamed",
+                    "from": to_unix_path(from),
+                    "path": to_unix_path(path),
+                    "kind": new.kind()
+                }));
+                false
+            }
+            None => true,
+        }
+    });
+
+    let event = |kind: &str, path: &Path, state: &EntryState| {
+        json!({
+            "type": kind,
+            "path": to_unix_path(path),
+            "kind": state.kind()
+        })
+    };
+    events.extend(
+        created
+            .into_iter()
+            .map(|(path, state)| event("created", path, state)),
+    );
+    events.extend(current.iter().filter_map(|(path, state)| {
+        let old = previous.get(path)?;
+        let changed = !state.is_dir && (old.len != state.len || old.modified != state.modified);
+        changed.then(|| event("modified", path, state))
+    }));
+    events.extend(
+        removed
+            .into_iter()
+            .map(|(path, state)| event("removed", path, state)),
+    );
+    events
+}
+
+/// Sleeps in short ticks; false when `cancelled` fired before the end.
+fn sleep_unless(duration: Duration, cancelled: &dyn Fn() -> bool) -> bool {
+    let deadline = Instant::now() + duration;
+    loop {
+        if cancelled() {
+            return false;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        thread::sleep((deadline - now).min(TICK));
+    }
+}
+
+fn duration_input(input: &Value, key: &str, default: Duration) -> Duration {
+    input
+        .get(key)
+        .and_then(Value::as_u64)
+        .map(Duration::from_millis)
+        .unwrap_or(default)
+}
+
+fn watch_contract(ctx: &mut Context, input: Value, meta: Option<Value>) -> Result<Value> {
+    let roots: Vec<PathBuf> = match input.get("paths").or_else(|| input.get("path")) {
+        Some(Value::String(path)) => vec![from_unix_path(path)],
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(from_unix_path)
+                    .ok_or_else(|| anyhow!("`paths` must contain strings"))
+            })
+            .collect::<Result<_>>()?,
+        _ => return Err(anyhow!("missing or invalid `paths`")),
+    };
+    for root in &roots {
+        ctx.check_fs_access(root, FsAccess::Read)?;
+    }
+    let recursive = input
+        .get("recursive")
+        .and_then(Value::as_bool)
+        .unwrap_or(true);
+    let interval = duration_input(&input, "intervalMs", DEFAULT_INTERVAL).max(TICK);
+    let debounce = duration_input(&input, "debounceMs", DEFAULT_DEBOUNCE);
+    let max_batches = input.get("maxBatches").and_then(Value::as_u64);
+    let watched: Vec<Value> = roots
+        .iter()
+        .map(|root| Value::String(to_unix_path(root)))
+        .collect();
+
+    let mut poller = Poller::new(roots, recursive, interval, debounce);
+    let has_on_change = meta
+        .as_ref()
+        .and_then(|meta| meta.get("children"))
+        .and_then(|children| children.get("onChange"))
+        .is_some();
+
+    if has_on_change {
+        let mut batches = 0u64;
+        let mut total = 0usize;
+        while max_batches.is_none_or(|limit| batches < limit) {
+            let token = ctx.cancellation_token();
+            let Some(events) = poller.next_batch(&|| token.load(Ordering::SeqCst)) else {
+                ctx.ensure_not_cancelled()?;
+                break;
+            };
+            total += events.len();
+            let vars = json!({ "events": events, "batch": batches });
+            batches += 1;
+            ctx.run_slot("onChange", None, Some(vars))?;
+        }
+        return Ok(json!({ "paths": watched, "batches": batches, "events": total }));
+    }
+
+    // Stream mode: one NDJSON chunk per debounced batch, produced until the
+    // context is cancelled, `maxBatches` is reached or the stream is closed.
+    let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+    let token: Arc<AtomicBool> = ctx.cancellation_token();
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = Arc::clone(&stop);
+    thread::spawn(move || {
+        let cancelled = || token.load(Ordering::SeqCst) || stopped.load(Ordering::SeqCst);
+        let mut batches = 0u64;
+        while max_batches.is_none_or(|limit| batches < limit) {
+            let Some(events) = poller.next_batch(&cancelled) else {
+                break;
+            };
+            let mut chunk = Vec::new();
+            for event in events {
+                chunk.extend_from_slice(event.to_string().as_bytes());
+                chunk.push(b'\n');
+            }
+            if sender.send(chunk).is_err() {
+                break;
+            }
+            batches += 1;
+        }
+    });
+    let stream = ctx.streams_mut().register_channel(receiver, stop, "utf-8");
+    Ok(json!({ "paths": watched, "stream": stream }))
+}
@@ -0,0 +1,124 @@
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Number, Value};
+
+use super::{collect_path_value, FlowSignalError};
+use crate::registry::Context;
+
+/// Splits `items` into batches of at most `size` items and `max_bytes` bytes
+/// of serialized JSON. An item larger than the byte budget gets a batch of
+/// its own.
+fn split_batches(
+    items: Vec<Value>,
+    size: Option<usize>,
+    max_bytes: Option<usize>,
+) -> Result<Vec<Vec<Value>>> {
+    let mut batches = Vec::new();
+    let mut current: Vec<Value> = Vec::new();
+    let mut current_bytes = 0;
+    for item in items {
+        let item_bytes = match max_bytes {
+            Some(_) => serde_json::to_vec(&item)?.len(),
+            None => 0,
+        };
+        let full_by_size = size.is_some_and(|size| current.len() >= size);
+        let full_by_bytes = max_bytes.is_some_and(|limit| current_bytes + item_bytes > limit);
+        if !current.is_empty() && (full_by_size || full_by_bytes) {
+            batches.push(std::mem::take(&mut current));
+            current_bytes = 0;
+        }
+        current_bytes += item_bytes;
+        current.push(item);
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+    Ok(batches)
+}
+
+fn positive_limit(input: &Value, field: &str) -> Result<Option<usize>> {
+    match input.get(field) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => match value.as_u64() {
+            Some(limit) if limit > 0 => Ok(Some(limit as usize)),
+            _ => Err(anyhow!(
+                "flow/batch: `{field}` must be a positive integer, got {value}"
+            )),
+        },
+    }
+}
+
+/// Runs `body` once per chunk of `list`. Results collected through
+/// `collectPath` are concatenated when `flatten` is set (the default) and
+/// kept one entry per batch otherwise.
+pub fn flow_batch(ctx: &mut Context, input: Value, meta: Option<Value>) -> Result<Value> {
+    let items = match input.get("list") {
+        Some(Value::Array(items)) => items.clone(),
+        Some(Value::Null) | None => Vec::new(),
+        Some(other) => {
+            return Err(anyhow!(
+                "flow/batch: expected array for `list`, got {}",
+                other
+            ))
+        }
+    };
+    let size = positive_limit(&input, "size")?;
+    let max_bytes = positive_limit(&input, "maxBytes")?;
+    if size.is_none() && max_bytes.is_none() {
+        return Err(anyhow!("flow/batch: `size` or `maxBytes` is required"));
+    }
+    let flatten = input
+        .get("flatten")
+        .and_then(Value::as_bool)
+        .unwrap_or(true);
+    let collect_path = meta
+        .as_ref()
+        .and_then(|m| m.get("collectPath"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    let batches = split_batches(items, size, max_bytes)?;
+    let batch_count = batches.len();
+    let mut results = Vec::new();
+    let mut offset = 0;
+    for (batch_index, batch) in batches.into_iter().enumerate() {
+        ctx.ensure_not_cancelled()?;
+        let batch_len = batch.len();
+        let mut slot_vars = Map::new();
+        slot_vars.insert("batch".to_string(), Value::Array(batch.clone()));
+        slot_vars.insert(
+            "batchIndex".to_string(),
+            Value::Number(Number::from(batch_index as u64)),
+        );
+        slot_vars.insert(
+            "offset".to_string(),
+            Value::Number(Number::from(offset as u64)),
+        );
+        offset += batch_len;
+
+        let collected = match ctx.run_slot("body", None, Some(Value::Object(slot_vars.clone()))) {
+            Ok(iter_state) => match collect_path.as_deref() {
+                Some(path) => {
+                    collect_path_value(path, &iter_state, &slot_vars).unwrap_or(Value::Null)
+                }
+                None => Value::Array(batch),
+            },
+            Err(err) => match err.downcast_ref::<FlowSignalError>() {
+                Some(signal) if signal.is("continue") => continue,
+                Some(signal) if signal.is("break") => break,
+                _ => return Err(err),
+            },
+        };
+        match collected {
+            Value::Array(values) if flatten => results.extend(values),
+            value => results.push(value),
+        }
+    }
+
+    let mut out = Map::new();
+    out.insert("results".to_string(), Value::Array(results));
+    out.insert(
+        "batches".to_string(),
+        Value::Number(Number::from(batch_count as u64)),
+    );
+    Ok(Value::Object(out))
+}
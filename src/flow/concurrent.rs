@@ -0,0 +1,144 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Number, Value};
+
+use super::{collect_path_value, FlowSignalError};
+use crate::checkpoint;
+use crate::registry::{CancelledError, Context};
+
+enum ItemOutcome {
+    Collected(Value),
+    Skipped,
+    Break,
+    Failed(anyhow::Error),
+}
+
+fn run_item(
+    worker: &mut Context,
+    index: usize,
+    item: Value,
+    collect_path: Option<&str>,
+) -> ItemOutcome {
+    let mut slot_vars = Map::new();
+    slot_vars.insert("item".to_string(), item.clone());
+    slot_vars.insert(
+        "index".to_string(),
+        Value::Number(Number::from(index as i64)),
+    );
+    match worker.run_slot("body", None, Some(Value::Object(slot_vars.clone()))) {
+        Ok(iter_state) => ItemOutcome::Collected(match collect_path {
+            Some(path) => collect_path_value(path, &iter_state, &slot_vars).unwrap_or(Value::Null),
+            None => item,
+        }),
+        Err(err) => match err.downcast_ref::<FlowSignalError>() {
+            Some(signal) if signal.is("continue") => ItemOutcome::Skipped,
+            Some(signal) if signal.is("break") => ItemOutcome::Break,
+            _ => ItemOutcome::Failed(err),
+        },
+    }
+}
+
+/// Runs `flow/foreach` bodies on `concurrency` forked contexts. Results keep
+/// the item order; a `break` stops scheduling and drops anything after it.
+pub(super) fn foreach_concurrent(
+    ctx: &mut Context,
+    items: Vec<Value>,
+    start: usize,
+    mut results: Vec<Value>,
+    concurrency: usize,
+    collect_path: Option<&str>,
+) -> Result<Vec<Value>> {
+    let total = items.len();
+    let next = AtomicUsize::new(start);
+    let stop = AtomicBool::new(false);
+    let mut outcomes: Vec<Option<ItemOutcome>> = (0..total).map(|_| None).collect();
+    let mut checkpoint_error = None;
+    let workers: Vec<Context> = (0..concurrency.min(total - start))
+        .map(|_| ctx.fork())
+        .collect();
+
+    thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel::<(usize, ItemOutcome)>();
+        for mut worker in workers {
+            let tx = tx.clone();
+            let (items, next, stop) = (&items, &next, &stop);
+            scope.spawn(move || loop {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                if index >= total {
+                    break;
+                }
+                let outcome = run_item(&mut worker, index, items[index].clone(), collect_path);
+                if matches!(outcome, ItemOutcome::Break | ItemOutcome::Failed(_)) {
+                    stop.store(true, Ordering::SeqCst);
+                }
+                if tx.send((index, outcome)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        // Checkpoints only cover the contiguous prefix of finished items.
+        let mut prefix = start;
+        for (index, outcome) in rx {
+            outcomes[index] = Some(outcome);
+            let before = prefix;
+            while prefix < total {
+                match &outcomes[prefix] {
+                    Some(ItemOutcome::Collected(value)) => results.push(value.clone()),
+                    Some(ItemOutcome::Skipped) => {}
+                    _ => break,
+                }
+                prefix += 1;
+            }
+            if prefix != before && checkpoint_error.is_none() {
+                if let Err(err) = checkpoint::record_foreach_iteration(ctx, prefix, &results) {
+                    stop.store(true, Ordering::SeqCst);
+                    checkpoint_error = Some(err);
+                }
+            }
+        }
+    });
+
+    if let Some(err) = checkpoint_error {
+        return Err(err);
+    }
+
+    let break_index = outcomes
+        .iter()
+        .position(|outcome| matches!(outcome, Some(ItemOutcome::Break)))
+        .unwrap_or(total);
+    let mut failures: Vec<(usize, anyhow::Error)> = outcomes
+        .into_iter()
+        .enumerate()
+        .take(break_index)
+        .filter_map(|(index, outcome)| match outcome {
+            Some(ItemOutcome::Failed(err)) => Some((index, err)),
+            _ => None,
+        })
+        .collect();
+    if let Some(position) = failures
+        .iter()
+        .position(|(_, err)| err.is::<CancelledError>())
+    {
+        return Err(failures.swap_remove(position).1);
+    }
+    match failures.len() {
+        0 => Ok(results),
+        1 => Err(failures.remove(0).1),
+        count => {
+            let details = failures
+                .iter()
+                .map(|(index, err)| format!("#{index}: {err}"))
+                .collect::<Vec<_>>()
+                .join("; ");
+            Err(anyhow!("flow/foreach: {count} items failed: {details}"))
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Map, Number, Value};
+
+use super::{has_slot, replace_state, sleep_cancellable, FlowSignalError};
+use crate::registry::{CancelledError, Context};
+
+/// Token buckets and circuit breakers, keyed by the compose-provided `key`.
+/// Shared between a context and its forks so concurrent branches draw from
+/// the same budget.
+#[derive(Clone, Debug, Default)]
+pub struct FlowLimits {
+    buckets: Arc<Mutex<HashMap<String, TokenBucket>>>,
+    breakers: Arc<Mutex<HashMap<String, Breaker>>>,
+}
+
+impl FlowLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct Breaker {
+    failures: u64,
+    opened_at: Option<Instant>,
+    trial_running: bool,
+}
+
+impl Breaker {
+    fn state(&self, cooldown: Duration) -> &'static str {
+        match self.opened_at {
+            None => "closed",
+            Some(opened) if opened.elapsed() < cooldown => "open",
+            Some(_) => "half_open",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CircuitOpenError {
+    pub key: String,
+    pub retry_in_ms: u64,
+}
+
+impl fmt::Display for CircuitOpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "flow/circuit_breaker: circuit `{}` is open (retry in {} ms)",
+            self.key, self.retry_in_ms
+        )
+    }
+}
+
+impl std::error::Error for CircuitOpenError {}
+
+fn limit_key(input: &Value) -> String {
+    match input.get("key") {
+        None | Some(Value::Null) => "default".to_string(),
+        Some(Value::String(key)) => key.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn positive(input: &Value, field: &str, default: f64, block: &str) -> Result<f64> {
+    let value = input.get(field).and_then(Value::as_f64).unwrap_or(default);
+    if value > 0.0 {
+        Ok(value)
+    } else {
+        Err(anyhow!("{block}: `{field}` must be greater than 0"))
+    }
+}
+
+/// Waits for a token from the bucket `key` (`rate` tokens per `intervalMs`,
+/// holding at most `burst`), then runs `body`.
+pub fn flow_rate_limit(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let key = limit_key(&input);
+    let rate = positive(&input, "rate", 1.0, "flow/rate_limit")?;
+    let interval_ms = positive(&input, "intervalMs", 1000.0, "flow/rate_limit")?;
+    let burst = positive(&input, "burst", rate, "flow/rate_limit")?;
+    let per_ms = rate / interval_ms;
+
+    let started = Instant::now();
+    loop {
+        ctx.ensure_not_cancelled()?;
+        let wait_ms = {
+            let mut buckets = ctx
+                .flow_limits()
+                .buckets
+                .lock()
+                .expect("flow limits poisoned");
+            let now = Instant::now();
+            let bucket = buckets.entry(key.clone()).or_insert(TokenBucket {
+                tokens: burst,
+                refilled_at: now,
+            });
+            let elapsed_ms = now.duration_since(bucket.refilled_at).as_secs_f64() * 1000.0;
+            bucket.tokens = (bucket.tokens + elapsed_ms * per_ms).min(burst);
+            bucket.refilled_at = now;
+            if bucket.tokens >= 1.0 {
+                bucket.tokens -= 1.0;
+                None
+            } else {
+                Some((1.0 - bucket.tokens) / per_ms)
+            }
+        };
+        match wait_ms {
+            None => break,
+            Some(ms) => sleep_cancellable(ctx, Duration::from_secs_f64(ms.ceil() / 1000.0))?,
+        }
+    }
+    let waited_ms = started.elapsed().as_millis() as u64;
+
+    let value = ctx.run_slot(
+        "body",
+        None,
+        Some(json!({ "key": key, "waitedMs": waited_ms })),
+    )?;
+    let mut result_state = Map::new();
+    replace_state(&mut result_state, value, "flow/rate_limit", "body")?;
+    result_state.insert(
+        "waitedMs".to_string(),
+        Value::Number(Number::from(waited_ms)),
+    );
+    Ok(Value::Object(result_state))
+}
+
+fn circuit_value(state: &str, failures: u64) -> Value {
+    json!({ "state": state, "failures": failures })
+}
+
+/// Runs `body` unless the circuit `key` is open. `failureThreshold`
+/// consecutive failures open it for `cooldownMs`; afterwards a single trial
+/// call decides whether it closes again. While open, the `fallback` slot runs
+/// instead when present, otherwise a `CircuitOpenError` is raised.
+pub fn flow_circuit_breaker(ctx: &mut Context, input: Value, meta: Option<Value>) -> Result<Value> {
+    let key = limit_key(&input);
+    let threshold = input
+        .get("failureThreshold")
+        .and_then(Value::as_u64)
+        .unwrap_or(5)
+        .max(1);
+    let cooldown = Duration::from_millis(
+        input
+            .get("cooldownMs")
+            .and_then(Value::as_u64)
+            .unwrap_or(30_000),
+    );
+    ctx.ensure_not_cancelled()?;
+
+    let limits = ctx.flow_limits().clone();
+    let rejected = {
+        let mut breakers = limits.breakers.lock().expect("flow limits poisoned");
+        let breaker = breakers.entry(key.clone()).or_default();
+        match breaker.state(cooldown) {
+            "closed" => None,
+            "half_open" if !breaker.trial_running => {
+                breaker.trial_running = true;
+                None
+            }
+            state => {
+                let retry_in = breaker
+                    .opened_at
+                    .map(|opened| cooldown.saturating_sub(opened.elapsed()))
+                    .unwrap_or_default();
+                Some((circuit_value(state, breaker.failures), retry_in))
+            }
+        }
+    };
+
+    let mut result_state = Map::new();
+    if let Some((circuit, retry_in)) = rejected {
+        if !has_slot(&meta, "fallback") {
+            return Err(CircuitOpenError {
+                key,
+                retry_in_ms: retry_in.as_millis() as u64,
+            }
+            .into());
+        }
+        let vars = json!({ "key": key, "circuit": circuit });
+        let value = ctx.run_slot("fallback", None, Some(vars))?;
+        replace_state(&mut result_state, value, "flow/circuit_breaker", "fallback")?;
+        result_state.insert("circuit".to_string(), circuit);
+        return Ok(Value::Object(result_state));
+    }
+
+    let outcome = ctx.run_slot("body", None, Some(json!({ "key": key })));
+    let failed = match &outcome {
+        Ok(_) => false,
+        Err(err) => !(err.is::<FlowSignalError>() || err.is::<CancelledError>()),
+    };
+    let circuit = {
+        let mut breakers = limits.breakers.lock().expect("flow limits poisoned");
+        let breaker = breakers.entry(key.clone()).or_default();
+        let trial = std::mem::take(&mut breaker.trial_running);
+        if failed {
+            breaker.failures += 1;
+            if trial || breaker.failures >= threshold {
+                breaker.opened_at = Some(Instant::now());
+            }
+        } else if outcome.is_ok() {
+            breaker.failures = 0;
+            breaker.opened_at = None;
+        }
+        circuit_value(breaker.state(cooldown), breaker.failures)
+    };
+    replace_state(&mut result_state, outcome?, "flow/circuit_breaker", "body")?;
+    result_state.insert("circuit".to_string(), circuit);
+    Ok(Value::Object(result_state))
+}
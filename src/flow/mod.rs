@@ -1,10 +1,32 @@
 use std::fmt;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
+use crate::checkpoint;
 use crate::compose::SlotNotFoundError;
-use crate::registry::{Context, Registry};
+use crate::registry::{CancelledError, Context, Registry};
 use anyhow::{anyhow, Result};
 use serde_json::{Map, Number, Value};
 
+mod batch;
+mod concurrent;
+mod limits;
+mod race;
+mod reduce;
+mod retry;
+mod saga;
+mod timeout;
+
+pub use batch::flow_batch;
+pub use limits::{flow_circuit_breaker, flow_rate_limit, CircuitOpenError, FlowLimits};
+pub use race::{flow_any, flow_race};
+pub use reduce::flow_reduce;
+pub use retry::flow_retry;
+pub use saga::{flow_saga, SagaError};
+pub use timeout::{flow_timeout, TimeoutError};
+
 #[derive(Debug)]
 pub struct FlowSignalError {
     name: &'static str,
@@ -28,6 +50,22 @@ impl fmt::Display for FlowSignalError {
 
 impl std::error::Error for FlowSignalError {}
 
+/// Error raised by `flow/throw@1`, carrying a code catch clauses can match.
+#[derive(Debug)]
+pub struct FlowError {
+    pub code: String,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+impl fmt::Display for FlowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for FlowError {}
+
 pub fn flow_break(_ctx: &mut Context, _input: Value, _meta: Option<Value>) -> Result<Value> {
     Err(FlowSignalError::new("break").into())
 }
@@ -36,6 +74,28 @@ pub fn flow_continue(_ctx: &mut Context, _input: Value, _meta: Option<Value>) ->
     Err(FlowSignalError::new("continue").into())
 }
 
+/// Inside a `flow/try` catch clause, re-raises the caught error unchanged.
+pub fn flow_rethrow(_ctx: &mut Context, _input: Value, _meta: Option<Value>) -> Result<Value> {
+    Err(FlowSignalError::new("rethrow").into())
+}
+
+pub fn flow_throw(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let message = input
+        .get("message")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("flow/throw: `message` is required"))?;
+    Err(FlowError {
+        code: input
+            .get("code")
+            .and_then(Value::as_str)
+            .unwrap_or("unexpected_error")
+            .to_string(),
+        message: message.to_string(),
+        data: input.get("data").filter(|data| !data.is_null()).cloned(),
+    }
+    .into())
+}
+
 pub fn flow_check_abort(ctx: &mut Context, _input: Value, _meta: Option<Value>) -> Result<Value> {
     ctx.ensure_not_cancelled()?;
     Ok(Value::Object(Map::new()))
@@ -73,17 +133,57 @@ fn slot_vars(phase: &str, error: Option<&Value>) -> Value {
     Value::Object(vars)
 }
 
+fn error_kind(err: &anyhow::Error) -> &'static str {
+    if err.is::<CancelledError>() {
+        "cancelled"
+    } else if err.is::<TimeoutError>() {
+        "timeout"
+    } else {
+        "error"
+    }
+}
+
 fn normalize_error_value(err: &anyhow::Error) -> Value {
     let mut map = Map::new();
-    map.insert(
-        "code".to_string(),
-        Value::String("unexpected_error".to_string()),
-    );
+    let kind = error_kind(err);
+    let code = match err.downcast_ref::<FlowError>() {
+        Some(flow_error) => flow_error.code.as_str(),
+        None if err.is::<CircuitOpenError>() => "circuit_open",
+        None if kind == "error" => "unexpected_error",
+        None => kind,
+    };
+    map.insert("code".to_string(), Value::String(code.to_string()));
+    map.insert("kind".to_string(), Value::String(kind.to_string()));
     map.insert("message".to_string(), Value::String(err.to_string()));
+    if let Some(data) = err
+        .downcast_ref::<FlowError>()
+        .and_then(|flow_error| flow_error.data.clone())
+    {
+        map.insert("data".to_string(), data);
+    }
     Value::Object(map)
 }
 
-fn replace_state(target: &mut Map<String, Value>, value: Value, context: &str) -> Result<()> {
+/// Sleeps for `duration`, waking up regularly to honour cancellation.
+fn sleep_cancellable(ctx: &Context, duration: Duration) -> Result<()> {
+    const TICK: Duration = Duration::from_millis(20);
+    let deadline = Instant::now() + duration;
+    loop {
+        ctx.ensure_not_cancelled()?;
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(());
+        }
+        thread::sleep((deadline - now).min(TICK));
+    }
+}
+
+fn replace_state(
+    target: &mut Map<String, Value>,
+    value: Value,
+    block: &str,
+    context: &str,
+) -> Result<()> {
     match value {
         Value::Object(map) => {
             *target = map;
@@ -91,7 +191,8 @@ fn replace_state(target: &mut Map<String, Value>, value: Value, context: &str) -
         Value::Null => {}
         other => {
             return Err(anyhow!(
-                "flow/try: {} slot must return an object or null, got {}",
+                "{}: {} slot must return an object or null, got {}",
+                block,
                 context,
                 other
             ))
@@ -100,7 +201,12 @@ fn replace_state(target: &mut Map<String, Value>, value: Value, context: &str) -
     Ok(())
 }
 
-fn merge_state(target: &mut Map<String, Value>, value: Value, context: &str) -> Result<()> {
+fn merge_state(
+    target: &mut Map<String, Value>,
+    value: Value,
+    block: &str,
+    context: &str,
+) -> Result<()> {
     match value {
         Value::Object(map) => {
             for (key, val) in map {
@@ -110,7 +216,8 @@ fn merge_state(target: &mut Map<String, Value>, value: Value, context: &str) ->
         Value::Null => {}
         other => {
             return Err(anyhow!(
-                "flow/try: {} slot must return an object or null, got {}",
+                "{}: {} slot must return an object or null, got {}",
+                block,
                 context,
                 other
             ))
@@ -119,53 +226,124 @@ fn merge_state(target: &mut Map<String, Value>, value: Value, context: &str) ->
     Ok(())
 }
 
-pub fn flow_try(ctx: &mut Context, _input: Value, meta: Option<Value>) -> Result<Value> {
+/// A typed `flow/try` catch clause from the `catches` input: errors whose
+/// code and kind match are handled by `slot`, or left to propagate when the
+/// clause sets `rethrow`.
+struct CatchClause {
+    codes: Vec<String>,
+    kinds: Vec<String>,
+    slot: Option<String>,
+}
+
+impl CatchClause {
+    fn parse_all(value: Option<&Value>) -> Result<Vec<Self>> {
+        let clauses = match value {
+            None | Some(Value::Null) => return Ok(Vec::new()),
+            Some(Value::Array(clauses)) => clauses,
+            Some(_) => return Err(anyhow!("flow/try: `catches` must be an array")),
+        };
+        clauses
+            .iter()
+            .map(|clause| {
+                let strings = |key: &str| -> Vec<String> {
+                    match clause.get(key) {
+                        Some(Value::String(single)) => vec![single.clone()],
+                        Some(Value::Array(items)) => items
+                            .iter()
+                            .filter_map(Value::as_str)
+                            .map(str::to_string)
+                            .collect(),
+                        _ => Vec::new(),
+                    }
+                };
+                let rethrow = clause.get("rethrow").is_some_and(is_truthy);
+                let slot = clause
+                    .get("slot")
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+                if slot.is_none() && !rethrow {
+                    return Err(anyhow!(
+                        "flow/try: catch clauses need a `slot` or `rethrow: true`"
+                    ));
+                }
+                Ok(Self {
+                    codes: strings("code"),
+                    kinds: strings("kind"),
+                    slot: if rethrow { None } else { slot },
+                })
+            })
+            .collect()
+    }
+
+    fn matches(&self, error: &Value) -> bool {
+        let field = |key: &str| error.get(key).and_then(Value::as_str).unwrap_or("");
+        (self.codes.is_empty() || self.codes.iter().any(|code| code == field("code")))
+            && (self.kinds.is_empty() || self.kinds.iter().any(|kind| kind == field("kind")))
+    }
+}
+
+/// Picks the slot handling `error`: the first matching clause, otherwise the
+/// plain `catch` slot. Cancellation only reaches clauses naming it explicitly.
+fn select_catch_slot(
+    clauses: &[CatchClause],
+    error: &Value,
+    meta: &Option<Value>,
+) -> Result<Option<String>> {
+    if let Some(clause) = clauses.iter().find(|clause| clause.matches(error)) {
+        // An unknown slot would fall back to `children` and rerun the body.
+        return match &clause.slot {
+            Some(slot) if !has_slot(meta, slot) => Err(anyhow!(
+                "flow/try: catch clause slot `{slot}` is not defined"
+            )),
+            slot => Ok(slot.clone()),
+        };
+    }
+    let cancelled = error.get("kind").and_then(Value::as_str) == Some("cancelled");
+    if !cancelled && has_slot(meta, "catch") {
+        return Ok(Some("catch".to_string()));
+    }
+    Ok(None)
+}
+
+pub fn flow_try(ctx: &mut Context, input: Value, meta: Option<Value>) -> Result<Value> {
+    let clauses = CatchClause::parse_all(input.get("catches"))?;
     let mut result_state = Map::new();
     let mut pending_error: Option<anyhow::Error> = None;
     let mut pending_error_value: Option<Value> = None;
 
     match ctx.run_slot("children", None, Some(slot_vars("try", None))) {
-        Ok(value) => replace_state(&mut result_state, value, "try")?,
-        Err(err) => match err.downcast::<FlowSignalError>() {
-            Ok(signal) => return Err(signal.into()),
-            Err(err) => {
-                let normalized = normalize_error_value(&err);
-                pending_error_value = Some(normalized.clone());
-                pending_error = Some(err);
-            }
-        },
+        Ok(value) => replace_state(&mut result_state, value, "flow/try", "try")?,
+        Err(err) if err.is::<FlowSignalError>() => pending_error = Some(err),
+        Err(err) => {
+            pending_error_value = Some(normalize_error_value(&err));
+            pending_error = Some(err);
+        }
     }
 
-    if pending_error.is_some() && has_slot(&meta, "catch") {
-        let error_value = pending_error_value.clone();
-        match ctx.run_slot(
-            "catch",
-            None,
-            Some(slot_vars("catch", error_value.as_ref())),
-        ) {
-            Ok(value) => {
-                replace_state(&mut result_state, value, "catch")?;
-                pending_error = None;
-                pending_error_value = None;
-            }
-            Err(err) => match err.downcast::<FlowSignalError>() {
-                Ok(signal) => return Err(signal.into()),
-                Err(err) => {
-                    let normalized = normalize_error_value(&err);
-                    pending_error_value = Some(normalized.clone());
-                    pending_error = Some(err);
-                }
-            },
-        }
+    let catch_slot = match pending_error_value.as_ref() {
+        Some(error) => select_catch_slot(&clauses, error, &meta).unwrap_or_else(|err| {
+            pending_error = Some(err);
+            None
+        }),
+        None => None,
+    };
+    if let Some(slot) = catch_slot {
+        let mut vars = slot_vars("catch", pending_error_value.as_ref());
+        vars["clause"] = Value::String(slot.clone());
+        let outcome = run_cleanup_slot(ctx, &slot, vars);
+        settle_catch(
+            outcome,
+            &slot,
+            &mut result_state,
+            &mut pending_error,
+            &mut pending_error_value,
+        )?;
     }
 
     if has_slot(&meta, "finally") {
-        let final_value = ctx.run_slot(
-            "finally",
-            None,
-            Some(slot_vars("finally", pending_error_value.as_ref())),
-        )?;
-        merge_state(&mut result_state, final_value, "finally")?;
+        let vars = slot_vars("finally", pending_error_value.as_ref());
+        let final_value = run_cleanup_slot(ctx, "finally", vars)?;
+        merge_state(&mut result_state, final_value, "flow/try", "finally")?;
     }
 
     if let Some(err) = pending_error {
@@ -175,6 +353,155 @@ pub fn flow_try(ctx: &mut Context, _input: Value, meta: Option<Value>) -> Result
     Ok(Value::Object(result_state))
 }
 
+/// Runs a catch or finally slot; once the context is cancelled the slot gets
+/// a fresh token so cleanup still happens.
+fn run_cleanup_slot(ctx: &mut Context, slot: &str, vars: Value) -> Result<Value> {
+    if !ctx.is_cancelled() {
+        return ctx.run_slot(slot, None, Some(vars));
+    }
+    let token = ctx.cancellation_token();
+    ctx.set_cancellation_token(Arc::new(AtomicBool::new(false)));
+    let outcome = ctx.run_slot(slot, None, Some(vars));
+    ctx.set_cancellation_token(token);
+    outcome
+}
+
+/// Applies the outcome of a catch clause: success clears the pending error,
+/// `flow/rethrow` keeps it and any other error replaces it.
+fn settle_catch(
+    outcome: Result<Value>,
+    slot: &str,
+    result_state: &mut Map<String, Value>,
+    pending_error: &mut Option<anyhow::Error>,
+    pending_error_value: &mut Option<Value>,
+) -> Result<()> {
+    match outcome {
+        Ok(value) => {
+            replace_state(result_state, value, "flow/try", slot)?;
+            *pending_error = None;
+            *pending_error_value = None;
+        }
+        Err(err) => match err.downcast_ref::<FlowSignalError>() {
+            Some(signal) if signal.is("rethrow") => {}
+            Some(_) => {
+                *pending_error = Some(err);
+                *pending_error_value = None;
+            }
+            None => {
+                *pending_error_value = Some(normalize_error_value(&err));
+                *pending_error = Some(err);
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Declared slot names. Compose copies a lone `body` slot to `children`;
+/// that alias is left out so the same steps are not listed twice.
+fn slot_names(meta: &Option<Value>) -> Vec<String> {
+    let Some(slots) = meta
+        .as_ref()
+        .and_then(|value| value.get("slots"))
+        .and_then(Value::as_object)
+    else {
+        return Vec::new();
+    };
+    let declared = meta.as_ref().and_then(|value| value.get("children"));
+    let aliased_body = slots.contains_key("body")
+        && declared
+            .and_then(Value::as_object)
+            .is_some_and(|children| !children.contains_key("children"));
+    slots
+        .keys()
+        .filter(|name| !(aliased_body && *name == "children"))
+        .cloned()
+        .collect()
+}
+
+/// Case table entries as `(slot, accepted)` pairs, in matching order. The
+/// array form (`[{ slot, when }]`) keeps the declared order; the object form
+/// (`slot -> value | [values]`) is tried in slot-name order.
+fn switch_cases(input: &Value, slots: &[String]) -> Result<Vec<(String, Value)>> {
+    let cases = match input.get("cases") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Object(cases)) => cases
+            .iter()
+            .map(|(name, accepted)| (name.clone(), accepted.clone()))
+            .collect(),
+        Some(Value::Array(entries)) => entries
+            .iter()
+            .map(|entry| {
+                let slot = entry
+                    .get("slot")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| anyhow!("flow/switch: each case entry needs a `slot` string"))?;
+                let accepted = entry.get("when").cloned().unwrap_or(Value::Null);
+                Ok((slot.to_string(), accepted))
+            })
+            .collect::<Result<_>>()?,
+        Some(_) => {
+            return Err(anyhow!(
+                "flow/switch: `cases` must be an object or an array"
+            ))
+        }
+    };
+    if let Some((name, _)) = cases
+        .iter()
+        .find(|(name, _)| !slots.iter().any(|slot| slot == name))
+    {
+        return Err(anyhow!("flow/switch: case slot `{name}` is not defined"));
+    }
+    Ok(cases)
+}
+
+/// Picks the case slot matching `value`: a slot named after the value first,
+/// then the first matching `cases` entry, then `default`.
+fn select_switch_case(input: &Value, slots: &[String]) -> Result<Option<String>> {
+    let cases = switch_cases(input, slots)?;
+    let value = input.get("value").cloned().unwrap_or(Value::Null);
+    if let Value::String(name) = &value {
+        if name != "default" && slots.iter().any(|slot| slot == name) {
+            return Ok(Some(name.clone()));
+        }
+    }
+    for (name, accepted) in cases {
+        let matches = match accepted {
+            Value::Array(options) => options.contains(&value),
+            single => single == value,
+        };
+        if matches {
+            return Ok(Some(name));
+        }
+    }
+    if slots.iter().any(|slot| slot == "default") {
+        return Ok(Some("default".to_string()));
+    }
+    Ok(None)
+}
+
+pub fn flow_switch(ctx: &mut Context, input: Value, meta: Option<Value>) -> Result<Value> {
+    let slots = slot_names(&meta);
+    let Some(case) = select_switch_case(&input, &slots)? else {
+        let mut result = Map::new();
+        result.insert("case".to_string(), Value::Null);
+        return Ok(Value::Object(result));
+    };
+
+    let mut vars = Map::new();
+    vars.insert("phase".to_string(), Value::String("case".to_string()));
+    vars.insert("case".to_string(), Value::String(case.clone()));
+    vars.insert(
+        "value".to_string(),
+        input.get("value").cloned().unwrap_or(Value::Null),
+    );
+
+    let mut result_state = Map::new();
+    let value = ctx.run_slot(&case, None, Some(Value::Object(vars)))?;
+    replace_state(&mut result_state, value, "flow/switch", &case)?;
+    result_state.insert("case".to_string(), Value::String(case));
+    Ok(Value::Object(result_state))
+}
+
 fn is_truthy(value: &Value) -> bool {
     match value {
         Value::Null => false,
@@ -268,7 +595,31 @@ pub fn flow_foreach(ctx: &mut Context, input: Value, meta: Option<Value>) -> Res
         return Ok(Value::Object(out));
     }
 
-    for (index, item) in items.into_iter().enumerate() {
+    let mut start = 0;
+    if let Some((next_index, saved_results)) = checkpoint::take_foreach_resume(ctx) {
+        start = next_index;
+        results = saved_results;
+    }
+
+    let concurrency = input
+        .get("concurrency")
+        .and_then(Value::as_u64)
+        .unwrap_or(1) as usize;
+    if concurrency > 1 && start < items.len() {
+        let results = concurrent::foreach_concurrent(
+            ctx,
+            items,
+            start,
+            results,
+            concurrency,
+            collect_path.as_deref(),
+        )?;
+        let mut out = Map::new();
+        out.insert("results".to_string(), Value::Array(results));
+        return Ok(Value::Object(out));
+    }
+
+    for (index, item) in items.into_iter().enumerate().skip(start) {
         ctx.ensure_not_cancelled()?;
         let mut slot_vars = Map::new();
         slot_vars.insert("item".to_string(), item.clone());
@@ -290,6 +641,7 @@ pub fn flow_foreach(ctx: &mut Context, input: Value, meta: Option<Value>) -> Res
             Err(err) => {
                 if let Some(signal) = err.downcast_ref::<FlowSignalError>() {
                     if signal.is("continue") {
+                        checkpoint::record_foreach_iteration(ctx, index + 1, &results)?;
                         continue;
                     }
                     if signal.is("break") {
@@ -299,6 +651,7 @@ pub fn flow_foreach(ctx: &mut Context, input: Value, meta: Option<Value>) -> Res
                 return Err(err);
             }
         }
+        checkpoint::record_foreach_iteration(ctx, index + 1, &results)?;
     }
 
     let mut out = Map::new();
@@ -464,8 +817,20 @@ pub fn register_flow(registry: &Registry) {
     registry.register("lcod://flow/break@1", flow_break);
     registry.register("lcod://flow/continue@1", flow_continue);
     registry.register("lcod://flow/try@1", flow_try);
+    registry.register("lcod://flow/throw@1", flow_throw);
+    registry.register("lcod://flow/rethrow@1", flow_rethrow);
     registry.register("lcod://flow/if@1", flow_if);
+    registry.register("lcod://flow/switch@1", flow_switch);
     registry.register("lcod://flow/foreach@1", flow_foreach);
+    registry.register("lcod://flow/reduce@1", flow_reduce);
+    registry.register("lcod://flow/batch@1", flow_batch);
     registry.register("lcod://flow/check_abort@1", flow_check_abort);
     registry.register("lcod://flow/while@1", flow_while);
+    registry.register("lcod://flow/retry@1", flow_retry);
+    registry.register("lcod://flow/timeout@1", flow_timeout);
+    registry.register("lcod://flow/race@1", flow_race);
+    registry.register("lcod://flow/any@1", flow_any);
+    registry.register("lcod://flow/saga@1", flow_saga);
+    registry.register("lcod://flow/rate_limit@1", flow_rate_limit);
+    registry.register("lcod://flow/circuit_breaker@1", flow_circuit_breaker);
 }
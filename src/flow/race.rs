@@ -0,0 +1,120 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+
+use super::{replace_state, slot_names};
+use crate::registry::{CancelledError, Context};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Settle {
+    /// `flow/race`: the first branch to finish wins, error or not.
+    FirstCompletion,
+    /// `flow/any`: the first branch to succeed wins.
+    FirstSuccess,
+}
+
+impl Settle {
+    fn block(self) -> &'static str {
+        match self {
+            Settle::FirstCompletion => "flow/race",
+            Settle::FirstSuccess => "flow/any",
+        }
+    }
+}
+
+/// Runs every slot as a branch on a forked context sharing a child
+/// cancellation token, which is tripped once a winner is known (or the parent
+/// is cancelled) so the remaining branches can bail out. Branch threads are
+/// detached: the winner is returned without waiting for the losers, which
+/// finish in the background once they observe the tripped token.
+fn run_branches(ctx: &mut Context, meta: Option<Value>, settle: Settle) -> Result<Value> {
+    const TICK: Duration = Duration::from_millis(20);
+    let block = settle.block();
+    let branches = slot_names(&meta);
+    if branches.is_empty() {
+        return Err(anyhow!("{block}: at least one branch slot is required"));
+    }
+    ctx.ensure_not_cancelled()?;
+
+    let parent = ctx.cancellation_token();
+    let child = Arc::new(AtomicBool::new(false));
+    let mut winner: Option<(String, Result<Value>)> = None;
+    let mut failures: Vec<(String, anyhow::Error)> = Vec::new();
+
+    let (tx, rx) = mpsc::channel::<(String, Result<Value>)>();
+    for name in &branches {
+        let mut worker = ctx.fork();
+        worker.set_cancellation_token(Arc::clone(&child));
+        let tx = tx.clone();
+        let name = name.clone();
+        thread::spawn(move || {
+            let mut vars = Map::new();
+            vars.insert("phase".to_string(), Value::String("branch".to_string()));
+            vars.insert("branch".to_string(), Value::String(name.clone()));
+            let outcome = worker.run_slot(&name, None, Some(Value::Object(vars)));
+            let _ = tx.send((name, outcome));
+        });
+    }
+    drop(tx);
+
+    let mut pending = branches.len();
+    while pending > 0 && winner.is_none() {
+        let (name, outcome) = match rx.recv_timeout(TICK) {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => {
+                if parent.load(Ordering::SeqCst) {
+                    child.store(true, Ordering::SeqCst);
+                }
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        pending -= 1;
+        match outcome {
+            Err(err) if settle == Settle::FirstSuccess => failures.push((name, err)),
+            outcome => winner = Some((name, outcome)),
+        }
+    }
+    child.store(true, Ordering::SeqCst);
+
+    if let Some((name, outcome)) = winner {
+        let mut result_state = Map::new();
+        replace_state(&mut result_state, outcome?, block, &name)?;
+        result_state.insert("winner".to_string(), Value::String(name));
+        return Ok(Value::Object(result_state));
+    }
+    ctx.ensure_not_cancelled()?;
+    if failures.is_empty() {
+        return Err(anyhow!(
+            "{block}: branches stopped without reporting a result"
+        ));
+    }
+    if failures.len() == 1 {
+        return Err(failures.remove(0).1);
+    }
+    if failures.iter().all(|(_, err)| err.is::<CancelledError>()) {
+        return Err(CancelledError.into());
+    }
+    let details = failures
+        .iter()
+        .map(|(name, err)| format!("{name}: {err}"))
+        .collect::<Vec<_>>()
+        .join("; ");
+    Err(anyhow!(
+        "{block}: all {} branches failed: {details}",
+        failures.len()
+    ))
+}
+
+pub fn flow_race(ctx: &mut Context, _input: Value, meta: Option<Value>) -> Result<Value> {
+    run_branches(ctx, meta, Settle::FirstCompletion)
+}
+
+pub fn flow_any(ctx: &mut Context, _input: Value, meta: Option<Value>) -> Result<Value> {
+    run_branches(ctx, meta, Settle::FirstSuccess)
+}
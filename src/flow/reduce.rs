@@ -0,0 +1,63 @@
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Number, Value};
+
+use super::{collect_path_value, FlowSignalError};
+use crate::registry::Context;
+
+/// Folds `list` into an accumulator. Each `body` run sees `$slot.acc` and the
+/// value found at `accPath` (default `acc`, a path into the body's result)
+/// becomes the next accumulator; a result without that value is an error.
+/// `continue` keeps the accumulator unchanged and `break` stops early.
+pub fn flow_reduce(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let items = match input.get("list") {
+        Some(Value::Array(items)) => items.clone(),
+        Some(Value::Null) | None => Vec::new(),
+        Some(other) => {
+            return Err(anyhow!(
+                "flow/reduce: expected array for `list`, got {}",
+                other
+            ))
+        }
+    };
+    // Given without the `$.` prefix, which compose would resolve as a state
+    // reference before the call.
+    let acc_name = input
+        .get("accPath")
+        .and_then(Value::as_str)
+        .unwrap_or("acc");
+    let acc_path = format!("$.{acc_name}");
+
+    let mut acc = input.get("initial").cloned().unwrap_or(Value::Null);
+    let mut iterations: u64 = 0;
+    for (index, item) in items.into_iter().enumerate() {
+        ctx.ensure_not_cancelled()?;
+        let mut slot_vars = Map::new();
+        slot_vars.insert("item".to_string(), item);
+        slot_vars.insert(
+            "index".to_string(),
+            Value::Number(Number::from(index as i64)),
+        );
+        slot_vars.insert("acc".to_string(), acc.clone());
+        iterations += 1;
+        match ctx.run_slot("body", None, Some(Value::Object(slot_vars.clone()))) {
+            Ok(iter_state) => {
+                acc = collect_path_value(&acc_path, &iter_state, &slot_vars).ok_or_else(|| {
+                    anyhow!("flow/reduce: body result at index {index} has no `{acc_name}` value")
+                })?;
+            }
+            Err(err) => match err.downcast_ref::<FlowSignalError>() {
+                Some(signal) if signal.is("continue") => continue,
+                Some(signal) if signal.is("break") => break,
+                _ => return Err(err),
+            },
+        }
+    }
+
+    let mut out = Map::new();
+    out.insert("result".to_string(), acc);
+    out.insert(
+        "iterations".to_string(),
+        Value::Number(Number::from(iterations)),
+    );
+    Ok(Value::Object(out))
+}
@@ -0,0 +1,163 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Number, Value};
+
+use super::{normalize_error_value, replace_state, sleep_cancellable, FlowSignalError};
+use crate::registry::{CancelledError, Context};
+
+struct Backoff {
+    delay_ms: f64,
+    factor: f64,
+    max_delay_ms: Option<f64>,
+    jitter: f64,
+}
+
+impl Backoff {
+    fn from_input(input: &Value) -> Result<Self> {
+        let backoff = Self {
+            delay_ms: input.get("delayMs").and_then(Value::as_f64).unwrap_or(0.0),
+            factor: input.get("factor").and_then(Value::as_f64).unwrap_or(2.0),
+            max_delay_ms: input.get("maxDelayMs").and_then(Value::as_f64),
+            jitter: input.get("jitter").and_then(Value::as_f64).unwrap_or(0.0),
+        };
+        if backoff.delay_ms < 0.0 || backoff.factor < 1.0 {
+            return Err(anyhow!(
+                "flow/retry: `delayMs` must be >= 0 and `factor` >= 1"
+            ));
+        }
+        if !(0.0..=1.0).contains(&backoff.jitter) {
+            return Err(anyhow!("flow/retry: `jitter` must be between 0 and 1"));
+        }
+        Ok(backoff)
+    }
+
+    /// Delay before attempt `next_attempt` (2-based: the first retry).
+    fn delay_before(&self, next_attempt: u64) -> Duration {
+        let exponent = next_attempt.saturating_sub(2).min(i32::MAX as u64) as i32;
+        let mut delay = self.delay_ms * self.factor.powi(exponent);
+        if let Some(max) = self.max_delay_ms {
+            delay = delay.min(max);
+        }
+        if self.jitter > 0.0 {
+            // Spread retries over [delay * (1 - jitter), delay].
+            delay -= delay * self.jitter * jitter_sample();
+        }
+        Duration::from_millis(delay.max(0.0).round() as u64)
+    }
+}
+
+fn jitter_sample() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default() as u64;
+    let mut x = nanos ^ 0x9E37_79B9_7F4A_7C15;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    (x % 10_000) as f64 / 10_000.0
+}
+
+#[derive(Default)]
+struct ErrorFilter {
+    codes: Vec<String>,
+    messages: Vec<String>,
+}
+
+impl ErrorFilter {
+    fn from_input(value: Option<&Value>, field: &str) -> Result<Option<Self>> {
+        let Some(value) = value.filter(|value| !value.is_null()) else {
+            return Ok(None);
+        };
+        let map = value
+            .as_object()
+            .ok_or_else(|| anyhow!("flow/retry: `{field}` must be an object"))?;
+        let strings = |key: &str| -> Vec<String> {
+            map.get(key)
+                .and_then(Value::as_array)
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        Ok(Some(Self {
+            codes: strings("codes"),
+            messages: strings("messages"),
+        }))
+    }
+
+    fn matches(&self, error: &Value) -> bool {
+        let code = error.get("code").and_then(Value::as_str).unwrap_or("");
+        let message = error.get("message").and_then(Value::as_str).unwrap_or("");
+        self.codes.iter().any(|candidate| candidate == code)
+            || self
+                .messages
+                .iter()
+                .any(|candidate| message.contains(candidate.as_str()))
+    }
+}
+
+pub fn flow_retry(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+    let attempts = input.get("attempts").and_then(Value::as_u64).unwrap_or(3);
+    if attempts == 0 {
+        return Err(anyhow!("flow/retry: `attempts` must be at least 1"));
+    }
+    let backoff = Backoff::from_input(&input)?;
+    let max_duration = input
+        .get("maxDurationMs")
+        .and_then(Value::as_u64)
+        .map(Duration::from_millis);
+    let retry_on = ErrorFilter::from_input(input.get("retryOn"), "retryOn")?;
+    let abort_on = ErrorFilter::from_input(input.get("abortOn"), "abortOn")?;
+
+    let started = Instant::now();
+    let mut last_error = Value::Null;
+    let mut attempt = 1u64;
+    loop {
+        ctx.ensure_not_cancelled()?;
+        let mut vars = Map::new();
+        vars.insert("attempt".to_string(), Value::Number(Number::from(attempt)));
+        vars.insert("error".to_string(), last_error.clone());
+
+        let err = match ctx.run_slot("body", None, Some(Value::Object(vars))) {
+            Ok(value) => {
+                let mut result_state = Map::new();
+                replace_state(&mut result_state, value, "flow/retry", "body")?;
+                result_state.insert("attempts".to_string(), Value::Number(Number::from(attempt)));
+                return Ok(Value::Object(result_state));
+            }
+            Err(err) => err,
+        };
+        if err.is::<FlowSignalError>() || err.is::<CancelledError>() {
+            return Err(err);
+        }
+
+        let normalized = normalize_error_value(&err);
+        let retryable = retry_on
+            .as_ref()
+            .is_none_or(|filter| filter.matches(&normalized))
+            && !abort_on
+                .as_ref()
+                .is_some_and(|filter| filter.matches(&normalized));
+        if !retryable || attempt >= attempts {
+            return Err(err);
+        }
+
+        let delay = backoff.delay_before(attempt + 1);
+        if let Some(limit) = max_duration {
+            if started.elapsed() + delay >= limit {
+                return Err(err.context(format!(
+                    "flow/retry: gave up after {attempt} attempt(s), maxDurationMs exceeded"
+                )));
+            }
+        }
+        sleep_cancellable(ctx, delay)?;
+        last_error = normalized;
+        attempt += 1;
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+
+use super::{has_slot, merge_state, normalize_error_value, run_cleanup_slot, FlowSignalError};
+use crate::registry::Context;
+
+/// Raised by `flow/saga@1` once the compensations of the completed steps ran.
+#[derive(Debug)]
+pub struct SagaError {
+    pub step: String,
+    pub source: anyhow::Error,
+    pub compensated: Vec<String>,
+    pub failed_compensations: Vec<(String, anyhow::Error)>,
+}
+
+impl fmt::Display for SagaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "flow/saga: step `{}` failed: {}", self.step, self.source)?;
+        for (step, err) in &self.failed_compensations {
+            write!(f, "; compensation for `{step}` failed: {err}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SagaError {}
+
+struct SagaStep {
+    slot: String,
+    compensate: Option<String>,
+}
+
+fn parse_steps(input: &Value) -> Result<Vec<SagaStep>> {
+    let steps = input
+        .get("steps")
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("flow/saga: `steps` must be an array"))?;
+    steps
+        .iter()
+        .map(|step| match step {
+            Value::String(slot) => Ok(SagaStep {
+                slot: slot.clone(),
+                compensate: None,
+            }),
+            Value::Object(map) => Ok(SagaStep {
+                slot: map
+                    .get("slot")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| anyhow!("flow/saga: each step needs a `slot`"))?
+                    .to_string(),
+                compensate: map
+                    .get("compensate")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            }),
+            other => Err(anyhow!(
+                "flow/saga: steps must be slot names or objects, got {}",
+                other
+            )),
+        })
+        .collect()
+}
+
+/// Every step and compensation must name a declared slot; an unknown one
+/// would otherwise fall back to `children` and run the wrong steps.
+fn check_slots(steps: &[SagaStep], meta: &Option<Value>) -> Result<()> {
+    let names = steps
+        .iter()
+        .flat_map(|step| std::iter::once(&step.slot).chain(step.compensate.as_ref()));
+    for name in names {
+        if !has_slot(meta, name) {
+            return Err(anyhow!("flow/saga: slot `{name}` is not defined"));
+        }
+    }
+    Ok(())
+}
+
+fn step_vars(phase: &str, step: &str) -> Map<String, Value> {
+    let mut vars = Map::new();
+    vars.insert("phase".to_string(), Value::String(phase.to_string()));
+    vars.insert("step".to_string(), Value::String(step.to_string()));
+    vars
+}
+
+/// Runs the `steps` slots in order. When one fails, the `compensate` slots of
+/// the steps that completed run in reverse order (even after cancellation),
+/// each receiving the step result and the error.
+pub fn flow_saga(ctx: &mut Context, input: Value, meta: Option<Value>) -> Result<Value> {
+    let steps = parse_steps(&input)?;
+    check_slots(&steps, &meta)?;
+    let mut result_state = Map::new();
+    let mut completed: Vec<(&SagaStep, Value)> = Vec::new();
+
+    for step in &steps {
+        let mut vars = step_vars("step", &step.slot);
+        vars.insert("state".to_string(), Value::Object(result_state.clone()));
+        let err = match ctx.run_slot(&step.slot, None, Some(Value::Object(vars))) {
+            Ok(value) => {
+                merge_state(&mut result_state, value.clone(), "flow/saga", &step.slot)?;
+                completed.push((step, value));
+                continue;
+            }
+            Err(err) if err.is::<FlowSignalError>() => return Err(err),
+            Err(err) => err,
+        };
+
+        let error_value = normalize_error_value(&err);
+        let mut compensated = Vec::new();
+        let mut failed_compensations = Vec::new();
+        for (done, result) in completed.iter().rev() {
+            let Some(compensate) = done.compensate.as_deref() else {
+                continue;
+            };
+            let mut vars = step_vars("compensate", &done.slot);
+            vars.insert("result".to_string(), result.clone());
+            vars.insert("error".to_string(), error_value.clone());
+            match run_cleanup_slot(ctx, compensate, Value::Object(vars)) {
+                Ok(_) => compensated.push(done.slot.clone()),
+                Err(comp_err) => failed_compensations.push((done.slot.clone(), comp_err)),
+            }
+        }
+        return Err(SagaError {
+            step: step.slot.clone(),
+            source: err,
+            compensated,
+            failed_compensations,
+        }
+        .into());
+    }
+
+    result_state.insert(
+        "completed".to_string(),
+        Value::Array(
+            completed
+                .iter()
+                .map(|(step, _)| Value::String(step.slot.clone()))
+                .collect(),
+        ),
+    );
+    Ok(Value::Object(result_state))
+}
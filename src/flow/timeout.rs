@@ -0,0 +1,138 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Number, Value};
+
+use super::{has_slot, replace_state};
+use crate::registry::Context;
+
+#[derive(Debug)]
+pub struct TimeoutError {
+    pub timeout_ms: u64,
+}
+
+impl fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "flow/timeout: body exceeded {} ms", self.timeout_ms)
+    }
+}
+
+impl std::error::Error for TimeoutError {}
+
+/// Cancellation token for the body: tripped when the deadline passes or when
+/// the parent token is cancelled.
+struct Deadline {
+    token: Arc<AtomicBool>,
+    expired: Arc<AtomicBool>,
+    done: Option<mpsc::Sender<()>>,
+    watchdog: Option<thread::JoinHandle<()>>,
+}
+
+impl Deadline {
+    fn start(parent: Arc<AtomicBool>, timeout: Duration) -> Self {
+        const TICK: Duration = Duration::from_millis(20);
+        let token = Arc::new(AtomicBool::new(parent.load(Ordering::SeqCst)));
+        let expired = Arc::new(AtomicBool::new(false));
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+        let deadline = Instant::now() + timeout;
+        let child = Arc::clone(&token);
+        let expired_flag = Arc::clone(&expired);
+        let watchdog = thread::spawn(move || loop {
+            if parent.load(Ordering::SeqCst) {
+                child.store(true, Ordering::SeqCst);
+                return;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                expired_flag.store(true, Ordering::SeqCst);
+                child.store(true, Ordering::SeqCst);
+                return;
+            }
+            match done_rx.recv_timeout((deadline - now).min(TICK)) {
+                Err(RecvTimeoutError::Timeout) => continue,
+                Ok(()) | Err(RecvTimeoutError::Disconnected) => return,
+            }
+        });
+        Self {
+            token,
+            expired,
+            done: Some(done_tx),
+            watchdog: Some(watchdog),
+        }
+    }
+
+    /// Stops the watchdog and reports whether the deadline was hit.
+    fn finish(mut self) -> bool {
+        self.stop();
+        self.expired.load(Ordering::SeqCst)
+    }
+
+    fn stop(&mut self) {
+        if let Some(done) = self.done.take() {
+            let _ = done.send(());
+        }
+        if let Some(handle) = self.watchdog.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for Deadline {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+pub fn flow_timeout(ctx: &mut Context, input: Value, meta: Option<Value>) -> Result<Value> {
+    let timeout_ms = input
+        .get("timeoutMs")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| anyhow!("flow/timeout: `timeoutMs` is required"))?;
+    ctx.ensure_not_cancelled()?;
+
+    let parent = ctx.cancellation_token();
+    let started = Instant::now();
+    let deadline = Deadline::start(Arc::clone(&parent), Duration::from_millis(timeout_ms));
+    ctx.set_cancellation_token(Arc::clone(&deadline.token));
+    let mut vars = Map::new();
+    vars.insert("phase".to_string(), Value::String("body".to_string()));
+    vars.insert(
+        "timeoutMs".to_string(),
+        Value::Number(Number::from(timeout_ms)),
+    );
+    let outcome = ctx.run_slot("body", None, Some(Value::Object(vars)));
+    ctx.set_cancellation_token(parent);
+    let expired = deadline.finish();
+
+    let mut result_state = Map::new();
+    if !expired {
+        replace_state(&mut result_state, outcome?, "flow/timeout", "body")?;
+        result_state.insert("timedOut".to_string(), Value::Bool(false));
+        return Ok(Value::Object(result_state));
+    }
+
+    // The parent may have been cancelled at the same time: that wins.
+    ctx.ensure_not_cancelled()?;
+    if !has_slot(&meta, "onTimeout") {
+        return Err(TimeoutError { timeout_ms }.into());
+    }
+    let mut vars = Map::new();
+    vars.insert("phase".to_string(), Value::String("timeout".to_string()));
+    vars.insert(
+        "timeoutMs".to_string(),
+        Value::Number(Number::from(timeout_ms)),
+    );
+    vars.insert(
+        "elapsedMs".to_string(),
+        Value::Number(Number::from(started.elapsed().as_millis() as u64)),
+    );
+    let value = ctx.run_slot("onTimeout", None, Some(Value::Object(vars)))?;
+    replace_state(&mut result_state, value, "flow/timeout", "onTimeout")?;
+    result_state.insert("timedOut".to_string(), Value::Bool(true));
+    Ok(Value::Object(result_state))
+}
@@ -0,0 +1,239 @@
+use std::env;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Result;
+use toml::Value as TomlValue;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FsAccess {
+    Read,
+    Write,
+}
+
+impl fmt::Display for FsAccess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FsAccess::Read => write!(f, "read"),
+            FsAccess::Write => write!(f, "write"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PermissionError {
+    pub path: PathBuf,
+    pub access: FsAccess,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for PermissionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "permission denied: {} access to {} {}",
+            self.access,
+            self.path.display(),
+            self.reason
+        )
+    }
+}
+
+impl std::error::Error for PermissionError {}
+
+/// A root as configured and, when it exists, with symlinks resolved, so
+/// `/tmp/x` and `/private/tmp/x` are recognised alike.
+#[derive(Clone, Debug)]
+struct Root {
+    lexical: PathBuf,
+    canonical: Option<PathBuf>,
+}
+
+impl Root {
+    fn new(path: &Path) -> Self {
+        let lexical = absolutize(path);
+        let canonical = lexical.canonicalize().ok();
+        Self { lexical, canonical }
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        path.starts_with(&self.lexical)
+            || self
+                .canonical
+                .as_ref()
+                .is_some_and(|root| path.starts_with(root))
+    }
+}
+
+/// Filesystem sandbox enforced by the path-taking contracts (`core/fs/*`,
+/// hashing, git clones, JSONL reads). `None` roots leave that access
+/// unrestricted; write roots are readable too.
+#[derive(Clone, Debug, Default)]
+pub struct FsPolicy {
+    read_roots: Option<Vec<Root>>,
+    write_roots: Option<Vec<Root>>,
+    deny_symlink_escapes: bool,
+}
+
+impl FsPolicy {
+    pub fn unrestricted() -> Self {
+        Self::default()
+    }
+
+    pub fn is_unrestricted(&self) -> bool {
+        self.read_roots.is_none() && self.write_roots.is_none()
+    }
+
+    pub fn allow_read(mut self, root: impl AsRef<Path>) -> Self {
+        self.read_roots
+            .get_or_insert_with(Vec::new)
+            .push(Root::new(root.as_ref()));
+        self
+    }
+
+    pub fn allow_write(mut self, root: impl AsRef<Path>) -> Self {
+        self.write_roots
+            .get_or_insert_with(Vec::new)
+            .push(Root::new(root.as_ref()));
+        self
+    }
+
+    /// Resolve symlinks before checking roots, so a link inside an allowed
+    /// root cannot be used to reach a path outside of it.
+    pub fn deny_symlink_escapes(mut self, deny: bool) -> Self {
+        self.deny_symlink_escapes = deny;
+        self
+    }
+
+    pub fn denies_symlink_escapes(&self) -> bool {
+        self.deny_symlink_escapes
+    }
+
+    /// Reads the `[sandbox.fs]` table of an `lcp.toml` (`read`, `write`,
+    /// `denySymlinkEscapes`); relative roots are resolved against `base_dir`.
+    pub fn from_manifest(manifest: &TomlValue, base_dir: &Path) -> Option<Self> {
+        let table = manifest.get("sandbox")?.get("fs")?.as_table()?;
+        let roots = |key: &str| -> Vec<PathBuf> {
+            table
+                .get(key)
+                .and_then(TomlValue::as_array)
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(TomlValue::as_str)
+                        .map(|root| base_dir.join(root))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let mut policy = Self::default();
+        if table.contains_key("read") {
+            policy.read_roots = Some(Vec::new());
+        }
+        if table.contains_key("write") {
+            policy.write_roots = Some(Vec::new());
+        }
+        for root in roots("read") {
+            policy = policy.allow_read(root);
+        }
+        for root in roots("write") {
+            policy = policy.allow_write(root);
+        }
+        let deny = table
+            .get("denySymlinkEscapes")
+            .and_then(TomlValue::as_bool)
+            .unwrap_or(false);
+        Some(policy.deny_symlink_escapes(deny))
+    }
+
+    pub fn check(&self, path: &Path, access: FsAccess) -> Result<()> {
+        let allowed: Vec<&Root> = match access {
+            FsAccess::Read if self.read_roots.is_none() => return Ok(()),
+            FsAccess::Read => self
+                .read_roots
+                .iter()
+                .chain(self.write_roots.iter())
+                .flatten()
+                .collect(),
+            FsAccess::Write => match &self.write_roots {
+                None => return Ok(()),
+                Some(roots) => roots.iter().collect(),
+            },
+        };
+
+        let lexical = absolutize(path);
+        if !allowed.iter().any(|root| root.contains(&lexical)) {
+            return Err(PermissionError {
+                path: lexical,
+                access,
+                reason: "is outside the allowed roots",
+            }
+            .into());
+        }
+        if self.deny_symlink_escapes {
+            let resolved = resolve_existing(&lexical);
+            if !allowed.iter().any(|root| root.contains(&resolved)) {
+                return Err(PermissionError {
+                    path: lexical,
+                    access,
+                    reason: "escapes the allowed roots through a symlink",
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Absolute path with `.` and `..` removed, without touching the filesystem.
+fn absolutize(path: &Path) -> PathBuf {
+    let joined = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        env::current_dir()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    };
+    let mut normalized = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Where `path` points once symlinks are followed, whether it exists yet or
+/// not.
+pub(crate) fn resolve_path(path: &Path) -> PathBuf {
+    resolve_existing(&absolutize(path))
+}
+
+/// Canonicalizes the longest existing ancestor of `path` and appends the
+/// remaining components, so paths about to be created are resolved too.
+fn resolve_existing(path: &Path) -> PathBuf {
+    let mut existing = path.to_path_buf();
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return rest
+                .iter()
+                .rev()
+                .fold(canonical, |acc, part| acc.join(part));
+        }
+        match (
+            existing.file_name().map(|name| name.to_owned()),
+            existing.parent(),
+        ) {
+            (Some(name), Some(parent)) => {
+                rest.push(name);
+                existing = parent.to_path_buf();
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
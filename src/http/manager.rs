@@ -77,6 +77,12 @@ impl HttpHostManager {
         }
     }
 
+    pub fn contains_handle(&self, handle: &Value) -> bool {
+        extract_handle_id(handle)
+            .map(|id| self.hosts.contains_key(&id))
+            .unwrap_or(false)
+    }
+
     pub fn stop_all(&mut self) {
         let mut hosts = HashMap::new();
         std::mem::swap(&mut self.hosts, &mut hosts);
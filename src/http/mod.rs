@@ -15,7 +15,8 @@ use serde_json::{json, Map, Value};
 use tiny_http::{Header, Response, StatusCode};
 use url::Url;
 
-use crate::compose::{parse_compose, run_compose};
+use crate::compose::{parse_compose_cached, run_compose_with_signature};
+use crate::compose_signature::{ComposeSignature, SignatureError, SignatureSide};
 use crate::registry::{Context, Registry};
 
 const CONTRACT_API_ROUTE: &str = "lcod://http/api_route@0.1.0";
@@ -508,13 +509,17 @@ fn handle_http_request(
     let result = match result {
         Ok(value) => value,
         Err(err) => {
+            let status = match err.downcast_ref::<SignatureError>() {
+                Some(signature_err) if signature_err.side == SignatureSide::Input => 400,
+                _ => 500,
+            };
             let response = Response::from_string(
                 json!({
                     "error": err.to_string()
                 })
                 .to_string(),
             )
-            .with_status_code(StatusCode(500))
+            .with_status_code(StatusCode(status))
             .with_header(
                 Header::from_bytes("content-type", "application/json")
                     .unwrap_or_else(|_| Header::from_bytes("content-type", "text/plain").unwrap()),
@@ -648,8 +653,10 @@ fn execute_handler(
                 .get("compose")
                 .cloned()
                 .ok_or_else(|| anyhow!("Compose handler requires compose array"))?;
-            let steps = parse_compose(&compose_value)
+            let steps = parse_compose_cached(&compose_value)
                 .with_context(|| "invalid compose steps in handler")?;
+            let signature = ComposeSignature::from_document(handler)
+                .with_context(|| "invalid compose header in handler")?;
             let mut initial_state = handler
                 .get("initialState")
                 .cloned()
@@ -662,7 +669,7 @@ fn execute_handler(
                     .or_insert(request_context.clone());
             }
             let mut ctx = registry.context();
-            run_compose(&mut ctx, &steps, initial_state)
+            run_compose_with_signature(&mut ctx, &steps, signature.as_ref(), initial_state)
         }
         other => Err(anyhow!("Unsupported handler type: {other}")),
     }
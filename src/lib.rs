@@ -1,11 +1,16 @@
+pub mod checkpoint;
 pub mod compose;
 pub mod compose_contracts;
+pub mod compose_loader;
+pub mod compose_signature;
 pub mod core;
 pub mod demo;
 pub mod flow;
+pub mod fs_policy;
 pub mod http;
 pub mod impls;
 pub mod registry;
+pub mod secrets;
 pub mod streams;
 pub mod tooling;
 
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::fmt;
+use std::path::{Path, PathBuf};
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc, Mutex,
@@ -8,10 +9,16 @@ use std::sync::{
 use anyhow::{anyhow, Result};
 use serde_json::{json, Map, Value};
 
+use crate::checkpoint::CheckpointSession;
+use crate::core::lock::FileLocks;
+use crate::core::temp::TempPaths;
+use crate::flow::FlowLimits;
+use crate::fs_policy::{FsAccess, FsPolicy};
 use crate::http::manager::{HttpHostControl, HttpHostManager};
+use crate::secrets::SecretStore;
 use crate::streams::StreamManager;
 
-pub trait SlotExecutor {
+pub trait SlotExecutor: Send {
     fn run_slot(
         &mut self,
         ctx: &mut Context,
@@ -23,6 +30,11 @@ pub trait SlotExecutor {
     fn into_fallback(self: Box<Self>) -> Option<Box<dyn SlotExecutor + 'static>> {
         None
     }
+
+    /// Independent copy used by forked contexts (concurrent slot execution).
+    fn fork(&self) -> Option<Box<dyn SlotExecutor + 'static>> {
+        None
+    }
 }
 
 #[derive(Debug)]
@@ -72,6 +84,7 @@ impl ComponentEntry {
 struct RegistryInner {
     funcs: HashMap<String, Arc<ComponentEntry>>,
     bindings: HashMap<String, String>,
+    fs_policy: Arc<FsPolicy>,
 }
 
 impl RegistryInner {
@@ -79,6 +92,7 @@ impl RegistryInner {
         Self {
             funcs: HashMap::new(),
             bindings: HashMap::new(),
+            fs_policy: Arc::new(FsPolicy::unrestricted()),
         }
     }
 }
@@ -171,6 +185,26 @@ impl Registry {
         inner.funcs.insert(name.into(), entry);
     }
 
+    /// Runs `body`, then puts back whatever `ids` resolved to beforehand, so
+    /// registrations made for those ids inside `body` do not outlive it.
+    pub(crate) fn with_restored_entries<T>(&self, ids: &[String], body: impl FnOnce() -> T) -> T {
+        let saved: Vec<(String, Option<Arc<ComponentEntry>>)> = {
+            let inner = self.inner.lock().expect("registry poisoned");
+            ids.iter()
+                .map(|id| (id.clone(), inner.funcs.get(id).cloned()))
+                .collect()
+        };
+        let result = body();
+        let mut inner = self.inner.lock().expect("registry poisoned");
+        for (id, entry) in saved {
+            match entry {
+                Some(entry) => inner.funcs.insert(id, entry),
+                None => inner.funcs.remove(&id),
+            };
+        }
+        result
+    }
+
     pub fn set_binding(&self, contract: impl Into<String>, implementation: impl Into<String>) {
         let mut inner = self.inner.lock().expect("registry poisoned");
         inner
@@ -178,6 +212,12 @@ impl Registry {
             .insert(contract.into(), implementation.into());
     }
 
+    /// Default filesystem policy for contexts created from this registry.
+    pub fn set_fs_policy(&self, policy: FsPolicy) {
+        let mut inner = self.inner.lock().expect("registry poisoned");
+        inner.fs_policy = Arc::new(policy);
+    }
+
     pub fn call(
         &self,
         ctx: &mut Context,
@@ -276,10 +316,22 @@ pub struct Context {
     spec_captured_logs: Vec<Value>,
     spec_logs_truncated: bool,
     cancellation: Arc<AtomicBool>,
+    checkpoint: Option<CheckpointSession>,
+    secrets: SecretStore,
+    flow_limits: FlowLimits,
+    file_locks: FileLocks,
+    temp_paths: TempPaths,
+    temp_scopes: Vec<u64>,
+    fs_policy: Arc<FsPolicy>,
 }
 
 impl Context {
     fn new(registry: Arc<Mutex<RegistryInner>>, cancellation: Arc<AtomicBool>) -> Self {
+        let fs_policy = registry
+            .lock()
+            .expect("registry poisoned")
+            .fs_policy
+            .clone();
         Self {
             registry,
             scope_depth: 0,
@@ -292,6 +344,13 @@ impl Context {
             spec_captured_logs: Vec::new(),
             spec_logs_truncated: false,
             cancellation,
+            checkpoint: None,
+            secrets: SecretStore::new(),
+            flow_limits: FlowLimits::new(),
+            file_locks: FileLocks::new(),
+            temp_paths: TempPaths::new(),
+            temp_scopes: Vec::new(),
+            fs_policy,
         }
     }
 
@@ -384,6 +443,10 @@ impl Context {
         }
     }
 
+    pub fn scope_depth(&self) -> usize {
+        self.scope_depth
+    }
+
     pub fn streams_mut(&mut self) -> &mut StreamManager {
         &mut self.streams
     }
@@ -404,6 +467,22 @@ impl Context {
         self.http_hosts.stop_all();
     }
 
+    pub fn has_http_host(&self, handle: &Value) -> bool {
+        self.http_hosts.contains_handle(handle)
+    }
+
+    pub fn set_checkpoint_session(&mut self, session: Option<CheckpointSession>) {
+        self.checkpoint = session;
+    }
+
+    pub fn take_checkpoint_session(&mut self) -> Option<CheckpointSession> {
+        self.checkpoint.take()
+    }
+
+    pub fn checkpoint_session_mut(&mut self) -> Option<&mut CheckpointSession> {
+        self.checkpoint.as_mut()
+    }
+
     pub fn enter_registry_scope(
         &mut self,
         bindings: Option<HashMap<String, String>>,
@@ -427,10 +506,14 @@ impl Context {
             inner.funcs = snapshot.funcs.clone();
         }
         self.registry_scope_stack.push(snapshot);
+        self.temp_scopes.push(self.temp_paths.open_scope());
         Ok(())
     }
 
     pub fn leave_registry_scope(&mut self) -> Result<()> {
+        if let Some(scope) = self.temp_scopes.pop() {
+            self.temp_paths.close_scope(scope);
+        }
         if let Some(previous) = self.registry_scope_stack.pop() {
             let mut inner = self.registry.lock().expect("registry poisoned");
             inner.bindings = previous.bindings;
@@ -441,10 +524,21 @@ impl Context {
 
     pub fn fork(&self) -> Context {
         let mut cloned = Context::new(self.registry.clone(), self.cancellation.clone());
+        cloned.scope_depth = self.scope_depth;
+        cloned.run_slot_handler = self
+            .run_slot_handler
+            .as_ref()
+            .and_then(|handler| handler.fork());
         cloned.log_tag_stack = self.log_tag_stack.clone();
         cloned.raw_input_stack = self.raw_input_stack.clone();
         cloned.spec_captured_logs = self.spec_captured_logs.clone();
         cloned.spec_logs_truncated = self.spec_logs_truncated;
+        cloned.secrets = self.secrets.clone();
+        cloned.flow_limits = self.flow_limits.clone();
+        cloned.file_locks = self.file_locks.clone();
+        cloned.temp_paths = self.temp_paths.clone();
+        cloned.temp_scopes = self.temp_scopes.clone();
+        cloned.fs_policy = self.fs_policy.clone();
         cloned
     }
 
@@ -478,6 +572,43 @@ impl Context {
         }
     }
 
+    pub fn secrets(&self) -> &SecretStore {
+        &self.secrets
+    }
+
+    pub fn register_secret(&self, value: &str) -> bool {
+        self.secrets.register(value)
+    }
+
+    pub fn flow_limits(&self) -> &FlowLimits {
+        &self.flow_limits
+    }
+
+    /// Deletes `path` when the innermost registry scope is left, or when the
+    /// context and its forks are gone if no scope is open.
+    pub fn track_temp_path(&self, path: PathBuf) {
+        self.temp_paths
+            .track(path, self.temp_scopes.last().copied());
+    }
+
+    pub fn file_locks(&self) -> &FileLocks {
+        &self.file_locks
+    }
+
+    pub fn fs_policy(&self) -> &FsPolicy {
+        &self.fs_policy
+    }
+
+    pub fn set_fs_policy(&mut self, policy: FsPolicy) {
+        self.fs_policy = Arc::new(policy);
+    }
+
+    /// Fails with a `PermissionError` when the filesystem policy forbids
+    /// `access` to `path`.
+    pub fn check_fs_access(&self, path: &Path, access: FsAccess) -> Result<()> {
+        self.fs_policy.check(path, access)
+    }
+
     pub fn push_log_tags(&mut self, tags: Map<String, Value>) {
         if tags.is_empty() {
             return;
@@ -0,0 +1,83 @@
+use std::sync::{Arc, RwLock};
+
+use serde_json::Value;
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Values shorter than this are not masked: redacting every occurrence of a
+/// one or two character string would make logs unreadable without protecting
+/// anything.
+const MIN_SECRET_LEN: usize = 4;
+
+/// Secret values seen during a run. Shared between a context and its forks so
+/// that anything registered by a branch is also masked by its parent.
+#[derive(Clone, Debug, Default)]
+pub struct SecretStore {
+    values: Arc<RwLock<Vec<String>>>,
+}
+
+impl SecretStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks `value` as secret. Returns `false` when it is too short to be
+    /// masked or was already known.
+    pub fn register(&self, value: &str) -> bool {
+        if value.chars().count() < MIN_SECRET_LEN {
+            return false;
+        }
+        let mut values = self.values.write().expect("secret store poisoned");
+        if values.iter().any(|existing| existing == value) {
+            return false;
+        }
+        values.push(value.to_string());
+        // Longest first so a secret containing another one is masked whole.
+        values.sort_by_key(|existing| std::cmp::Reverse(existing.len()));
+        true
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values
+            .read()
+            .expect("secret store poisoned")
+            .is_empty()
+    }
+
+    pub fn redact_str(&self, text: &str) -> String {
+        let values = self.values.read().expect("secret store poisoned");
+        let mut output = text.to_string();
+        for secret in values.iter() {
+            if output.contains(secret.as_str()) {
+                output = output.replace(secret.as_str(), REDACTED);
+            }
+        }
+        output
+    }
+
+    pub fn redact_value(&self, value: &Value) -> Value {
+        let mut copy = value.clone();
+        self.redact_in_place(&mut copy);
+        copy
+    }
+
+    pub fn redact_in_place(&self, value: &mut Value) {
+        if self.is_empty() {
+            return;
+        }
+        match value {
+            Value::String(text) => *text = self.redact_str(text),
+            Value::Array(items) => {
+                for item in items {
+                    self.redact_in_place(item);
+                }
+            }
+            Value::Object(map) => {
+                for item in map.values_mut() {
+                    self.redact_in_place(item);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,4 +1,8 @@
 use std::collections::HashMap;
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
 
 use anyhow::{anyhow, Result};
 use base64::Engine;
@@ -7,23 +11,142 @@ use serde_json::{json, Map, Value};
 #[derive(Default)]
 pub struct StreamManager {
     entries: HashMap<String, StreamEntry>,
+    cursors: HashMap<String, CursorEntry>,
     counter: u64,
 }
 
+/// Records parsed from a stream, pulled a batch at a time through a cursor.
+pub trait RecordSource: Send {
+    fn next_batch(&mut self, max: usize) -> Result<RecordBatch>;
+}
+
+#[derive(Debug, Default)]
+pub struct RecordBatch {
+    pub rows: Vec<Value>,
+    pub warnings: Vec<String>,
+    pub done: bool,
+}
+
+struct CursorEntry {
+    handle: Value,
+    source: Box<dyn RecordSource>,
+    total: u64,
+    done: bool,
+}
+
+/// A stream taken out of the manager, read through `std::io::Read` by
+/// parsers that keep their own buffering between calls.
+pub struct DetachedStream {
+    entry: StreamEntry,
+}
+
+impl Read for DetachedStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let bytes = self
+            .entry
+            .take(Some(buf.len()), false)
+            .map_err(std::io::Error::other)?;
+        buf[..bytes.len()].copy_from_slice(&bytes);
+        Ok(bytes.len())
+    }
+}
+
 struct StreamEntry {
     handle: Value,
     encoding: String,
-    chunks: Vec<Vec<u8>>,
+    source: StreamSource,
     pending: Vec<u8>,
-    index: usize,
     done: bool,
     seq: u64,
 }
 
+enum StreamSource {
+    Chunks {
+        chunks: Vec<Vec<u8>>,
+        index: usize,
+    },
+    /// Pulled lazily, `chunk_size` bytes at a time (files and other large
+    /// payloads that should not be buffered).
+    Reader {
+        reader: Box<dyn Read + Send>,
+        chunk_size: usize,
+    },
+    /// Fed by a producer thread; reads return whatever has arrived instead
+    /// of waiting for `maxBytes`, and the stream ends when the sender is
+    /// dropped.
+    Channel {
+        receiver: Receiver<Vec<u8>>,
+        _stop: StopOnDrop,
+    },
+}
+
+/// Raises the producer's stop flag once the stream is closed or dropped, so
+/// a producer with nothing to send does not outlive its reader.
+struct StopOnDrop(Arc<AtomicBool>);
+
+impl Drop for StopOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+impl StreamSource {
+    fn next_chunk(&mut self) -> Result<Option<Vec<u8>>> {
+        match self {
+            StreamSource::Chunks { chunks, index } => {
+                let chunk = chunks.get(*index).cloned();
+                if chunk.is_some() {
+                    *index += 1;
+                }
+                Ok(chunk)
+            }
+            StreamSource::Reader { reader, chunk_size } => {
+                let mut chunk = vec![0; *chunk_size];
+                let read = loop {
+                    match reader.read(&mut chunk) {
+                        Ok(read) => break read,
+                        Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                        Err(err) => return Err(anyhow!("stream read failed: {err}")),
+                    }
+                };
+                if read == 0 {
+                    return Ok(None);
+                }
+                chunk.truncate(read);
+                Ok(Some(chunk))
+            }
+            StreamSource::Channel { receiver, .. } => Ok(receiver.recv().ok()),
+        }
+    }
+
+    /// Next chunk if one is ready without blocking, for live sources only.
+    fn poll_chunk(&mut self) -> Option<Vec<u8>> {
+        match self {
+            StreamSource::Channel { receiver, .. } => receiver.try_recv().ok(),
+            _ => None,
+        }
+    }
+
+    fn is_live(&self) -> bool {
+        matches!(self, StreamSource::Channel { .. })
+    }
+
+    fn is_exhausted(&self) -> bool {
+        match self {
+            StreamSource::Chunks { chunks, index } => *index >= chunks.len(),
+            StreamSource::Reader { .. } | StreamSource::Channel { .. } => false,
+        }
+    }
+}
+
 impl StreamManager {
     pub fn new() -> Self {
         Self {
             entries: HashMap::new(),
+            cursors: HashMap::new(),
             counter: 0,
         }
     }
@@ -32,6 +155,45 @@ impl StreamManager {
     where
         I: IntoIterator<Item = Vec<u8>>,
     {
+        self.register_source(
+            StreamSource::Chunks {
+                chunks: chunks.into_iter().collect(),
+                index: 0,
+            },
+            encoding,
+        )
+    }
+
+    /// Registers a stream whose bytes are read from `reader` on demand.
+    pub fn register_reader<R>(&mut self, reader: R, encoding: &str, chunk_size: usize) -> Value
+    where
+        R: Read + Send + 'static,
+    {
+        self.register_source(
+            StreamSource::Reader {
+                reader: Box::new(reader),
+                chunk_size: chunk_size.max(1),
+            },
+            encoding,
+        )
+    }
+
+    /// Registers a stream fed chunk by chunk through `receiver`; `stop` is
+    /// set when the stream is closed or dropped.
+    pub fn register_channel(
+        &mut self,
+        receiver: Receiver<Vec<u8>>,
+        stop: Arc<AtomicBool>,
+        encoding: &str,
+    ) -> Value {
+        let source = StreamSource::Channel {
+            receiver,
+            _stop: StopOnDrop(stop),
+        };
+        self.register_source(source, encoding)
+    }
+
+    fn register_source(&mut self, source: StreamSource, encoding: &str) -> Value {
         self.counter += 1;
         let id = format!("stream-{}", self.counter);
         let handle = Value::Object({
@@ -43,9 +205,8 @@ impl StreamManager {
         let entry = StreamEntry {
             handle: handle.clone(),
             encoding: encoding.to_string(),
-            chunks: chunks.into_iter().collect(),
+            source,
             pending: Vec::new(),
-            index: 0,
             done: false,
             seq: 0,
         };
@@ -53,44 +214,30 @@ impl StreamManager {
         handle
     }
 
+    /// Takes up to `max_bytes` raw bytes (everything left when `None`).
+    /// Returns `None` once the stream is exhausted.
+    pub fn read_bytes(
+        &mut self,
+        stream: &Value,
+        max_bytes: Option<usize>,
+    ) -> Result<Option<Vec<u8>>> {
+        let entry = self.entry_mut(stream)?;
+        let buffer = entry.take(max_bytes, false)?;
+        Ok((!buffer.is_empty()).then_some(buffer))
+    }
+
     pub fn read(
         &mut self,
         stream: &Value,
         max_bytes: Option<usize>,
         decode: Option<&str>,
     ) -> Result<Value> {
-        let id = extract_id(stream)?;
-        let entry = self
-            .entries
-            .get_mut(&id)
-            .ok_or_else(|| anyhow!("Unknown stream handle: {id}"))?;
-
-        if entry.done && entry.pending.is_empty() {
-            return Ok(json!({
-                "done": true,
-                "stream": entry.handle.clone()
-            }));
-        }
-
-        let mut buffer = std::mem::take(&mut entry.pending);
-
-        if let Some(limit) = max_bytes {
-            while buffer.len() < limit && entry.index < entry.chunks.len() {
-                let chunk = entry.chunks[entry.index].clone();
-                entry.index += 1;
-                buffer.extend_from_slice(&chunk);
-            }
-        } else {
-            while entry.index < entry.chunks.len() {
-                let chunk = entry.chunks[entry.index].clone();
-                entry.index += 1;
-                buffer.extend_from_slice(&chunk);
-            }
-        }
-
-        if entry.index >= entry.chunks.len() {
-            entry.done = true;
-        }
+        let entry = self.entry_mut(stream)?;
+        let encoding = decode
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| entry.encoding.clone());
+        let utf8 = matches!(encoding.as_str(), "utf-8" | "utf8");
+        let buffer = entry.take(max_bytes, utf8)?;
 
         if buffer.is_empty() {
             return Ok(json!({
@@ -99,21 +246,10 @@ impl StreamManager {
             }));
         }
 
-        let mut carry = Vec::new();
-        if let Some(limit) = max_bytes {
-            if buffer.len() > limit {
-                carry = buffer.split_off(limit);
-            }
-        }
-        entry.pending = carry;
-
-        let encoding = decode
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| entry.encoding.clone());
-        let output = match encoding.as_str() {
-            "utf-8" | "utf8" => String::from_utf8(buffer.clone())?,
-            "base64" => base64::engine::general_purpose::STANDARD.encode(&buffer),
-            _ => base64::engine::general_purpose::STANDARD.encode(&buffer),
+        let output = if utf8 {
+            String::from_utf8(buffer.clone())?
+        } else {
+            base64::engine::general_purpose::STANDARD.encode(&buffer)
         };
 
         let seq = entry.seq;
@@ -129,6 +265,13 @@ impl StreamManager {
         }))
     }
 
+    fn entry_mut(&mut self, stream: &Value) -> Result<&mut StreamEntry> {
+        let id = extract_id(stream)?;
+        self.entries
+            .get_mut(&id)
+            .ok_or_else(|| anyhow!("Unknown stream handle: {id}"))
+    }
+
     pub fn close(&mut self, stream: &Value) -> Result<Value> {
         let id = extract_id(stream)?;
         self.entries
@@ -137,6 +280,69 @@ impl StreamManager {
         Ok(json!({ "released": true }))
     }
 
+    /// Removes the stream from the manager and hands it over to the caller.
+    pub fn detach(&mut self, stream: &Value) -> Result<DetachedStream> {
+        let id = extract_id(stream)?;
+        let entry = self
+            .entries
+            .remove(&id)
+            .ok_or_else(|| anyhow!("Unknown stream handle: {id}"))?;
+        Ok(DetachedStream { entry })
+    }
+
+    pub fn register_cursor(&mut self, source: Box<dyn RecordSource>, format: &str) -> Value {
+        self.counter += 1;
+        let id = format!("cursor-{}", self.counter);
+        let handle = json!({ "id": id, "format": format });
+        self.cursors.insert(
+            id,
+            CursorEntry {
+                handle: handle.clone(),
+                source,
+                total: 0,
+                done: false,
+            },
+        );
+        handle
+    }
+
+    pub fn next_records(&mut self, cursor: &Value, max: usize) -> Result<Value> {
+        let id = extract_id(cursor)?;
+        let entry = self
+            .cursors
+            .get_mut(&id)
+            .ok_or_else(|| anyhow!("Unknown cursor handle: {id}"))?;
+        let batch = if entry.done {
+            RecordBatch {
+                done: true,
+                ..RecordBatch::default()
+            }
+        } else {
+            entry.source.next_batch(max.max(1))?
+        };
+        entry.done = batch.done;
+        entry.total += batch.rows.len() as u64;
+
+        let mut out = Map::new();
+        out.insert("count".to_string(), json!(batch.rows.len()));
+        out.insert("rows".to_string(), Value::Array(batch.rows));
+        out.insert("done".to_string(), Value::Bool(batch.done));
+        out.insert("total".to_string(), json!(entry.total));
+        if !batch.warnings.is_empty() {
+            out.insert("warnings".to_string(), json!(batch.warnings));
+        }
+        out.insert("cursor".to_string(), entry.handle.clone());
+        Ok(Value::Object(out))
+    }
+
+    pub fn close_cursor(&mut self, cursor: &Value) -> Result<Value> {
+        let id = extract_id(cursor)?;
+        self.cursors
+            .remove(&id)
+            .ok_or_else(|| anyhow!("Unknown cursor handle: {id}"))?;
+        Ok(json!({ "released": true }))
+    }
+
     pub fn contains_handle(&self, stream: &Value) -> bool {
         extract_id(stream)
             .ok()
@@ -145,6 +351,51 @@ impl StreamManager {
     }
 }
 
+impl StreamEntry {
+    /// Collects up to `max_bytes` from the pending carry and the source. For
+    /// text reads a multi-byte character cut at the end is kept for the next
+    /// read instead of failing to decode.
+    fn take(&mut self, max_bytes: Option<usize>, utf8: bool) -> Result<Vec<u8>> {
+        if self.done && self.pending.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut buffer = std::mem::take(&mut self.pending);
+        while !self.done && max_bytes.is_none_or(|limit| buffer.len() < limit) {
+            if !buffer.is_empty() && self.source.is_live() {
+                match self.source.poll_chunk() {
+                    Some(chunk) => buffer.extend_from_slice(&chunk),
+                    None => break,
+                }
+                continue;
+            }
+            match self.source.next_chunk()? {
+                Some(chunk) => buffer.extend_from_slice(&chunk),
+                None => self.done = true,
+            }
+        }
+        if self.source.is_exhausted() {
+            self.done = true;
+        }
+
+        if let Some(limit) = max_bytes {
+            if buffer.len() > limit {
+                self.pending = buffer.split_off(limit);
+            }
+        }
+        if utf8 {
+            if let Err(err) = std::str::from_utf8(&buffer) {
+                let incomplete = err.error_len().is_none() && err.valid_up_to() > 0;
+                if incomplete && (!self.done || !self.pending.is_empty()) {
+                    let mut tail = buffer.split_off(err.valid_up_to());
+                    tail.append(&mut self.pending);
+                    self.pending = tail;
+                }
+            }
+        }
+        Ok(buffer)
+    }
+}
+
 fn extract_id(stream: &Value) -> Result<String> {
     let obj = stream
         .as_object()
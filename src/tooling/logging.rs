@@ -240,6 +240,10 @@ fn emit_log(ctx: &mut Context, input: Value, kernel_tags: bool) -> Result<Value>
         }
     }
 
+    for value in entry.values_mut() {
+        ctx.secrets().redact_in_place(value);
+    }
+
     if let Some(target) = ctx.binding_for(LOG_CONTRACT_ID) {
         if target != LOG_CONTRACT_ID && target != KERNEL_HELPER_ID {
             let cloned = ctx.registry_clone();
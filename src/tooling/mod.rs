@@ -13,7 +13,14 @@ use serde_json::{json, Map, Value};
 use sha2::{Digest, Sha256};
 use toml::Value as TomlValue;
 
-use crate::compose::{parse_compose, run_compose};
+use crate::compose::{parse_compose, run_compose_with_signature};
+use crate::compose_loader::{
+    id_path, id_version, load_compose_document, load_compose_document_with_scope, ComposeDocument,
+    IdScope,
+};
+use crate::compose_signature::ComposeSignature;
+use crate::core::glob::{glob, GlobOptions};
+use crate::fs_policy::FsAccess;
 use crate::registry::{ComponentMetadata, Context, Registry};
 
 mod common;
@@ -428,11 +435,13 @@ fn run_spec_register_components(
                 register_path.display()
             ));
         }
-        let steps = load_compose_from_path(&register_path)?;
+        let document = load_compose_document(&register_path)?;
+        document.register_imports(registry);
         let mut ctx = registry.context();
-        run_compose(
+        run_compose_with_signature(
             &mut ctx,
-            &steps,
+            &document.steps,
+            document.signature.as_ref(),
             json!({ "specRoot": crate::core::path::path_to_string(&spec_root) }),
         )
     })();
@@ -467,24 +476,29 @@ fn register_resolver_helpers(registry: &Registry) {
                     continue;
                 }
 
-                let (compose_steps, compose_path_buf) = if let Some(inline) =
+                let (compose_steps, signature, compose_path_buf) = if let Some(inline) =
                     component.get("compose")
                 {
                     let steps = parse_compose(inline).with_context(|| {
                         format!("resolver/register: invalid inline compose for {}", id_raw)
                     })?;
-                    (steps, None)
+                    let signature =
+                        ComposeSignature::from_document(&component).with_context(|| {
+                            format!("resolver/register: invalid compose header for {}", id_raw)
+                        })?;
+                    (steps, signature, None)
                 } else if let Some(path_str) = component.get("composePath").and_then(Value::as_str)
                 {
                     let path = PathBuf::from(path_str);
-                    let steps = load_compose_from_path(&path).with_context(|| {
+                    let document = load_compose_document(&path).with_context(|| {
                         format!(
                             "resolver/register: failed to load compose for {} from {}",
                             id_raw,
                             path.display()
                         )
                     })?;
-                    (steps, Some(path))
+                    document.register_imports(&dynamic_registry);
+                    (document.steps, document.signature, Some(path))
                 } else {
                     warnings.push(format!(
                         "resolver/register: component {} missing compose data",
@@ -500,6 +514,7 @@ fn register_resolver_helpers(registry: &Registry) {
                 .and_then(|manifest_path| load_component_metadata(&manifest_path));
 
                 let steps_arc = Arc::new(compose_steps);
+                let signature_arc = Arc::new(signature);
                 let id_string = id_raw.to_string();
                 let registry_clone = dynamic_registry.clone();
                 let metadata_arc = metadata.map(Arc::new);
@@ -509,7 +524,12 @@ fn register_resolver_helpers(registry: &Registry) {
                           input_inner: Value,
                           _meta_inner: Option<Value>| {
                         let steps = Arc::clone(&steps_arc);
-                        run_compose(ctx_inner, &steps, input_inner)
+                        run_compose_with_signature(
+                            ctx_inner,
+                            &steps,
+                            signature_arc.as_ref().as_ref(),
+                            input_inner,
+                        )
                     },
                     metadata_arc.clone(),
                 );
@@ -583,11 +603,19 @@ fn register_resolver_helpers(registry: &Registry) {
             registry.register_with_metadata(
                 id,
                 move |ctx: &mut Context, input: Value, _meta: Option<Value>| {
-                    let steps =
-                        load_helper_compose(&compose_path, &context).with_context(|| {
+                    let document =
+                        load_compose_document_with_scope(&compose_path, context.as_ref().clone())
+                            .with_context(|| {
                             format!("unable to load resolver helper {}", id_arc.as_ref())
                         })?;
-                    run_compose(ctx, &steps, input)
+                    document.with_imports(ctx, |ctx| {
+                        run_compose_with_signature(
+                            ctx,
+                            &document.steps,
+                            document.signature.as_ref(),
+                            input,
+                        )
+                    })
                 },
                 metadata_handle.clone(),
             );
@@ -595,17 +623,10 @@ fn register_resolver_helpers(registry: &Registry) {
     }
 }
 
-#[derive(Clone)]
-struct HelperContext {
-    base_path: String,
-    version: String,
-    alias_map: HashMap<String, String>,
-}
-
 struct ResolverHelperDef {
     id: String,
     compose_path: PathBuf,
-    context: HelperContext,
+    context: IdScope,
     aliases: Vec<String>,
     metadata: Option<ComponentMetadata>,
 }
@@ -758,6 +779,30 @@ fn gather_candidates() -> Vec<Candidate> {
     out
 }
 
+/// Expands workspace entries such as `/repos/*/components` into the matching
+/// directories; plain paths are returned unchanged.
+fn expand_workspace_entry(entry: PathBuf) -> Vec<PathBuf> {
+    let raw = crate::core::path::path_to_string(&entry);
+    let Some(split) = raw.find(['*', '?', '[', '{']) else {
+        return vec![entry];
+    };
+    let (root, pattern) = match raw[..split].rfind('/') {
+        Some(slash) => (&raw[..slash.max(1)], &raw[slash + 1..]),
+        None => (".", raw.as_str()),
+    };
+    let options = GlobOptions {
+        patterns: vec![pattern.to_string()],
+        include_directories: true,
+        ..GlobOptions::default()
+    };
+    glob(Path::new(root), &options)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|found| found.entry_type == "directory")
+        .map(|found| found.path)
+        .collect()
+}
+
 fn collect_workspace_paths() -> Vec<PathBuf> {
     let mut paths = Vec::new();
 
@@ -771,7 +816,7 @@ fn collect_workspace_paths() -> Vec<PathBuf> {
     ] {
         if let Ok(raw) = env::var(var) {
             for entry in env::split_paths(&raw) {
-                paths.push(entry);
+                paths.extend(expand_workspace_entry(entry));
             }
         }
     }
@@ -823,7 +868,7 @@ fn append_spec_fallbacks(collected: &mut Vec<ResolverHelperDef>) {
         collected.push(ResolverHelperDef {
             id: id.to_string(),
             compose_path: compose_path.clone(),
-            context: HelperContext {
+            context: IdScope {
                 base_path: base_path.to_string(),
                 version: "0.1.0".to_string(),
                 alias_map: HashMap::new(),
@@ -1276,11 +1321,7 @@ fn path_join_chain_helper(_ctx: &mut Context, input: Value, _meta: Option<Value>
     Ok(json!({ "path": path_str }))
 }
 
-fn fs_read_optional_helper(
-    _ctx: &mut Context,
-    input: Value,
-    _meta: Option<Value>,
-) -> Result<Value> {
+fn fs_read_optional_helper(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
     let encoding = non_empty_string(input.get("encoding")).unwrap_or_else(|| "utf-8".to_string());
     let path_value = non_empty_string(input.get("path"));
     let fallback = non_empty_string(input.get("fallback"));
@@ -1295,6 +1336,7 @@ fn fs_read_optional_helper(
     }
 
     let path_str = path_value.unwrap();
+    ctx.check_fs_access(Path::new(&path_str), FsAccess::Read)?;
     match fs::read(&path_str) {
         Ok(bytes) => {
             let text = if encoding.eq_ignore_ascii_case("utf-8") {
@@ -1323,7 +1365,7 @@ fn fs_read_optional_helper(
 }
 
 fn fs_write_if_changed_helper(
-    _ctx: &mut Context,
+    ctx: &mut Context,
     input: Value,
     _meta: Option<Value>,
 ) -> Result<Value> {
@@ -1332,6 +1374,7 @@ fn fs_write_if_changed_helper(
         .and_then(Value::as_str)
         .filter(|s| !s.is_empty())
         .ok_or_else(|| anyhow!("write_if_changed: path is required"))?;
+    ctx.check_fs_access(Path::new(path_str), FsAccess::Write)?;
 
     let encoding = non_empty_string(input.get("encoding")).unwrap_or_else(|| "utf-8".to_string());
     let content = match input.get("content") {
@@ -1509,7 +1552,7 @@ fn canonicalize(value: &Value) -> Value {
     }
 }
 
-fn jsonl_read_helper(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+fn jsonl_read_helper(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
     if let Some(url) = input.get("url").and_then(Value::as_str) {
         return Err(anyhow!("jsonl/read does not support url input yet: {url}"));
     }
@@ -1528,6 +1571,7 @@ fn jsonl_read_helper(_ctx: &mut Context, input: Value, _meta: Option<Value>) ->
     let Some(path) = input.get("path").and_then(Value::as_str) else {
         return Err(anyhow!("jsonl/read requires a `path`"));
     };
+    ctx.check_fs_access(Path::new(path), FsAccess::Read)?;
 
     let file =
         fs::File::open(path).with_context(|| format!("unable to open JSONL file: {path}"))?;
@@ -1814,7 +1858,7 @@ fn load_package_definitions(
             return Vec::new();
         }
     };
-    let context = create_context(&manifest, workspace_aliases);
+    let context = IdScope::from_manifest(&manifest, workspace_aliases);
     let mut defs = Vec::new();
     let components_dir = manifest
         .get("workspace")
@@ -1844,7 +1888,7 @@ fn load_package_definitions(
 fn load_package_components_from_dir(
     pkg_dir: &Path,
     components_dir: &str,
-    context: &HelperContext,
+    context: &IdScope,
 ) -> Vec<ResolverHelperDef> {
     let resolved_dir = if Path::new(components_dir).is_absolute() {
         PathBuf::from(components_dir)
@@ -1909,7 +1953,7 @@ fn load_package_components_from_dir(
             continue;
         };
         let metadata = load_component_metadata(&manifest_path);
-        let canonical_id = canonicalize_id(component_id_raw, context);
+        let canonical_id = context.canonicalize_id(component_id_raw);
         let mut aliases = Vec::new();
         if canonical_id != component_id_raw {
             aliases.push(component_id_raw.to_string());
@@ -1926,44 +1970,17 @@ fn load_package_components_from_dir(
 }
 
 fn collect_component_directories(root: &Path) -> Vec<PathBuf> {
-    let mut stack = vec![root.to_path_buf()];
-    let mut collected = Vec::new();
-
-    while let Some(dir) = stack.pop() {
-        let Ok(entries) = fs::read_dir(&dir) else {
-            continue;
-        };
-
-        let mut has_manifest = false;
-        let mut has_compose = false;
-        let mut subdirs = Vec::new();
-
-        for entry in entries.flatten() {
-            let path = entry.path();
-            match entry.file_type() {
-                Ok(ft) if ft.is_dir() => {
-                    subdirs.push(path);
-                }
-                Ok(_) | Err(_) => {
-                    if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                        if name == "lcp.toml" {
-                            has_manifest = true;
-                        } else if name == "compose.yaml" {
-                            has_compose = true;
-                        }
-                    }
-                }
-            }
-        }
-
-        if has_manifest && has_compose {
-            collected.push(dir.clone());
-        }
-
-        stack.extend(subdirs);
-    }
-
-    collected
+    let options = GlobOptions {
+        patterns: vec!["**/lcp.toml".to_string()],
+        include_hidden: true,
+        ..GlobOptions::default()
+    };
+    glob(root, &options)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|found| found.path.parent().map(Path::to_path_buf))
+        .filter(|dir| dir.join("compose.yaml").is_file())
+        .collect()
 }
 
 fn load_component_metadata(manifest_path: &Path) -> Option<ComponentMetadata> {
@@ -1986,59 +2003,6 @@ fn extract_metadata_keys(section: Option<&TomlValue>) -> Vec<String> {
         .unwrap_or_default()
 }
 
-fn create_context(
-    manifest: &TomlValue,
-    workspace_aliases: &HashMap<String, String>,
-) -> HelperContext {
-    let mut alias_map = workspace_aliases.clone();
-    if let Some(package_aliases) = manifest
-        .get("workspace")
-        .and_then(TomlValue::as_table)
-        .and_then(|w| w.get("scopeAliases"))
-        .and_then(TomlValue::as_table)
-    {
-        for (key, value) in package_aliases {
-            if let Some(alias) = value.as_str() {
-                alias_map.insert(key.to_string(), alias.to_string());
-            }
-        }
-    }
-
-    let manifest_id = manifest.get("id").and_then(TomlValue::as_str);
-    let base_path = manifest_id
-        .and_then(|id| extract_path_from_id(id).map(|s| s.to_string()))
-        .unwrap_or_else(|| {
-            let ns = manifest
-                .get("namespace")
-                .and_then(TomlValue::as_str)
-                .unwrap_or("");
-            let name = manifest
-                .get("name")
-                .and_then(TomlValue::as_str)
-                .unwrap_or("");
-            if ns.is_empty() {
-                name.to_string()
-            } else if name.is_empty() {
-                ns.to_string()
-            } else {
-                format!("{}/{}", ns, name)
-            }
-        });
-
-    let version = manifest
-        .get("version")
-        .and_then(TomlValue::as_str)
-        .map(|s| s.to_string())
-        .or_else(|| manifest_id.and_then(|id| extract_version_from_id(id).map(|v| v.to_string())))
-        .unwrap_or_else(|| "0.0.0".to_string());
-
-    HelperContext {
-        base_path,
-        version,
-        alias_map,
-    }
-}
-
 fn load_legacy_component_definitions(dir: &Path) -> Vec<ResolverHelperDef> {
     if !dir.exists() {
         return Vec::new();
@@ -2078,7 +2042,7 @@ fn load_legacy_component_definitions(dir: &Path) -> Vec<ResolverHelperDef> {
         let Some(component_id) = manifest.get("id").and_then(TomlValue::as_str) else {
             continue;
         };
-        let base_path = extract_path_from_id(component_id)
+        let base_path = id_path(component_id)
             .map(|p| {
                 let mut base = p.to_string();
                 if let Some(pos) = base.rfind('/') {
@@ -2089,14 +2053,12 @@ fn load_legacy_component_definitions(dir: &Path) -> Vec<ResolverHelperDef> {
                 base
             })
             .unwrap_or_default();
-        let version = extract_version_from_id(component_id)
-            .unwrap_or("0.0.0")
-            .to_string();
+        let version = id_version(component_id).unwrap_or("0.0.0").to_string();
         let metadata = load_component_metadata(&manifest_path);
         defs.push(ResolverHelperDef {
             id: component_id.to_string(),
             compose_path: compose_path.clone(),
-            context: HelperContext {
+            context: IdScope {
                 base_path,
                 version,
                 alias_map: HashMap::new(),
@@ -2108,132 +2070,17 @@ fn load_legacy_component_definitions(dir: &Path) -> Vec<ResolverHelperDef> {
     defs
 }
 
-fn extract_path_from_id(id: &str) -> Option<&str> {
-    if !id.starts_with("lcod://") {
-        return None;
-    }
-    id.strip_prefix("lcod://")?.split('@').next()
-}
-
-fn extract_version_from_id(id: &str) -> Option<&str> {
-    id.split('@').nth(1)
-}
-
-fn load_helper_compose(path: &Path, context: &HelperContext) -> Result<Vec<crate::compose::Step>> {
-    let content = fs::read_to_string(path)
-        .with_context(|| format!("unable to read compose file: {}", path.display()))?;
-    let mut doc: Value = serde_yaml::from_str(&content)
-        .with_context(|| format!("invalid YAML compose: {}", path.display()))?;
-    let compose_value = doc
-        .get_mut("compose")
-        .ok_or_else(|| anyhow!("compose root missing in {}", path.display()))?;
-    canonicalize_value(compose_value, context);
-    parse_compose(compose_value)
-        .with_context(|| format!("invalid compose structure in {}", path.display()))
-}
-
-fn canonicalize_value(value: &mut Value, context: &HelperContext) {
-    match value {
-        Value::Array(arr) => {
-            for item in arr.iter_mut() {
-                canonicalize_value(item, context);
-            }
-        }
-        Value::Object(map) => canonicalize_object(map, context),
-        _ => {}
-    }
-}
-
-fn canonicalize_object(map: &mut Map<String, Value>, context: &HelperContext) {
-    if let Some(Value::String(call)) = map.get_mut("call") {
-        let canonical = canonicalize_id(call, context);
-        *call = canonical;
-    }
-    if let Some(children) = map.get_mut("children") {
-        canonicalize_children(children, context);
-    }
-    if let Some(slots) = map.get_mut("slots") {
-        canonicalize_children(slots, context);
-    }
-    for (key, val) in map.iter_mut() {
-        if key == "call" || key == "children" || key == "slots" {
-            continue;
-        }
-        canonicalize_value(val, context);
-    }
-}
-
-fn canonicalize_children(value: &mut Value, context: &HelperContext) {
-    match value {
-        Value::Array(arr) => {
-            for item in arr.iter_mut() {
-                canonicalize_value(item, context);
-            }
-        }
-        Value::Object(map) => {
-            for val in map.values_mut() {
-                canonicalize_value(val, context);
-            }
-        }
-        _ => {}
-    }
-}
-
-fn canonicalize_id(raw: &str, context: &HelperContext) -> String {
-    if raw.starts_with("lcod://") {
-        return raw.to_string();
-    }
-    let trimmed = raw.trim_start_matches("./");
-    let segments: Vec<&str> = trimmed.split('/').filter(|s| !s.is_empty()).collect();
-    if segments.is_empty() {
-        return raw.to_string();
-    }
-    let alias = segments[0];
-    let mapped = context
-        .alias_map
-        .get(alias)
-        .map(|s| s.as_str())
-        .unwrap_or(alias);
-    let mut parts = Vec::new();
-    if !context.base_path.is_empty() {
-        parts.push(context.base_path.clone());
-    }
-    if !mapped.is_empty() {
-        parts.push(mapped.to_string());
-    }
-    for seg in segments.iter().skip(1) {
-        parts.push((*seg).to_string());
-    }
-    let full = parts.join("/");
-    if full.is_empty() {
-        return raw.to_string();
-    }
-    let version = if context.version.is_empty() {
-        "0.0.0"
-    } else {
-        context.version.as_str()
-    };
-    format!("lcod://{}@{}", full, version)
-}
-
 pub use resolver::register_resolver_axioms;
 
-fn load_compose_from_path(path: &Path) -> Result<Vec<crate::compose::Step>> {
-    let content = fs::read_to_string(path)
-        .with_context(|| format!("unable to read compose file: {}", path.display()))?;
-    let doc: Value = serde_yaml::from_str(&content)
-        .with_context(|| format!("invalid YAML compose: {}", path.display()))?;
-    let compose_value = doc
-        .get("compose")
-        .cloned()
-        .ok_or_else(|| anyhow!("compose root missing in {}", path.display()))?;
-    parse_compose(&compose_value)
-        .with_context(|| format!("invalid compose structure in {}", path.display()))
-}
-
-fn ensure_compose(input: &Value) -> Result<Vec<crate::compose::Step>> {
+fn ensure_compose(input: &Value) -> Result<ComposeDocument> {
     if let Some(compose) = input.get("compose") {
-        return parse_compose(compose).map_err(|err| anyhow!("invalid inline compose: {err}"));
+        let steps =
+            parse_compose(compose).map_err(|err| anyhow!("invalid inline compose: {err}"))?;
+        return Ok(ComposeDocument {
+            steps,
+            signature: None,
+            imports: Vec::new(),
+        });
     }
     if let Some(compose_ref) = input.get("composeRef") {
         let path_str = compose_ref
@@ -2241,7 +2088,7 @@ fn ensure_compose(input: &Value) -> Result<Vec<crate::compose::Step>> {
             .and_then(Value::as_str)
             .ok_or_else(|| anyhow!("composeRef.path must be a string"))?;
         let resolved = PathBuf::from(path_str);
-        return load_compose_from_path(&resolved);
+        return load_compose_document(&resolved);
     }
     Err(anyhow!("compose or composeRef.path must be provided"))
 }
@@ -2274,7 +2121,7 @@ fn test_checker(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result
         .cloned()
         .ok_or_else(|| anyhow!("expected output is required"))?;
 
-    let compose_steps = ensure_compose(&input)?;
+    let document = ensure_compose(&input)?;
 
     let mut initial_state = input
         .get("input")
@@ -2290,7 +2137,14 @@ fn test_checker(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result
     }
 
     let start = Instant::now();
-    let exec_result = run_compose(ctx, &compose_steps, initial_state);
+    let exec_result = document.with_imports(ctx, |ctx| {
+        run_compose_with_signature(
+            ctx,
+            &document.steps,
+            document.signature.as_ref(),
+            initial_state,
+        )
+    });
     let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
 
     let mut report = Map::new();
@@ -2331,5 +2185,7 @@ fn test_checker(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result
         report.insert("messages".to_string(), Value::Array(messages));
     }
 
-    Ok(Value::Object(report))
+    let mut report = Value::Object(report);
+    ctx.secrets().redact_in_place(&mut report);
+    Ok(report)
 }
@@ -4,7 +4,8 @@ use std::sync::Arc;
 use anyhow::{anyhow, Result};
 use serde_json::{json, Map, Value};
 
-use crate::compose::{parse_compose, run_compose};
+use crate::compose::{parse_compose, run_compose_with_signature};
+use crate::compose_signature::ComposeSignature;
 use crate::registry::{ComponentMetadata, Context, Registry};
 use crate::tooling::logging::log_kernel_warn;
 
@@ -120,6 +121,13 @@ fn register_inline_components(ctx: &mut Context, value: Option<&Value>) -> Resul
                     }
                 }
             }
+            let signature = ComposeSignature::from_document(entry).map_err(|err| {
+                anyhow!(
+                    "invalid header for inline component \"{}\": {}",
+                    component_id,
+                    err
+                )
+            })?;
             let steps_arc = Arc::new(steps);
             let id_owned = component_id.to_string();
             let registry_clone = registry.clone();
@@ -136,7 +144,12 @@ fn register_inline_components(ctx: &mut Context, value: Option<&Value>) -> Resul
                         Value::Null => Value::Object(Map::new()),
                         other => other,
                     };
-                    let result = run_compose(ctx, steps_arc.as_ref(), seed)?;
+                    let result = run_compose_with_signature(
+                        ctx,
+                        steps_arc.as_ref(),
+                        signature.as_ref(),
+                        seed,
+                    )?;
                     if let Value::Object(map) = &result {
                         if let Some(entry) = map.get("entry") {
                             return Ok(entry.clone());
@@ -2,6 +2,7 @@ use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use anyhow::{anyhow, Context as AnyhowContext, Result};
 use base64::Engine as _;
@@ -10,6 +11,9 @@ use sha2::{Digest, Sha256};
 use toml::Value as TomlValue;
 
 use crate::core;
+use crate::core::fs::write_atomic;
+use crate::core::lock::FileLock;
+use crate::fs_policy::FsAccess;
 use crate::registry::{Context, Registry};
 
 fn ensure_cache_dir(project_path: &Path) -> Result<PathBuf> {
@@ -49,11 +53,7 @@ pub fn register_resolver_axioms(registry: &Registry) {
         "lcod://contract/core/fs/read-file@1",
         "lcod://axiom/fs/read-file@1",
     );
-    alias_contract(
-        registry,
-        "lcod://contract/core/fs/write-file@1",
-        "lcod://axiom/fs/write-file@1",
-    );
+    registry.register("lcod://axiom/fs/write-file@1", cache_write_file_axiom);
     alias_contract(
         registry,
         "lcod://contract/core/hash/sha256@1",
@@ -80,7 +80,11 @@ pub fn register_resolver_axioms(registry: &Registry) {
         "lcod://axiom/toml/parse@1",
     );
 
-    registry.register("lcod://axiom/toml/stringify@1", toml_stringify_axiom);
+    alias_contract(
+        registry,
+        "lcod://contract/core/format/toml@1",
+        "lcod://axiom/toml/stringify@1",
+    );
     registry.register("lcod://axiom/http/download@1", http_download_axiom);
     registry.register("lcod://tooling/resolver/cache-dir@1", cache_dir_axiom);
     registry.register("lcod://impl/set@1", impl_set_axiom);
@@ -113,6 +117,35 @@ fn cache_dir_axiom(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Re
     Ok(json!({ "path": path_to_string(&cache)? }))
 }
 
+/// How long resolver writes wait for a concurrent `lcod-run` sharing the same
+/// cache to finish with a file.
+const CACHE_LOCK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Locks the `<path>.lock` sidecar, which stays next to the cache entry.
+fn lock_cache_path(ctx: &Context, path: &Path) -> Result<FileLock> {
+    let token = ctx.cancellation_token();
+    FileLock::acquire(path, false, Some(CACHE_LOCK_TIMEOUT), Some(token.as_ref()))
+}
+
+/// Resolver writes land in the shared cache: take the file's lock and write
+/// atomically unless the caller asked to append.
+fn cache_write_file_axiom(ctx: &mut Context, input: Value, meta: Option<Value>) -> Result<Value> {
+    let mut input = input;
+    let path = input
+        .get("path")
+        .and_then(Value::as_str)
+        .map(PathBuf::from)
+        .ok_or_else(|| anyhow!("missing or invalid `path`"))?;
+    if let Value::Object(map) = &mut input {
+        let append = map.get("append").and_then(Value::as_bool).unwrap_or(false);
+        if !append {
+            map.entry("atomic").or_insert(Value::Bool(true));
+        }
+    }
+    let _lock = lock_cache_path(ctx, &path)?;
+    ctx.call("lcod://contract/core/fs/write-file@1", input, meta)
+}
+
 fn impl_set_axiom(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
     Ok(input)
 }
@@ -425,16 +458,7 @@ fn value_as_path(value: &Value, key: &str) -> Result<PathBuf> {
         .ok_or_else(|| anyhow!("missing `{}`", key))
 }
 
-fn toml_stringify_axiom(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
-    let value = input.get("value").cloned().unwrap_or(Value::Null);
-    let toml_value = TomlValue::try_from(value)
-        .map_err(|err| anyhow!("unable to convert value to TOML: {err}"))?;
-    let text = toml::to_string_pretty(&toml_value)
-        .map_err(|err| anyhow!("unable to serialize TOML: {err}"))?;
-    Ok(json!({ "text": text }))
-}
-
-fn http_download_axiom(_ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
+fn http_download_axiom(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Result<Value> {
     let url = input
         .get("url")
         .and_then(Value::as_str)
@@ -443,6 +467,7 @@ fn http_download_axiom(_ctx: &mut Context, input: Value, _meta: Option<Value>) -
         .get("path")
         .and_then(Value::as_str)
         .ok_or_else(|| anyhow!("path is required"))?;
+    ctx.check_fs_access(Path::new(path_str), FsAccess::Write)?;
 
     let mut request = Map::new();
     request.insert("url".to_string(), Value::String(url.to_string()));
@@ -465,7 +490,7 @@ fn http_download_axiom(_ctx: &mut Context, input: Value, _meta: Option<Value>) -
         }
     }
 
-    let response = _ctx.call(
+    let response = ctx.call(
         "lcod://contract/core/http/request@1",
         Value::Object(request.clone()),
         None,
@@ -476,37 +501,55 @@ fn http_download_axiom(_ctx: &mut Context, input: Value, _meta: Option<Value>) -
         .and_then(Value::as_u64)
         .unwrap_or_default() as i64;
 
-    let mut bytes = Vec::new();
-    if let Some(stream) = response.get("stream") {
-        loop {
-            let chunk = _ctx.streams_mut().read(stream, None, None)?;
-            if chunk.get("done").and_then(Value::as_bool).unwrap_or(false) {
-                break;
-            }
-            if let Some(data) = chunk.get("chunk").and_then(Value::as_str) {
-                let decoded = base64::engine::general_purpose::STANDARD
-                    .decode(data)
-                    .map_err(|err| anyhow!("invalid base64 chunk: {err}"))?;
-                bytes.extend_from_slice(&decoded);
+    let path = PathBuf::from(path_str);
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    fs::create_dir_all(&parent)
+        .with_context(|| format!("unable to create parent directory for {}", parent.display()))?;
+    let _lock = lock_cache_path(ctx, &path)?;
+
+    let bytes = if let Some(stream) = response.get("stream") {
+        // Spool the stream into a sibling temp file, then move it into place.
+        let temp = tempfile::Builder::new()
+            .prefix(".lcod-download-")
+            .tempfile_in(&parent)
+            .with_context(|| format!("unable to create temporary file in {}", parent.display()))?
+            .into_temp_path();
+        let written = ctx.call(
+            "lcod://contract/core/fs/write-stream@1",
+            json!({ "path": core::path::path_to_string(&temp), "stream": stream }),
+            None,
+        );
+        let written = match written {
+            Ok(written) => written,
+            Err(err) => {
+                let _ = ctx.streams_mut().close(stream);
+                return Err(err.context(format!("unable to download {url}")));
             }
-        }
-        let _ = _ctx.streams_mut().close(stream);
-    } else if let Some(body) = response.get("body") {
+        };
+        temp.persist(&path).map_err(|err| {
+            anyhow!(err.error).context(format!(
+                "unable to write downloaded file to {}",
+                path.display()
+            ))
+        })?;
+        written
+            .get("bytesWritten")
+            .and_then(Value::as_u64)
+            .unwrap_or_default() as usize
+    } else {
+        let body = response.get("body").cloned().unwrap_or(Value::Null);
         let encoding = response
             .get("bodyEncoding")
             .and_then(Value::as_str)
             .unwrap_or("utf-8");
-        bytes = decode_body(body, encoding)?;
-    }
-
-    let path = PathBuf::from(path_str);
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).with_context(|| {
-            format!("unable to create parent directory for {}", parent.display())
-        })?;
-    }
-    fs::write(&path, &bytes)
-        .with_context(|| format!("unable to write downloaded file to {}", path.display()))?;
+        let bytes = decode_body(&body, encoding)?;
+        write_atomic(&path, &bytes, false)
+            .with_context(|| format!("unable to write downloaded file to {}", path.display()))?;
+        bytes.len()
+    };
 
     let headers = response
         .get("headers")
@@ -515,7 +558,7 @@ fn http_download_axiom(_ctx: &mut Context, input: Value, _meta: Option<Value>) -
 
     Ok(json!({
         "status": status,
-        "bytes": bytes.len(),
+        "bytes": bytes,
         "headers": headers
     }))
 }
@@ -571,6 +614,14 @@ mod tests {
         format!("http://{}", addr)
     }
 
+    #[test]
+    fn lock_integrity_keeps_hex_encoding() {
+        assert_eq!(
+            compute_integrity("abc"),
+            "sha256-ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
     #[test]
     fn http_download_writes_file() {
         let response = "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\nbinary-data";
@@ -60,7 +60,7 @@ fn script_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Res
             for (key, value) in obj {
                 match key.as_str() {
                     "source" | "language" | "timeoutMs" | "tools" | "imports" | "bindings"
-                    | "config" | "meta" | "streams" | "input" => continue,
+                    | "config" | "meta" | "streams" | "input" | "env" => continue,
                     _ => {
                         fallback.insert(key, value);
                     }
@@ -86,8 +86,8 @@ fn script_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Res
         .cloned()
         .unwrap_or_else(|| Value::Object(Map::new()));
     let tools = build_tools(input.get("tools"), timeout_ms)?;
-    let imports_map = build_imports(input.get("imports"));
-    let imports = Arc::new(imports_map);
+    let imports = Arc::new(build_imports(input.get("imports")));
+    let env_vars = Arc::new(build_env(input.get("env")));
 
     let mut scope_map = Map::new();
     scope_map.insert("bindings".to_string(), bindings.clone());
@@ -98,18 +98,20 @@ fn script_contract(ctx: &mut Context, input: Value, _meta: Option<Value>) -> Res
     let scope = Value::Object(scope_map);
 
     let messages = Rc::new(Mutex::new(Vec::new()));
-    let tools_rc = Arc::new(tools);
-    let config_rc = Arc::new(config);
+    let runtime = ScriptRuntime {
+        messages: Rc::clone(&messages),
+        tools: Arc::new(tools),
+        config: Arc::new(config),
+        imports,
+        env_vars,
+    };
 
     let evaluation = execute_script(
         ctx,
         source,
         ScriptInvocation::Main { scope: &scope },
         timeout_ms,
-        Rc::clone(&messages),
-        tools_rc,
-        config_rc,
-        Arc::clone(&imports),
+        runtime,
     );
 
     match evaluation {
@@ -190,16 +192,30 @@ enum ScriptInvocation<'a> {
     Tool { payload: &'a Value },
 }
 
+/// Host state shared by the main script and the tools it runs.
+#[derive(Clone)]
+struct ScriptRuntime {
+    messages: Rc<Mutex<Vec<String>>>,
+    tools: Arc<HashMap<String, ToolDef>>,
+    config: Arc<Value>,
+    imports: Arc<HashMap<String, String>>,
+    env_vars: Arc<HashMap<String, String>>,
+}
+
 fn execute_script(
     ctx: &mut Context,
     source: &str,
     invocation: ScriptInvocation,
     timeout_ms: u64,
-    messages: Rc<Mutex<Vec<String>>>,
-    tools: Arc<HashMap<String, ToolDef>>,
-    config: Arc<Value>,
-    imports: Arc<HashMap<String, String>>,
+    runtime: ScriptRuntime,
 ) -> Result<(Value, Option<Value>)> {
+    let ScriptRuntime {
+        messages,
+        tools,
+        config,
+        imports,
+        env_vars,
+    } = runtime;
     ctx.ensure_not_cancelled()?;
     let context = JsContext::new().map_err(|err| anyhow!("unable to create JS context: {err}"))?;
 
@@ -210,8 +226,7 @@ fn execute_script(
         .map(|path| crate::core::path::path_to_string(path))
         .unwrap_or_else(|_| ".".to_string());
     let cwd_literal = serde_json::to_string(&cwd)?;
-    let env_map: HashMap<String, String> = env::vars().collect();
-    let env_literal = serde_json::to_string(&env_map)?;
+    let env_literal = serde_json::to_string(env_vars.as_ref())?;
     context
         .eval(&format!(
             "
@@ -366,9 +381,13 @@ fn execute_script(
         .map_err(|err| anyhow!("failed to register api.config bridge: {err}"))?;
 
     let tools_for_callback = Arc::clone(&tools);
-    let config_for_tools = Arc::clone(&config);
-    let imports_for_tools = Arc::clone(&imports);
-    let messages_for_tools = Rc::clone(&messages);
+    let runtime_for_tools = ScriptRuntime {
+        messages: Rc::clone(&messages),
+        tools: Arc::clone(&tools),
+        config: Arc::clone(&config),
+        imports: Arc::clone(&imports),
+        env_vars: Arc::clone(&env_vars),
+    };
     let ctx_ptr_tool = ctx as *mut Context as usize;
     context
         .add_callback(
@@ -400,10 +419,7 @@ fn execute_script(
                     &tool.source,
                     ScriptInvocation::Tool { payload: &payload },
                     effective_timeout,
-                    Rc::clone(&messages_for_tools),
-                    Arc::clone(&tools_for_callback),
-                    Arc::clone(&config_for_tools),
-                    Arc::clone(&imports_for_tools),
+                    runtime_for_tools.clone(),
                 )
                 .map(|(value, _)| json_to_js_value(&value))
                 .map_err(|err| err.to_string())
@@ -696,6 +712,21 @@ fn resolve_path<'a>(state: &'a Value, path: &str) -> Option<&'a Value> {
     Some(cursor)
 }
 
+/// `process.env` mirrors the host environment unless `env` lists the only
+/// variables scripts may see.
+fn build_env(spec: Option<&Value>) -> HashMap<String, String> {
+    let Some(names) = spec.and_then(Value::as_array) else {
+        return env::vars().collect();
+    };
+    let mut map = HashMap::new();
+    for name in names.iter().filter_map(Value::as_str) {
+        if let Ok(value) = env::var(name) {
+            map.insert(name.to_string(), value);
+        }
+    }
+    map
+}
+
 fn build_imports(spec: Option<&Value>) -> HashMap<String, String> {
     let mut map = HashMap::new();
     let Some(object) = spec.and_then(Value::as_object) else {
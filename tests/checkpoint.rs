@@ -0,0 +1,244 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use tempfile::tempdir;
+
+use lcod_kernel_rs::checkpoint::{compose_fingerprint, CheckpointSession};
+use lcod_kernel_rs::compose::{parse_compose, run_compose};
+use lcod_kernel_rs::flow::register_flow;
+use lcod_kernel_rs::{Context as KernelContext, Registry};
+
+struct Harness {
+    registry: Registry,
+    calls: Arc<AtomicUsize>,
+    fail_on: Arc<AtomicUsize>,
+}
+
+fn create_harness() -> Harness {
+    let registry = Registry::new();
+    register_flow(&registry);
+    let calls = Arc::new(AtomicUsize::new(0));
+    let fail_on = Arc::new(AtomicUsize::new(usize::MAX));
+
+    let calls_clone = Arc::clone(&calls);
+    let fail_clone = Arc::clone(&fail_on);
+    registry.register(
+        "lcod://test/visit@1",
+        move |_ctx: &mut KernelContext, input: Value, _meta| {
+            calls_clone.fetch_add(1, Ordering::SeqCst);
+            let value = input.get("value").and_then(Value::as_u64).unwrap_or(0);
+            if value as usize == fail_clone.load(Ordering::SeqCst) {
+                return Err(anyhow!("visit failed on {value}"));
+            }
+            Ok(json!({ "value": value * 10 }))
+        },
+    );
+    Harness {
+        registry,
+        calls,
+        fail_on,
+    }
+}
+
+#[test]
+fn resume_skips_completed_top_level_steps() -> Result<()> {
+    let harness = create_harness();
+    let dir = tempdir()?;
+    let steps = parse_compose(&json!([
+        { "call": "lcod://test/visit@1", "in": { "value": 1 }, "out": { "first": "value" } },
+        { "call": "lcod://test/visit@1", "in": { "value": 2 }, "out": { "second": "value" } },
+        { "call": "lcod://test/visit@1", "in": { "value": 3 }, "out": { "third": "value" } }
+    ]))?;
+    let fingerprint = compose_fingerprint(&steps)?;
+
+    harness.fail_on.store(2, Ordering::SeqCst);
+    let mut ctx = harness.registry.context();
+    ctx.set_checkpoint_session(Some(CheckpointSession::start(
+        dir.path(),
+        "batch-1",
+        &fingerprint,
+    )?));
+    run_compose(&mut ctx, &steps, json!({})).expect_err("second step fails");
+    let session = ctx.take_checkpoint_session().expect("session attached");
+    assert_eq!(session.checkpoint().next_step, 1);
+    assert_eq!(session.checkpoint().state.get("first"), Some(&json!(10)));
+    assert!(dir.path().join("batch-1.json").is_file());
+
+    harness.fail_on.store(usize::MAX, Ordering::SeqCst);
+    harness.calls.store(0, Ordering::SeqCst);
+    let mut ctx = harness.registry.context();
+    ctx.set_checkpoint_session(Some(CheckpointSession::resume(
+        dir.path(),
+        "batch-1",
+        &fingerprint,
+    )?));
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    assert_eq!(harness.calls.load(Ordering::SeqCst), 2);
+    assert_eq!(result["first"], json!(10));
+    assert_eq!(result["second"], json!(20));
+    assert_eq!(result["third"], json!(30));
+    assert!(
+        ctx.take_checkpoint_session()
+            .unwrap()
+            .checkpoint()
+            .completed
+    );
+    Ok(())
+}
+
+#[test]
+fn resume_continues_top_level_foreach_iterations() -> Result<()> {
+    let harness = create_harness();
+    let dir = tempdir()?;
+    let steps = parse_compose(&json!([
+        {
+            "call": "lcod://flow/foreach@1",
+            "in": { "list": [1, 2, 3, 4] },
+            "collectPath": "$.value",
+            "children": {
+                "body": [
+                    { "call": "lcod://test/visit@1", "in": { "value": "$slot.item" }, "out": { "value": "value" } }
+                ]
+            },
+            "out": { "results": "results" }
+        }
+    ]))?;
+    let fingerprint = compose_fingerprint(&steps)?;
+
+    harness.fail_on.store(3, Ordering::SeqCst);
+    let mut ctx = harness.registry.context();
+    ctx.set_checkpoint_session(Some(CheckpointSession::start(
+        dir.path(),
+        "loop",
+        &fingerprint,
+    )?));
+    run_compose(&mut ctx, &steps, json!({})).expect_err("third iteration fails");
+    let session = ctx.take_checkpoint_session().unwrap();
+    let progress = session
+        .checkpoint()
+        .foreach
+        .clone()
+        .expect("foreach progress");
+    assert_eq!(progress.next_index, 2);
+    assert_eq!(progress.results, vec![json!(10), json!(20)]);
+
+    harness.fail_on.store(usize::MAX, Ordering::SeqCst);
+    harness.calls.store(0, Ordering::SeqCst);
+    let mut ctx = harness.registry.context();
+    ctx.set_checkpoint_session(Some(CheckpointSession::resume(
+        dir.path(),
+        "loop",
+        &fingerprint,
+    )?));
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    assert_eq!(harness.calls.load(Ordering::SeqCst), 2);
+    assert_eq!(result["results"], json!([10, 20, 30, 40]));
+    Ok(())
+}
+
+#[test]
+fn checkpoint_reports_process_local_handles() -> Result<()> {
+    let harness = create_harness();
+    harness.registry.register(
+        "lcod://test/open_stream@1",
+        |ctx: &mut KernelContext, _input: Value, _meta| {
+            let handle = ctx
+                .streams_mut()
+                .register_chunks(vec![b"chunk".to_vec()], "utf-8");
+            Ok(json!({ "stream": handle }))
+        },
+    );
+    let dir = tempdir()?;
+    let steps = parse_compose(&json!([
+        { "call": "lcod://test/open_stream@1", "out": { "stream": "stream" } }
+    ]))?;
+    let fingerprint = compose_fingerprint(&steps)?;
+    let mut ctx = harness.registry.context();
+    ctx.set_checkpoint_session(Some(CheckpointSession::start(
+        dir.path(),
+        "handles",
+        &fingerprint,
+    )?));
+    run_compose(&mut ctx, &steps, json!({}))?;
+    let session = ctx.take_checkpoint_session().unwrap();
+    assert_eq!(
+        session.checkpoint().live_handles,
+        vec!["$.stream".to_string()]
+    );
+    Ok(())
+}
+
+#[test]
+fn resume_rejects_a_different_compose() -> Result<()> {
+    let dir = tempdir()?;
+    let steps = parse_compose(&json!([{ "call": "lcod://test/visit@1" }]))?;
+    let harness = create_harness();
+    let mut ctx = harness.registry.context();
+    ctx.set_checkpoint_session(Some(CheckpointSession::start(
+        dir.path(),
+        "mismatch",
+        "aaa",
+    )?));
+    run_compose(&mut ctx, &steps, json!({}))?;
+    let err = CheckpointSession::resume(dir.path(), "mismatch", &compose_fingerprint(&steps)?)
+        .err()
+        .expect("fingerprint mismatch");
+    assert!(err.to_string().contains("different compose"));
+    Ok(())
+}
+
+#[test]
+fn start_writes_checkpoint_and_redacts_secrets() -> Result<()> {
+    let harness = create_harness();
+    harness.registry.register(
+        "lcod://test/login@1",
+        |ctx: &mut KernelContext, _input: Value, _meta| {
+            ctx.register_secret("s3cr3t-token");
+            Ok(json!({ "token": "s3cr3t-token", "header": "Bearer s3cr3t-token" }))
+        },
+    );
+    let dir = tempdir()?;
+    let steps = parse_compose(&json!([
+        { "call": "lcod://test/login@1", "out": { "token": "token", "header": "header" } },
+        { "call": "lcod://test/visit@1", "in": { "value": 1 } }
+    ]))?;
+    let fingerprint = compose_fingerprint(&steps)?;
+    let session = CheckpointSession::start(dir.path(), "secrets", &fingerprint)?;
+    let file = dir.path().join("secrets.json");
+    let initial: Value = serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+    assert_eq!(initial["nextStep"], json!(0));
+    assert_eq!(initial["completed"], json!(false));
+
+    harness.fail_on.store(1, Ordering::SeqCst);
+    let mut ctx = harness.registry.context();
+    ctx.set_checkpoint_session(Some(session));
+    run_compose(&mut ctx, &steps, json!({})).expect_err("second step fails");
+    let text = std::fs::read_to_string(&file)?;
+    assert!(!text.contains("s3cr3t-token"));
+    let saved: Value = serde_json::from_str(&text)?;
+    assert_eq!(saved["nextStep"], json!(1));
+    assert_eq!(saved["state"]["header"], json!("Bearer [REDACTED]"));
+    assert_eq!(saved["redacted"], json!(["$.header", "$.token"]));
+
+    let err = CheckpointSession::resume(dir.path(), "secrets", &fingerprint)
+        .err()
+        .expect("masked values are required");
+    assert!(err.to_string().contains("$.header, $.token"), "{err}");
+
+    harness.fail_on.store(usize::MAX, Ordering::SeqCst);
+    let supplied = json!({ "$.header": "Bearer s3cr3t-token", "$.token": "s3cr3t-token" });
+    let mut ctx = harness.registry.context();
+    ctx.set_checkpoint_session(Some(CheckpointSession::resume_with_secrets(
+        dir.path(),
+        "secrets",
+        &fingerprint,
+        supplied.as_object().unwrap(),
+    )?));
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    assert_eq!(result["header"], json!("Bearer s3cr3t-token"));
+    // Supplied values stay masked in later checkpoints.
+    assert!(!std::fs::read_to_string(&file)?.contains("s3cr3t-token"));
+    Ok(())
+}
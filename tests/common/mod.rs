@@ -0,0 +1,48 @@
+//! Mock components shared by the flow block tests.
+#![allow(dead_code)]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use serde_json::Value;
+
+use lcod_kernel_rs::{register_flow, Context, Registry};
+
+/// Registry with the flow blocks registered.
+pub fn flow_registry() -> Registry {
+    let registry = Registry::new();
+    register_flow(&registry);
+    registry
+}
+
+/// Registers `id` as a mock component. `handler` receives the 1-based call
+/// number; the returned counter tracks how many calls were made.
+pub fn register_mock<F>(registry: &Registry, id: &str, handler: F) -> Arc<AtomicUsize>
+where
+    F: Fn(&mut Context, Value, usize) -> Result<Value> + Send + Sync + 'static,
+{
+    let calls = Arc::new(AtomicUsize::new(0));
+    let calls_clone = Arc::clone(&calls);
+    registry.register(
+        id,
+        move |ctx: &mut Context, input: Value, _meta: Option<Value>| {
+            let call = calls_clone.fetch_add(1, Ordering::SeqCst) + 1;
+            handler(ctx, input, call)
+        },
+    );
+    calls
+}
+
+/// Works for `ms` milliseconds, checking cancellation the way I/O-bound
+/// components do.
+pub fn work_for(ctx: &Context, ms: u64) -> Result<()> {
+    let started = Instant::now();
+    while started.elapsed() < Duration::from_millis(ms) {
+        ctx.ensure_not_cancelled()?;
+        thread::sleep(Duration::from_millis(5));
+    }
+    Ok(())
+}
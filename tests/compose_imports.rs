@@ -0,0 +1,177 @@
+use std::fs;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use tempfile::tempdir;
+
+use lcod_kernel_rs::compose::run_compose_with_signature;
+use lcod_kernel_rs::compose_loader::load_compose_document;
+use lcod_kernel_rs::{register_tooling, Context as KernelContext, Registry};
+
+fn echo_registry() -> Registry {
+    let registry = Registry::new();
+    registry.register(
+        "lcod://impl/echo@1",
+        |_ctx: &mut KernelContext, input: Value, _meta| Ok(input),
+    );
+    registry
+}
+
+#[test]
+fn imports_resolve_aliases_and_register_relative_composes() -> Result<()> {
+    let dir = tempdir()?;
+    fs::create_dir_all(dir.path().join("helpers"))?;
+    fs::write(
+        dir.path().join("helpers/shout.yaml"),
+        r#"
+inputs:
+  text: { type: string, required: true }
+imports:
+  echo: lcod://impl/echo@1
+compose:
+  - call: echo
+    in: { value: $.text }
+    out: { shouted: value }
+"#,
+    )?;
+    fs::write(
+        dir.path().join("main.yaml"),
+        r#"
+imports:
+  echo: lcod://impl/echo@1
+  shout: ./helpers/shout.yaml
+compose:
+  - call: echo
+    in: { value: $.name }
+    out: { echoed: value }
+  - call: shout
+    in: { text: $.echoed }
+    out: { loud: shouted }
+"#,
+    )?;
+
+    let document = load_compose_document(&dir.path().join("main.yaml"))?;
+    assert_eq!(document.steps[0].call, "lcod://impl/echo@1");
+    assert_eq!(document.imports.len(), 1);
+    let shout_id = document.imports[0].id.clone();
+    assert!(shout_id.starts_with("lcod://imports/shout-"));
+    assert_eq!(document.steps[1].call, shout_id);
+
+    let registry = echo_registry();
+    document.register_imports(&registry);
+    let mut ctx = registry.context();
+    let result = run_compose_with_signature(
+        &mut ctx,
+        &document.steps,
+        document.signature.as_ref(),
+        json!({ "name": "kernel" }),
+    )?;
+    assert_eq!(result["loud"], json!("kernel"));
+
+    let err = ctx
+        .call(&shout_id, json!({}), None)
+        .expect_err("imported compose keeps its signature");
+    assert!(err
+        .to_string()
+        .contains("missing required compose input(s): text"));
+    Ok(())
+}
+
+#[test]
+fn imported_compose_keeps_its_declared_id_and_manifest_scope() -> Result<()> {
+    let dir = tempdir()?;
+    fs::write(
+        dir.path().join("lcp.toml"),
+        "id = \"lcod://demo/pkg@1.2.0\"\n",
+    )?;
+    fs::write(
+        dir.path().join("greet.yaml"),
+        r#"
+id: lcod://demo/greet@1
+compose:
+  - call: lcod://impl/echo@1
+    in: { value: hello }
+    out: { greeting: value }
+"#,
+    )?;
+    fs::write(
+        dir.path().join("main.yaml"),
+        r#"
+imports:
+  greet: ./greet.yaml
+compose:
+  - call: greet
+    out: { greeting: greeting }
+  - call: ./local/helper
+"#,
+    )?;
+
+    let document = load_compose_document(&dir.path().join("main.yaml"))?;
+    assert_eq!(document.steps[0].call, "lcod://demo/greet@1");
+    assert_eq!(document.steps[1].call, "lcod://demo/pkg/local/helper@1.2.0");
+    Ok(())
+}
+
+#[test]
+fn import_cycles_and_invalid_targets_are_rejected() -> Result<()> {
+    let dir = tempdir()?;
+    fs::write(
+        dir.path().join("a.yaml"),
+        "imports:\n  b: ./b.yaml\ncompose: []\n",
+    )?;
+    fs::write(
+        dir.path().join("b.yaml"),
+        "imports:\n  a: ./a.yaml\ncompose: []\n",
+    )?;
+    let err = load_compose_document(&dir.path().join("a.yaml")).expect_err("cycle");
+    assert!(format!("{err:#}").contains("compose import cycle detected"));
+
+    fs::write(
+        dir.path().join("bad.yaml"),
+        "imports:\n  thing: not-an-id\ncompose: []\n",
+    )?;
+    let err = load_compose_document(&dir.path().join("bad.yaml")).expect_err("invalid target");
+    assert!(format!("{err:#}").contains("must be an lcod:// id or a relative compose path"));
+    Ok(())
+}
+
+#[test]
+fn test_checker_imports_do_not_outlive_the_run() -> Result<()> {
+    let dir = tempdir()?;
+    fs::write(
+        dir.path().join("greet.yaml"),
+        r#"
+id: lcod://demo/greet@1
+compose:
+  - call: lcod://impl/echo@1
+    in: { value: hello }
+    out: { greeting: value }
+"#,
+    )?;
+    fs::write(
+        dir.path().join("main.yaml"),
+        r#"
+imports:
+  greet: ./greet.yaml
+compose:
+  - call: greet
+    out: { greeting: greeting }
+"#,
+    )?;
+
+    let registry = echo_registry();
+    register_tooling(&registry);
+    let mut ctx = registry.context();
+    let report = ctx.call(
+        "lcod://tooling/test_checker@1",
+        json!({
+            "composeRef": { "path": dir.path().join("main.yaml").to_string_lossy() },
+            "expected": { "greeting": "hello" }
+        }),
+        None,
+    )?;
+    assert_eq!(report["success"], json!(true));
+    ctx.call("lcod://demo/greet@1", json!({}), None)
+        .expect_err("import is only registered while the compose runs");
+    Ok(())
+}
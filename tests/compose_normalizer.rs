@@ -1,5 +1,9 @@
-use lcod_kernel_rs::compose::parse_compose;
-use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+
+use lcod_kernel_rs::compose::{parse_compose, parse_compose_cached, run_compose, Step};
+use lcod_kernel_rs::{register_tooling, Context as KernelContext, Registry};
+use serde_json::{json, Value};
 
 #[test]
 fn parse_compose_expands_identity_inputs_and_outputs() {
@@ -97,3 +101,82 @@ fn parse_compose_handles_spreads_and_optional_inputs() {
     assert_eq!(step.inputs.get("required").unwrap(), "$.required");
     assert_eq!(step.out.get("result").unwrap(), "result");
 }
+
+fn normalize_with_spec_component(compose: &Value) -> Value {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("resources")
+        .join("normalize")
+        .join("compose.yaml");
+    let text = std::fs::read_to_string(path).expect("normalizer fixture");
+    let doc: Value = serde_yaml::from_str(&text).expect("normalizer yaml");
+    let steps: Vec<Step> =
+        serde_json::from_value(doc["compose"].clone()).expect("normalizer steps");
+
+    let registry = Registry::new();
+    register_tooling(&registry);
+    registry.register(
+        "lcod://impl/set@1",
+        |_ctx: &mut KernelContext, input, _meta| Ok(input),
+    );
+    let mut ctx = registry.context();
+    let result = run_compose(&mut ctx, &steps, json!({ "compose": compose.clone() }))
+        .expect("normalizer compose runs");
+    let normalized: Vec<Step> =
+        serde_json::from_value(result["compose"].clone()).expect("normalized steps");
+    serde_json::to_value(normalized).unwrap()
+}
+
+#[test]
+fn parse_compose_matches_spec_normalizer() {
+    let compose = json!([
+        {
+            "call": "lcod://impl/echo@1",
+            "in": {
+                "...": ["$.payload", null, { "pick": ["a", 1] }],
+                "...lock": "=",
+                "...extra": { "optional": true },
+                "...odd": 42,
+                "list": [{ "nested": "=" }, "="],
+                "value?": "=",
+                "literal": "text"
+            },
+            "out": {
+                "...": "=",
+                "...meta": { "source": "$.meta", "pick": ["id"] },
+                "result": "=",
+                "maybe?": "value"
+            },
+            "collectPath": "$.items"
+        },
+        {
+            "call": "lcod://flow/foreach@1",
+            "in": { "list": "=" },
+            "children": {
+                "body": [
+                    { "call": "lcod://impl/echo@1", "in": { "item": "$slot.item", "x": "=" } }
+                ],
+                "else": []
+            }
+        },
+        {
+            "call": "lcod://flow/try@1",
+            "children": [
+                { "call": "lcod://impl/echo@1", "out": { "...": { "path": "$.inner" } } }
+            ]
+        }
+    ]);
+
+    let native = serde_json::to_value(parse_compose(&compose).expect("compose parsed")).unwrap();
+    assert_eq!(native, normalize_with_spec_component(&compose));
+}
+
+#[test]
+fn parse_compose_cached_reuses_identical_content() {
+    let compose = json!([
+        { "call": "lcod://impl/echo@1", "in": { "cacheProbe": "=" } }
+    ]);
+    let first = parse_compose_cached(&compose).expect("compose parsed");
+    let second = parse_compose_cached(&compose.clone()).expect("compose parsed");
+    assert!(Arc::ptr_eq(&first, &second));
+    assert_eq!(first[0].inputs.get("cacheProbe").unwrap(), "$.cacheProbe");
+}
@@ -0,0 +1,128 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use lcod_kernel_rs::compose::{parse_compose, run_compose_with_signature};
+use lcod_kernel_rs::compose_signature::{ComposeSignature, SignatureError, SignatureSide};
+use lcod_kernel_rs::{register_tooling, Context as KernelContext, Registry};
+
+fn echo_registry() -> Registry {
+    let registry = Registry::new();
+    registry.register(
+        "lcod://impl/echo@1",
+        |_ctx: &mut KernelContext, input: Value, _meta| Ok(input),
+    );
+    registry
+}
+
+#[test]
+fn missing_required_inputs_are_reported_together() -> Result<()> {
+    let doc = json!({
+        "inputs": {
+            "name": { "type": "string", "required": true },
+            "path": { "type": "string", "required": true },
+            "count": { "type": "integer", "default": 1 }
+        },
+        "compose": [
+            { "call": "lcod://impl/echo@1", "in": { "value": "$.name" }, "out": { "echoed": "value" } }
+        ]
+    });
+    let signature = ComposeSignature::from_document(&doc)?.expect("header declared");
+    let steps = parse_compose(&doc["compose"])?;
+    let registry = echo_registry();
+    let mut ctx = registry.context();
+
+    let err = run_compose_with_signature(&mut ctx, &steps, Some(&signature), json!({}))
+        .expect_err("inputs are missing");
+    let signature_err = err
+        .downcast_ref::<SignatureError>()
+        .expect("signature error");
+    assert_eq!(signature_err.side, SignatureSide::Input);
+    assert_eq!(signature_err.missing, vec!["name", "path"]);
+    assert_eq!(
+        err.to_string(),
+        "missing required compose input(s): name, path"
+    );
+
+    let result = run_compose_with_signature(
+        &mut ctx,
+        &steps,
+        Some(&signature),
+        json!({ "name": "demo", "path": "/tmp" }),
+    )?;
+    assert_eq!(result["count"], json!(1));
+    assert_eq!(result["echoed"], json!("demo"));
+    Ok(())
+}
+
+#[test]
+fn input_and_output_types_are_enforced() -> Result<()> {
+    let doc = json!({
+        "inputs": { "count": "integer", "tags": { "type": ["array", "null"] } },
+        "outputs": { "echoed": { "type": "string" } },
+        "compose": [
+            { "call": "lcod://impl/echo@1", "in": { "value": "$.count" }, "out": { "echoed": "value" } }
+        ]
+    });
+    let signature = ComposeSignature::from_document(&doc)?.expect("header declared");
+    let steps = parse_compose(&doc["compose"])?;
+    let registry = echo_registry();
+    let mut ctx = registry.context();
+
+    let err = run_compose_with_signature(
+        &mut ctx,
+        &steps,
+        Some(&signature),
+        json!({ "count": "three", "tags": "x" }),
+    )
+    .expect_err("types mismatch");
+    assert_eq!(
+        err.to_string(),
+        "compose input `count` must be integer, got string; compose input `tags` must be array | null, got string"
+    );
+
+    let err = run_compose_with_signature(&mut ctx, &steps, Some(&signature), json!({ "count": 3 }))
+        .expect_err("output is an integer");
+    let signature_err = err
+        .downcast_ref::<SignatureError>()
+        .expect("signature error");
+    assert_eq!(signature_err.side, SignatureSide::Output);
+    Ok(())
+}
+
+#[test]
+fn compose_without_header_has_no_signature() -> Result<()> {
+    assert!(ComposeSignature::from_document(&json!({ "compose": [] }))?.is_none());
+    assert!(ComposeSignature::from_document(&json!([]))?.is_none());
+    Ok(())
+}
+
+#[test]
+fn registered_compose_components_validate_their_inputs() -> Result<()> {
+    let registry = echo_registry();
+    register_tooling(&registry);
+    let mut ctx = registry.context();
+    ctx.call(
+        "lcod://tooling/resolver/register@1",
+        json!({
+            "components": [{
+                "id": "lcod://test/greet@1",
+                "inputs": { "who": { "type": "string", "required": true } },
+                "compose": [
+                    { "call": "lcod://impl/echo@1", "in": { "value": "$.who" }, "out": { "greeted": "value" } }
+                ]
+            }]
+        }),
+        None,
+    )?;
+
+    let err = ctx
+        .call("lcod://test/greet@1", json!({}), None)
+        .expect_err("who is required");
+    assert!(err
+        .to_string()
+        .contains("missing required compose input(s): who"));
+
+    let result = ctx.call("lcod://test/greet@1", json!({ "who": "kernel" }), None)?;
+    assert_eq!(result["greeted"], json!("kernel"));
+    Ok(())
+}
@@ -3,6 +3,7 @@ use base64::Engine as _;
 use serde_json::json;
 use tempfile::tempdir;
 
+use lcod_kernel_rs::core::lock::LockTimeoutError;
 use lcod_kernel_rs::core::register_core;
 use lcod_kernel_rs::{Context, Registry};
 use serde_json::Value;
@@ -159,6 +160,522 @@ fn fs_stat_reports_exists_and_missing() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn fs_mkdir_copy_move_and_remove_trees() -> Result<()> {
+    let mut ctx = context();
+    let dir = tempdir()?;
+    let project = dir.path().join("project/src");
+
+    let created = ctx.call(
+        "lcod://contract/core/fs/mkdir@1",
+        json!({ "path": project, "recursive": true }),
+        None,
+    )?;
+    assert_eq!(created["created"], json!(true));
+    let again = ctx.call(
+        "lcod://contract/core/fs/mkdir@1",
+        json!({ "path": project, "recursive": true }),
+        None,
+    )?;
+    assert_eq!(again["created"], json!(false));
+
+    let touched = ctx.call(
+        "lcod://contract/core/fs/touch@1",
+        json!({ "path": project.join("lib.rs"), "mtime": "2024-01-02T03:04:05Z" }),
+        None,
+    )?;
+    assert_eq!(touched["created"], json!(true));
+    assert_eq!(touched["mtime"], json!("2024-01-02T03:04:05Z"));
+    std::fs::write(project.join("main.rs"), "fn main() {}")?;
+
+    let copy_in = json!({ "from": dir.path().join("project"), "to": dir.path().join("copy") });
+    let err = ctx
+        .call("lcod://contract/core/fs/copy@1", copy_in.clone(), None)
+        .expect_err("directories need recursive");
+    assert!(err.to_string().contains("recursive"));
+    let mut recursive_in = copy_in.clone();
+    recursive_in["recursive"] = json!(true);
+    let copied = ctx.call("lcod://contract/core/fs/copy@1", recursive_in.clone(), None)?;
+    assert_eq!(copied["files"], json!(2));
+    assert_eq!(copied["directories"], json!(2));
+
+    std::fs::write(project.join("main.rs"), "changed")?;
+    ctx.call("lcod://contract/core/fs/copy@1", recursive_in.clone(), None)
+        .expect_err("existing files are not overwritten by default");
+    recursive_in["overwrite"] = json!("skip");
+    let skipped = ctx.call("lcod://contract/core/fs/copy@1", recursive_in.clone(), None)?;
+    assert_eq!(skipped["skipped"], json!(2));
+    recursive_in["overwrite"] = json!("replace");
+    ctx.call("lcod://contract/core/fs/copy@1", recursive_in, None)?;
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("copy/src/main.rs"))?,
+        "changed"
+    );
+
+    let moved_to = dir.path().join("moved");
+    ctx.call(
+        "lcod://contract/core/fs/move@1",
+        json!({ "from": dir.path().join("copy"), "to": moved_to }),
+        None,
+    )?;
+    let exists = ctx.call(
+        "lcod://contract/core/fs/exists@1",
+        json!({ "path": moved_to.join("src/lib.rs") }),
+        None,
+    )?;
+    assert_eq!(exists["exists"], json!(true));
+    assert_eq!(exists["type"], json!("file"));
+
+    ctx.call(
+        "lcod://contract/core/fs/remove@1",
+        json!({ "path": moved_to }),
+        None,
+    )
+    .expect_err("non-empty directory needs recursive");
+    let removed = ctx.call(
+        "lcod://contract/core/fs/remove@1",
+        json!({ "path": moved_to, "recursive": true }),
+        None,
+    )?;
+    assert_eq!(removed["type"], json!("directory"));
+    let missing = ctx.call(
+        "lcod://contract/core/fs/remove@1",
+        json!({ "path": moved_to, "missingOk": true }),
+        None,
+    )?;
+    assert_eq!(missing["removed"], json!(false));
+    let gone = ctx.call(
+        "lcod://contract/core/fs/exists@1",
+        json!({ "path": moved_to }),
+        None,
+    )?;
+    assert_eq!(
+        gone,
+        json!({ "path": moved_to, "exists": false, "type": null })
+    );
+    Ok(())
+}
+
+#[test]
+fn fs_copy_and_move_reject_directory_into_its_own_subtree() -> Result<()> {
+    let mut ctx = context();
+    let dir = tempdir()?;
+    let source = dir.path().join("a");
+    std::fs::create_dir_all(source.join("b"))?;
+    std::fs::write(source.join("file.txt"), "x")?;
+
+    for (contract, extra) in [("copy", json!({ "recursive": true })), ("move", json!({}))] {
+        let mut input = extra;
+        input["from"] = json!(source);
+        input["to"] = json!(dir.path().join("a/b/../b/nested"));
+        let err = ctx
+            .call(
+                &format!("lcod://contract/core/fs/{contract}@1"),
+                input,
+                None,
+            )
+            .expect_err("destination inside the source");
+        assert!(err.to_string().contains("into itself"), "{contract}: {err}");
+    }
+    assert!(!source.join("b/nested").exists());
+
+    // A sibling sharing the name as a prefix is not inside the source.
+    ctx.call(
+        "lcod://contract/core/fs/copy@1",
+        json!({ "from": source, "to": dir.path().join("ab"), "recursive": true }),
+        None,
+    )?;
+    assert!(dir.path().join("ab/file.txt").is_file());
+    Ok(())
+}
+
+#[test]
+fn fs_move_reports_rename_errors_without_copying() -> Result<()> {
+    let mut ctx = context();
+    let dir = tempdir()?;
+    let from = dir.path().join("incoming");
+    let to = dir.path().join("current");
+    std::fs::create_dir_all(&from)?;
+    std::fs::write(from.join("new.txt"), "new")?;
+    std::fs::create_dir_all(&to)?;
+    std::fs::write(to.join("old.txt"), "old")?;
+
+    let err = ctx
+        .call(
+            "lcod://contract/core/fs/move@1",
+            json!({ "from": from, "to": to, "overwrite": true }),
+            None,
+        )
+        .expect_err("rename onto a non-empty directory fails");
+    let io = err
+        .downcast_ref::<std::io::Error>()
+        .expect("rename error is kept");
+    assert_eq!(io.kind(), std::io::ErrorKind::DirectoryNotEmpty);
+    assert!(from.join("new.txt").is_file());
+    assert!(!to.join("new.txt").exists());
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn fs_symlink_and_chmod() -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut ctx = context();
+    let dir = tempdir()?;
+    let script = dir.path().join("run.sh");
+    std::fs::write(&script, "#!/bin/sh\n")?;
+
+    let chmod = ctx.call(
+        "lcod://contract/core/fs/chmod@1",
+        json!({ "path": script, "mode": "755" }),
+        None,
+    )?;
+    assert_eq!(chmod["mode"], json!("0755"));
+    assert_eq!(
+        std::fs::metadata(&script)?.permissions().mode() & 0o777,
+        0o755
+    );
+
+    let link = dir.path().join("current");
+    ctx.call(
+        "lcod://contract/core/fs/symlink@1",
+        json!({ "target": "run.sh", "path": link }),
+        None,
+    )?;
+    let target = ctx.call(
+        "lcod://contract/core/fs/read-link@1",
+        json!({ "path": link }),
+        None,
+    )?;
+    assert_eq!(target["target"], json!("run.sh"));
+    let link_type = ctx.call(
+        "lcod://contract/core/fs/exists@1",
+        json!({ "path": link, "followSymlinks": false }),
+        None,
+    )?;
+    assert_eq!(link_type["type"], json!("symlink"));
+    ctx.call(
+        "lcod://contract/core/fs/symlink@1",
+        json!({ "target": "elsewhere", "path": link }),
+        None,
+    )
+    .expect_err("link already exists");
+    Ok(())
+}
+
+#[test]
+fn fs_glob_matches_patterns_and_honours_ignore_files() -> Result<()> {
+    let mut ctx = context();
+    let dir = tempdir()?;
+    let root = dir.path();
+    for file in [
+        "src/lib.rs",
+        "src/nested/mod.rs",
+        "src/nested/notes.txt",
+        "src/nested/draft.txt",
+        "src/generated.rs",
+        "target/debug/build.rs",
+        ".cache/hidden.rs",
+        "logs/run.log",
+        "logs/keep.log",
+    ] {
+        let path = root.join(file);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(&path, file)?;
+    }
+    std::fs::write(root.join(".gitignore"), "target/\n*.log\n!keep.log\n")?;
+    std::fs::write(root.join("src/.gitignore"), "/generated.rs\n")?;
+
+    let found = ctx.call(
+        "lcod://contract/core/fs/glob@1",
+        json!({
+            "cwd": root,
+            "patterns": ["**/*.{rs,txt,log}", "!**/draft.txt"],
+            "ignoreFiles": [".gitignore"]
+        }),
+        None,
+    )?;
+    assert_eq!(
+        found["paths"],
+        json!([
+            "logs/keep.log",
+            "src/lib.rs",
+            "src/nested/mod.rs",
+            "src/nested/notes.txt"
+        ])
+    );
+    assert!(found.get("entries").is_none());
+
+    let found = ctx.call(
+        "lcod://contract/core/fs/glob@1",
+        json!({
+            "cwd": root,
+            "patterns": "**/*.rs",
+            "ignore": ["src/"],
+            "includeHidden": true,
+            "includeStats": true
+        }),
+        None,
+    )?;
+    assert_eq!(
+        found["paths"],
+        json!([".cache/hidden.rs", "target/debug/build.rs"])
+    );
+    assert_eq!(found["entries"][0]["type"], json!("file"));
+    assert_eq!(found["entries"][0]["size"], json!(16));
+
+    let found = ctx.call(
+        "lcod://contract/core/fs/glob@1",
+        json!({ "cwd": root, "patterns": ["*"], "includeDirectories": true }),
+        None,
+    )?;
+    assert_eq!(found["paths"], json!(["logs", "src", "target"]));
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn fs_glob_skips_unreadable_subdirectories() -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut ctx = context();
+    let dir = tempdir()?;
+    let root = dir.path();
+    for file in ["a/lcp.toml", "locked/inner/lcp.toml", "z/lcp.toml"] {
+        let path = root.join(file);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(&path, "")?;
+    }
+    let locked = root.join("locked");
+    std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000))?;
+    // Permission bits do not apply to privileged users.
+    let readable = std::fs::read_dir(&locked).is_ok();
+
+    let found = ctx.call(
+        "lcod://contract/core/fs/glob@1",
+        json!({ "cwd": root, "patterns": "**/lcp.toml" }),
+        None,
+    );
+    std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755))?;
+    if !readable {
+        assert_eq!(found?["paths"], json!(["a/lcp.toml", "z/lcp.toml"]));
+    }
+    Ok(())
+}
+
+#[test]
+fn fs_open_read_and_write_stream_copy_without_buffering() -> Result<()> {
+    let mut ctx = context();
+    let dir = tempdir()?;
+    let source = dir.path().join("archive.bin");
+    let payload: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    std::fs::write(&source, &payload)?;
+
+    let opened = ctx.call(
+        "lcod://contract/core/fs/open_read@1",
+        json!({ "path": source, "chunkSize": 4096 }),
+        None,
+    )?;
+    assert_eq!(opened["size"], json!(200_000));
+    let target = dir.path().join("out/copy.bin");
+    let written = ctx.call(
+        "lcod://contract/core/fs/write_stream@1",
+        json!({ "path": target, "stream": opened["stream"], "createParents": true }),
+        None,
+    )?;
+    assert_eq!(written["bytesWritten"], json!(200_000));
+    assert_eq!(std::fs::read(&target)?, payload);
+    ctx.call(
+        "lcod://contract/core/stream/read@1",
+        json!({ "stream": opened["stream"] }),
+        None,
+    )
+    .expect_err("write_stream closes the handle");
+
+    // Text reads never split a multi-byte character.
+    let text = dir.path().join("notes.txt");
+    std::fs::write(&text, "héllo wörld")?;
+    let opened = ctx.call(
+        "lcod://contract/core/fs/open-read@1",
+        json!({ "path": text, "encoding": "utf-8", "chunkSize": 2 }),
+        None,
+    )?;
+    let mut collected = String::new();
+    loop {
+        let chunk = ctx.call(
+            "lcod://contract/core/stream/read@1",
+            json!({ "stream": opened["stream"], "maxBytes": 2 }),
+            None,
+        )?;
+        if chunk["done"] == json!(true) {
+            break;
+        }
+        collected.push_str(chunk["chunk"].as_str().unwrap());
+    }
+    assert_eq!(collected, "héllo wörld");
+    Ok(())
+}
+
+#[test]
+fn fs_atomic_write_replaces_file_and_leaves_no_temp() -> Result<()> {
+    let mut ctx = context();
+    let dir = tempdir()?;
+    let target = dir.path().join("cache").join("index.json");
+    let target_str = target.to_string_lossy().to_string();
+
+    for data in ["{\"v\":1}", "{\"v\":2}"] {
+        ctx.call(
+            "lcod://contract/core/fs/write_file@1",
+            json!({
+                "path": target_str,
+                "data": data,
+                "atomic": true,
+                "fsync": true,
+                "createParents": true
+            }),
+            None,
+        )?;
+    }
+    assert_eq!(std::fs::read_to_string(&target)?, "{\"v\":2}");
+    let names: Vec<_> = std::fs::read_dir(target.parent().unwrap())?
+        .map(|entry| entry.map(|e| e.file_name()))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(names, vec![std::ffi::OsString::from("index.json")]);
+
+    let err = ctx
+        .call(
+            "lcod://contract/core/fs/write_file@1",
+            json!({ "path": target_str, "data": "x", "atomic": true, "append": true }),
+            None,
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("atomic"));
+    Ok(())
+}
+
+#[test]
+fn fs_lock_excludes_other_holders_until_unlocked() -> Result<()> {
+    let registry = registry_with_core();
+    let mut first = registry.context();
+    let mut second = registry.context();
+    let dir = tempdir()?;
+    let path = dir.path().join("shared.json").to_string_lossy().to_string();
+
+    let held = first.call(
+        "lcod://contract/core/fs/lock@1",
+        json!({ "path": path }),
+        None,
+    )?;
+    assert!(held["lockPath"]
+        .as_str()
+        .unwrap()
+        .ends_with("shared.json.lock"));
+
+    let err = second
+        .call(
+            "lcod://contract/core/fs/lock@1",
+            json!({ "path": path, "timeoutMs": 50 }),
+            None,
+        )
+        .unwrap_err();
+    assert!(err.is::<LockTimeoutError>());
+
+    let released = first.call(
+        "lcod://contract/core/fs/unlock@1",
+        json!({ "lock": held["lock"] }),
+        None,
+    )?;
+    assert_eq!(released["released"], json!(true));
+
+    let reacquired = second.call(
+        "lcod://contract/core/fs/lock@1",
+        json!({ "path": path, "timeoutMs": 1000 }),
+        None,
+    )?;
+    assert!(reacquired["lock"].is_string());
+    Ok(())
+}
+
+#[test]
+fn fs_temp_paths_are_removed_with_their_scope_or_context() -> Result<()> {
+    let base = tempdir()?;
+    let base_str = base.path().to_string_lossy().to_string();
+    let path_of = |value: &Value| std::path::PathBuf::from(value["path"].as_str().unwrap());
+
+    let mut ctx = context();
+    let scratch = ctx.call(
+        "lcod://contract/core/fs/temp_dir@1",
+        json!({ "dir": base_str, "prefix": "scratch-" }),
+        None,
+    )?;
+    let kept = ctx.call(
+        "lcod://contract/core/fs/temp_file@1",
+        json!({ "dir": base_str, "suffix": ".json", "keep": true }),
+        None,
+    )?;
+
+    ctx.enter_registry_scope(None)?;
+    let scoped = ctx.call(
+        "lcod://contract/core/fs/temp_file@1",
+        json!({ "dir": base_str }),
+        None,
+    )?;
+    assert!(path_of(&scoped).is_file());
+    ctx.leave_registry_scope()?;
+    assert!(!path_of(&scoped).exists());
+
+    let scratch_dir = path_of(&scratch);
+    assert!(scratch_dir.is_dir());
+    assert!(scratch_dir
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .starts_with("scratch-"));
+    std::fs::write(scratch_dir.join("nested.txt"), "x")?;
+    drop(ctx);
+    assert!(!scratch_dir.exists());
+
+    let kept_file = path_of(&kept);
+    assert!(kept_file.is_file());
+    assert!(kept_file.to_string_lossy().ends_with(".json"));
+    Ok(())
+}
+
+#[test]
+fn git_clone_keeps_checkouts_unless_keep_is_false() -> Result<()> {
+    let origin = tempdir()?;
+    let repo = git2::Repository::init(origin.path())?;
+    std::fs::write(origin.path().join("README.md"), "hello")?;
+    let mut index = repo.index()?;
+    index.add_path(std::path::Path::new("README.md"))?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = git2::Signature::now("tester", "tester@example.com")?;
+    repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])?;
+    let url = origin.path().to_string_lossy().to_string();
+    let path_of = |value: &Value| std::path::PathBuf::from(value["path"].as_str().unwrap());
+
+    let mut ctx = context();
+    let kept = ctx.call(
+        "lcod://contract/core/git/clone@1",
+        json!({ "url": url }),
+        None,
+    )?;
+    let scratch = ctx.call(
+        "lcod://contract/core/git/clone@1",
+        json!({ "url": url, "keep": false }),
+        None,
+    )?;
+    assert!(path_of(&scratch).join("README.md").is_file());
+    drop(ctx);
+
+    assert!(!path_of(&scratch).exists());
+    let kept_dir = path_of(&kept);
+    assert!(kept_dir.join("README.md").is_file());
+    std::fs::remove_dir_all(kept_dir)?;
+    Ok(())
+}
+
 #[test]
 fn value_clone_returns_independent_copy() -> Result<()> {
     let mut ctx = context();
@@ -213,6 +730,95 @@ fn hash_sha256_computes_digest() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn hash_digest_supports_algorithms_hmac_files_and_streams() -> Result<()> {
+    let mut ctx = context();
+    let digest = |ctx: &mut Context, input: Value| {
+        ctx.call("lcod://contract/core/hash/digest@1", input, None)
+    };
+
+    let expected = [
+        ("sha1", "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed"),
+        ("md5", "5eb63bbbe01eeed093cb22bb8f5acdc3"),
+        (
+            "blake3",
+            "d74981efa70a0c880b8d8c1985d075dbcbf679b99a5f9914e5aaf96b831a9e24",
+        ),
+    ];
+    for (algorithm, hex) in expected {
+        let res = digest(
+            &mut ctx,
+            json!({ "algorithm": algorithm, "data": "hello world" }),
+        )?;
+        assert_eq!(res["hex"], json!(hex), "{algorithm}");
+    }
+
+    let sri = digest(
+        &mut ctx,
+        json!({ "algorithm": "SHA-384", "data": "alert('Hello, world.');" }),
+    )?;
+    assert_eq!(
+        sri["integrity"],
+        json!("sha384-H8BRh8j48O9oYatfu5AZzq6A9RINhZO5H16dQZngK7T62em8MUt1FLm52t+eX6xO")
+    );
+
+    let lock = digest(
+        &mut ctx,
+        json!({ "data": "abc", "integrityFormat": "lock" }),
+    )?;
+    assert_eq!(
+        lock["integrity"],
+        json!("sha256-ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+    );
+
+    let hmac = digest(
+        &mut ctx,
+        json!({
+            "algorithm": "sha256",
+            "key": "key",
+            "data": "The quick brown fox jumps over the lazy dog"
+        }),
+    )?;
+    assert_eq!(
+        hmac["hex"],
+        json!("f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8")
+    );
+    assert_eq!(hmac["hmac"], json!(true));
+    assert!(hmac.get("integrity").is_none());
+
+    let dir = tempdir()?;
+    let file = dir.path().join("big.bin");
+    let payload: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    std::fs::write(&file, &payload)?;
+    let from_file = digest(
+        &mut ctx,
+        json!({ "algorithm": "sha512", "path": file.to_string_lossy() }),
+    )?;
+    let from_data = digest(
+        &mut ctx,
+        json!({
+            "algorithm": "sha512",
+            "data": base64::engine::general_purpose::STANDARD.encode(&payload),
+            "encoding": "base64"
+        }),
+    )?;
+    assert_eq!(from_file["hex"], from_data["hex"]);
+    assert_eq!(from_file["bytes"], json!(200_000));
+
+    let opened = ctx.call(
+        "lcod://contract/core/fs/open_read@1",
+        json!({ "path": file.to_string_lossy(), "chunkSize": 4096 }),
+        None,
+    )?;
+    let from_stream = digest(
+        &mut ctx,
+        json!({ "algorithm": "sha512", "stream": opened["stream"] }),
+    )?;
+    assert_eq!(from_stream["hex"], from_data["hex"]);
+    assert_eq!(from_stream["bytes"], json!(200_000));
+    Ok(())
+}
+
 #[test]
 fn env_get_reads_value_and_falls_back() -> Result<()> {
     let mut ctx = context();
@@ -279,6 +885,207 @@ fn parse_json_toml_and_csv() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn parse_yaml_documents_and_merge_keys() -> Result<()> {
+    let mut ctx = context();
+    let text = "defaults: &defaults\n  retries: 3\n  timeout: 10\nservice:\n  <<: *defaults\n  timeout: 30\n  ports: [80, 443]\n  1: one\n";
+    let merged = ctx.call(
+        "lcod://contract/core/parse/yaml@1",
+        json!({ "text": text }),
+        None,
+    )?;
+    assert_eq!(
+        merged["value"]["service"],
+        json!({ "retries": 3, "timeout": 30, "ports": [80, 443], "1": "one" })
+    );
+
+    let raw = ctx.call(
+        "lcod://contract/core/parse/yaml@1",
+        json!({ "text": text, "mergeKeys": false }),
+        None,
+    )?;
+    assert_eq!(raw["value"]["service"]["<<"]["retries"], json!(3));
+
+    let stream = "---\nname: first\n---\nname: second\n";
+    let err = ctx
+        .call(
+            "lcod://contract/core/parse/yaml@1",
+            json!({ "text": stream }),
+            None,
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("multiDocument"));
+    let docs = ctx.call(
+        "lcod://contract/core/parse/yaml@1",
+        json!({ "text": stream, "multiDocument": true }),
+        None,
+    )?;
+    assert_eq!(
+        docs["documents"],
+        json!([{ "name": "first" }, { "name": "second" }])
+    );
+    assert_eq!(docs["value"], json!({ "name": "first" }));
+    Ok(())
+}
+
+#[test]
+fn format_contracts_round_trip_through_parse() -> Result<()> {
+    let mut ctx = context();
+    let value = json!({ "name": "demo", "tags": ["a", "b"], "owner": { "id": 7 } });
+
+    for (format, extra) in [
+        ("json", json!({ "pretty": true, "indent": 4 })),
+        ("yaml", json!({})),
+        ("toml", json!({})),
+    ] {
+        let mut input = extra;
+        input["value"] = value.clone();
+        let formatted = ctx.call(
+            &format!("lcod://contract/core/format/{format}@1"),
+            input,
+            None,
+        )?;
+        let text = formatted["text"].as_str().unwrap().to_string();
+        assert_eq!(formatted["bytes"], json!(text.len()));
+        let parsed = ctx.call(
+            &format!("lcod://contract/core/parse/{format}@1"),
+            json!({ "text": text }),
+            None,
+        )?;
+        assert_eq!(parsed["value"], value, "{format}");
+    }
+
+    let pretty = ctx.call(
+        "lcod://contract/core/format/json@1",
+        json!({ "value": { "a": 1 }, "pretty": true, "indent": 4 }),
+        None,
+    )?;
+    assert_eq!(pretty["text"], json!("{\n    \"a\": 1\n}"));
+
+    let yaml_stream = ctx.call(
+        "lcod://contract/core/format/yaml@1",
+        json!({ "documents": [{ "n": 1 }, { "n": 2 }] }),
+        None,
+    )?;
+    assert_eq!(yaml_stream["text"], json!("---\nn: 1\n---\nn: 2\n"));
+
+    let csv = ctx.call(
+        "lcod://contract/core/format/csv@1",
+        json!({ "rows": [
+            { "name": "Bob", "age": 30 },
+            { "name": "Ana, Jr.", "city": null }
+        ] }),
+        None,
+    )?;
+    assert_eq!(
+        csv["text"],
+        json!("age,city,name\n30,,Bob\n,,\"Ana, Jr.\"\n")
+    );
+    let reparsed = ctx.call(
+        "lcod://contract/core/parse/csv@1",
+        json!({ "text": csv["text"], "header": true }),
+        None,
+    )?;
+    assert_eq!(reparsed["rows"][1]["name"], json!("Ana, Jr."));
+    Ok(())
+}
+
+#[test]
+fn parse_cursors_read_ndjson_and_csv_streams_in_batches() -> Result<()> {
+    let mut ctx = context();
+    let dir = tempdir()?;
+    let open = |ctx: &mut Context, name: &str, text: &str| -> Result<Value> {
+        let path = dir.path().join(name);
+        std::fs::write(&path, text)?;
+        let opened = ctx.call(
+            "lcod://contract/core/fs/open_read@1",
+            json!({ "path": path.to_string_lossy(), "chunkSize": 5 }),
+            None,
+        )?;
+        Ok(opened["stream"].clone())
+    };
+    let drain = |ctx: &mut Context, cursor: &Value, max: u64| -> Result<(Vec<Value>, Vec<Value>)> {
+        let mut batches = Vec::new();
+        let mut warnings = Vec::new();
+        loop {
+            let next = ctx.call(
+                "lcod://contract/core/parse/next@1",
+                json!({ "cursor": cursor, "max": max }),
+                None,
+            )?;
+            if let Some(items) = next["warnings"].as_array() {
+                warnings.extend(items.iter().cloned());
+            }
+            batches.push(next["rows"].clone());
+            if next["done"].as_bool().unwrap_or(false) {
+                return Ok((batches, warnings));
+            }
+        }
+    };
+
+    let flatten = |batches: &[Value]| -> Value {
+        batches
+            .iter()
+            .flat_map(|batch| batch.as_array().cloned().unwrap_or_default())
+            .collect()
+    };
+
+    let stream = open(
+        &mut ctx,
+        "events.ndjson",
+        "{\"n\":1}\n\n{\"n\":2}\nnot json\n{\"n\":3}",
+    )?;
+    let ndjson = ctx.call(
+        "lcod://contract/core/parse/ndjson@1",
+        json!({ "stream": stream }),
+        None,
+    )?;
+    let cursor = ndjson["cursor"].clone();
+    assert_eq!(cursor["format"], json!("ndjson"));
+    let (batches, warnings) = drain(&mut ctx, &cursor, 2)?;
+    assert_eq!(batches[0], json!([{ "n": 1 }, { "n": 2 }]));
+    assert_eq!(
+        flatten(&batches),
+        json!([{ "n": 1 }, { "n": 2 }, { "n": 3 }])
+    );
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].as_str().unwrap().contains("line 4"));
+    let closed = ctx.call(
+        "lcod://contract/core/parse/close@1",
+        json!({ "cursor": cursor }),
+        None,
+    )?;
+    assert_eq!(closed["released"], json!(true));
+
+    let stream = open(
+        &mut ctx,
+        "people.csv",
+        "name,note\nAna,\"multi\nline, quoted\"\nBob,plain\n",
+    )?;
+    let csv = ctx.call(
+        "lcod://contract/core/parse/csv@1",
+        json!({ "stream": stream, "header": true }),
+        None,
+    )?;
+    let (batches, _) = drain(&mut ctx, &csv["cursor"], 1)?;
+    assert_eq!(
+        flatten(&batches),
+        json!([
+            { "name": "Ana", "note": "multi\nline, quoted" },
+            { "name": "Bob", "note": "plain" }
+        ])
+    );
+
+    let inline = ctx.call(
+        "lcod://contract/core/parse/ndjson@1",
+        json!({ "text": "[1]\n[2]\n" }),
+        None,
+    )?;
+    assert_eq!(inline["rows"], json!([[1], [2]]));
+    assert!(inline.get("warnings").is_none());
+    Ok(())
+}
+
 #[test]
 fn array_length_and_push() -> Result<()> {
     let mut ctx = context();
@@ -403,8 +1210,11 @@ fn path_dirname_returns_parent_or_dot() -> Result<()> {
     )?;
     assert_eq!(abs["dirname"], json!("/tmp/work"));
 
-    let relative =
-        ctx.call("lcod://contract/core/path/dirname@1", json!({ "path": "README.md" }), None)?;
+    let relative = ctx.call(
+        "lcod://contract/core/path/dirname@1",
+        json!({ "path": "README.md" }),
+        None,
+    )?;
     assert_eq!(relative["dirname"], json!("."));
 
     let root = ctx.call(
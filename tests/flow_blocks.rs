@@ -396,3 +396,298 @@ fn foreach_stream_demo_from_spec_yaml() -> Result<()> {
     assert!(!ctx.streams().contains_handle(&handle));
     Ok(())
 }
+
+fn switch_step(value: Value, cases: Option<Value>, slots: &[&str]) -> Result<Step> {
+    let mut slot_map = Map::new();
+    for name in slots {
+        slot_map.insert(
+            (*name).to_string(),
+            json!([{
+                "call": "lcod://impl/echo@1",
+                "in": { "value": "$slot.case" },
+                "out": { "picked": "val" }
+            }]),
+        );
+    }
+    let mut step = json!({
+        "call": "lcod://flow/switch@1",
+        "in": { "value": value },
+        "slots": slot_map,
+        "out": { "case": "case", "picked": "picked" }
+    });
+    if let Some(cases) = cases {
+        step["in"]["cases"] = cases;
+    }
+    Ok(parse_compose(&json!([step]))?.remove(0))
+}
+
+#[test]
+fn switch_dispatches_on_slot_names_case_lists_and_default() -> Result<()> {
+    let registry = create_registry();
+    let mut ctx = registry.context();
+    let cases = Some(json!({ "warm": ["orange", "yellow"], "answer": 42 }));
+    let slots = ["red", "warm", "answer", "default"];
+
+    let by_name = run_compose(
+        &mut ctx,
+        &[switch_step(json!("red"), cases.clone(), &slots)?],
+        json!({}),
+    )?;
+    assert_eq!(by_name["case"], json!("red"));
+    assert_eq!(by_name["picked"], json!("red"));
+
+    let by_list = run_compose(
+        &mut ctx,
+        &[switch_step(json!("yellow"), cases.clone(), &slots)?],
+        json!({}),
+    )?;
+    assert_eq!(by_list["case"], json!("warm"));
+
+    let by_number = run_compose(
+        &mut ctx,
+        &[switch_step(json!(42), cases.clone(), &slots)?],
+        json!({}),
+    )?;
+    assert_eq!(by_number["case"], json!("answer"));
+
+    let fallback = run_compose(
+        &mut ctx,
+        &[switch_step(json!("purple"), cases, &slots)?],
+        json!({}),
+    )?;
+    assert_eq!(fallback["case"], json!("default"));
+    assert_eq!(fallback["picked"], json!("default"));
+
+    let unmatched = run_compose(
+        &mut ctx,
+        &[switch_step(json!("purple"), None, &["red"])?],
+        json!({}),
+    )?;
+    assert_eq!(unmatched["case"], Value::Null);
+    assert!(unmatched.get("picked").is_none_or(Value::is_null));
+    Ok(())
+}
+
+#[test]
+fn switch_case_arrays_keep_order_and_unknown_slots_are_rejected() -> Result<()> {
+    let registry = create_registry();
+    let mut ctx = registry.context();
+    let slots = ["warm", "amber"];
+    let ordered = Some(json!([
+        { "slot": "warm", "when": ["orange", "yellow"] },
+        { "slot": "amber", "when": "orange" }
+    ]));
+    let first = run_compose(
+        &mut ctx,
+        &[switch_step(json!("orange"), ordered, &slots)?],
+        json!({}),
+    )?;
+    assert_eq!(first["case"], json!("warm"));
+
+    let typo = Some(json!({ "wram": "orange" }));
+    let err = run_compose(
+        &mut ctx,
+        &[switch_step(json!("red"), typo, &slots)?],
+        json!({}),
+    )
+    .expect_err("case slot is not declared");
+    assert!(
+        err.to_string().contains("case slot `wram` is not defined"),
+        "{err}"
+    );
+    Ok(())
+}
+
+#[test]
+fn foreach_concurrency_preserves_result_order() -> Result<()> {
+    let registry = create_registry();
+    let mut ctx = registry.context();
+    let steps = parse_compose(&json!([{
+        "call": "lcod://flow/foreach@1",
+        "in": {
+            "list": [
+                { "ms": 80, "value": "a" },
+                { "ms": 10, "value": "b" },
+                { "ms": 40, "value": "c" },
+                { "ms": 5, "value": "d" }
+            ],
+            "concurrency": 4
+        },
+        "collectPath": "$.value",
+        "slots": {
+            "body": [{
+                "call": "lcod://impl/delay@1",
+                "in": { "ms": "$slot.item.ms", "value": "$slot.item.value" },
+                "out": { "value": "value" }
+            }]
+        },
+        "out": { "values": "results" }
+    }]))?;
+
+    let started = std::time::Instant::now();
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    assert_eq!(result["values"], json!(["a", "b", "c", "d"]));
+    assert!(started.elapsed() < std::time::Duration::from_millis(135));
+    Ok(())
+}
+
+#[test]
+fn foreach_concurrency_honours_continue_break_and_aggregates_errors() -> Result<()> {
+    let registry = create_registry();
+    let mut ctx = registry.context();
+    let body = json!([
+        { "call": "lcod://impl/is_even@1", "in": { "value": "$slot.item" }, "out": { "isEven": "ok" } },
+        { "call": "lcod://flow/if@1", "in": { "cond": "$.isEven" }, "slots": { "then": [{ "call": "lcod://flow/continue@1" }] } },
+        { "call": "lcod://impl/gt@1", "in": { "value": "$slot.item", "limit": 7 }, "out": { "tooBig": "ok" } },
+        { "call": "lcod://flow/if@1", "in": { "cond": "$.tooBig" }, "slots": { "then": [{ "call": "lcod://flow/break@1" }] } },
+        { "call": "lcod://impl/echo@1", "in": { "value": "$slot.item" }, "out": { "val": "val" } }
+    ]);
+    let steps = parse_compose(&json!([{
+        "call": "lcod://flow/foreach@1",
+        "in": { "list": [1, 2, 3, 5, 8, 9, 11, 13], "concurrency": 2 },
+        "collectPath": "$.val",
+        "slots": { "body": body },
+        "out": { "numbers": "results" }
+    }]))?;
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    assert_eq!(result["numbers"], json!([1, 3, 5]));
+
+    let steps = parse_compose(&json!([{
+        "call": "lcod://flow/foreach@1",
+        "in": { "list": [1, 2], "concurrency": 2 },
+        "slots": {
+            "body": [
+                { "call": "lcod://impl/delay@1", "in": { "ms": 30 } },
+                { "call": "lcod://impl/fail@1" }
+            ]
+        }
+    }]))?;
+    let err = run_compose(&mut ctx, &steps, json!({})).expect_err("both items fail");
+    let message = err.to_string();
+    assert!(message.contains("2 items failed"), "{message}");
+    assert!(message.contains("#0: boom") && message.contains("#1: boom"));
+    Ok(())
+}
+
+#[test]
+fn reduce_folds_items_into_accumulator() -> Result<()> {
+    let registry = create_registry();
+    registry.register(
+        "lcod://test/add@1",
+        |_ctx: &mut Context, input: Value, _meta: Option<Value>| {
+            let acc = input.get("acc").and_then(Value::as_i64).unwrap_or(0);
+            let item = input.get("item").and_then(Value::as_i64).unwrap_or(0);
+            Ok(json!({ "sum": acc + item }))
+        },
+    );
+    let mut ctx = registry.context();
+    let steps = parse_compose(&json!([{
+        "call": "lcod://flow/reduce@1",
+        "in": { "list": [1, 2, 3, 4, 8, 5], "initial": 10 },
+        "slots": {
+            "body": [
+                { "call": "lcod://impl/is_even@1", "in": { "value": "$slot.item" }, "out": { "isEven": "ok" } },
+                { "call": "lcod://flow/if@1", "in": { "cond": "$.isEven" }, "slots": { "then": [{ "call": "lcod://flow/continue@1" }] } },
+                { "call": "lcod://impl/gt@1", "in": { "value": "$slot.item", "limit": 4 }, "out": { "tooBig": "ok" } },
+                { "call": "lcod://flow/if@1", "in": { "cond": "$.tooBig" }, "slots": { "then": [{ "call": "lcod://flow/break@1" }] } },
+                { "call": "lcod://test/add@1", "in": { "acc": "$slot.acc", "item": "$slot.item" }, "out": { "acc": "sum" } }
+            ]
+        },
+        "out": { "total": "result", "iterations": "iterations" }
+    }]))?;
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    // 10 + 1 + 3; even items are skipped and 5 breaks.
+    assert_eq!(result["total"], json!(14));
+    assert_eq!(result["iterations"], json!(6));
+
+    let steps = parse_compose(&json!([{
+        "call": "lcod://flow/reduce@1",
+        "in": { "list": [1, 2, 3], "initial": 0, "accPath": "running" },
+        "slots": {
+            "body": [
+                { "call": "lcod://test/add@1", "in": { "acc": "$slot.acc", "item": "$slot.item" }, "out": { "running": "sum" } }
+            ]
+        },
+        "out": { "total": "result" }
+    }]))?;
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    assert_eq!(result["total"], json!(6));
+
+    let steps = parse_compose(&json!([{
+        "call": "lcod://flow/reduce@1",
+        "in": { "list": [1, 2], "initial": 0, "accPath": "runnign" },
+        "slots": {
+            "body": [
+                { "call": "lcod://test/add@1", "in": { "acc": "$slot.acc", "item": "$slot.item" }, "out": { "running": "sum" } }
+            ]
+        }
+    }]))?;
+    let err = run_compose(&mut ctx, &steps, json!({})).expect_err("accPath typo");
+    assert!(
+        err.to_string().contains("index 0 has no `runnign` value"),
+        "{err}"
+    );
+
+    let steps = parse_compose(&json!([{
+        "call": "lcod://flow/reduce@1",
+        "in": { "list": [], "initial": { "seen": [] } },
+        "out": { "result": "result" }
+    }]))?;
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    assert_eq!(result["result"], json!({ "seen": [] }));
+    Ok(())
+}
+
+#[test]
+fn batch_chunks_by_size_and_byte_budget() -> Result<()> {
+    let registry = create_registry();
+    registry.register(
+        "lcod://test/bulk@1",
+        |_ctx: &mut Context, input: Value, _meta: Option<Value>| {
+            let batch = input["batch"].as_array().cloned().unwrap_or_default();
+            Ok(json!({ "count": batch.len(), "echo": batch }))
+        },
+    );
+    let mut ctx = registry.context();
+    let batch_step = |batch_in: Value, collect: &str| {
+        parse_compose(&json!([{
+            "call": "lcod://flow/batch@1",
+            "in": batch_in,
+            "collectPath": collect,
+            "slots": {
+                "body": [{
+                    "call": "lcod://test/bulk@1",
+                    "in": { "batch": "$slot.batch" },
+                    "out": { "count": "count", "echo": "echo" }
+                }]
+            },
+            "out": { "results": "results", "batches": "batches" }
+        }]))
+    };
+
+    let list = json!([1, 2, 3, 4, 5, 6, 7]);
+    let steps = batch_step(
+        json!({ "list": list, "size": 3, "flatten": false }),
+        "$.count",
+    )?;
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    assert_eq!(result["results"], json!([3, 3, 1]));
+    assert_eq!(result["batches"], json!(3));
+
+    let steps = batch_step(json!({ "list": list, "size": 3 }), "$.echo")?;
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    assert_eq!(result["results"], list);
+
+    // Each "aaaa" serializes to 6 bytes: two fit in 13, the long one is alone.
+    let steps = batch_step(
+        json!({ "list": ["aaaa", "aaaa", "aaaa", "a-very-long-item", "aaaa"], "maxBytes": 13, "flatten": false }),
+        "$.count",
+    )?;
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    assert_eq!(result["results"], json!([2, 1, 1, 1]));
+
+    let steps = batch_step(json!({ "list": list }), "$.count")?;
+    let err = run_compose(&mut ctx, &steps, json!({})).expect_err("no limit given");
+    assert!(err.to_string().contains("`size` or `maxBytes`"));
+    Ok(())
+}
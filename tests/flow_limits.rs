@@ -0,0 +1,110 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use serde_json::json;
+
+use lcod_kernel_rs::compose::parse_compose;
+use lcod_kernel_rs::flow::CircuitOpenError;
+use lcod_kernel_rs::{run_compose, Registry};
+
+mod common;
+
+use common::{flow_registry, register_mock};
+
+/// `lcod://test/service@1` stands in for a remote API: it fails while `fail`
+/// is set and counts every call.
+fn service_registry() -> (Registry, Arc<AtomicUsize>) {
+    let registry = flow_registry();
+    let calls = register_mock(&registry, "lcod://test/service@1", |_ctx, input, _call| {
+        if input["fail"].as_bool().unwrap_or(false) {
+            return Err(anyhow!("503 service unavailable"));
+        }
+        Ok(json!({ "status": 200 }))
+    });
+    (registry, calls)
+}
+
+#[test]
+fn rate_limit_spreads_calls_across_forks() -> Result<()> {
+    let (registry, calls) = service_registry();
+    let mut ctx = registry.context();
+    let steps = parse_compose(&json!([{
+        "call": "lcod://flow/foreach@1",
+        "in": { "list": [1, 2, 3, 4, 5], "concurrency": 3 },
+        "slots": {
+            "body": [{
+                "call": "lcod://flow/rate_limit@1",
+                "in": { "key": "registry", "rate": 2, "intervalMs": 100 },
+                "slots": { "body": [{ "call": "lcod://test/service@1" }] }
+            }]
+        }
+    }]))?;
+    let started = Instant::now();
+    run_compose(&mut ctx, &steps, json!({}))?;
+    // Two calls fit in the initial burst, the other three wait ~50 ms each.
+    assert!(started.elapsed() >= Duration::from_millis(130));
+    assert_eq!(calls.load(Ordering::SeqCst), 5);
+    Ok(())
+}
+
+#[test]
+fn circuit_breaker_opens_after_failures_and_recovers() -> Result<()> {
+    let (registry, calls) = service_registry();
+    let mut ctx = registry.context();
+    let breaker = |fail: bool, fallback: bool| {
+        let mut slots = json!({
+            "body": [{ "call": "lcod://test/service@1", "in": { "fail": fail }, "out": { "status": "status" } }]
+        });
+        if fallback {
+            slots["fallback"] = json!([{ "call": "lcod://flow/check_abort@1" }]);
+        }
+        parse_compose(&json!([{
+            "call": "lcod://flow/circuit_breaker@1",
+            "in": { "key": "catalogue", "failureThreshold": 2, "cooldownMs": 100 },
+            "slots": slots,
+            "out": { "status": "status", "circuit": "circuit" }
+        }]))
+    };
+
+    for _ in 0..2 {
+        run_compose(&mut ctx, &breaker(true, false)?, json!({})).expect_err("503");
+    }
+    let err = run_compose(&mut ctx, &breaker(false, false)?, json!({})).expect_err("open");
+    assert!(err.is::<CircuitOpenError>());
+    let result = run_compose(&mut ctx, &breaker(false, true)?, json!({}))?;
+    assert_eq!(result["circuit"], json!({ "state": "open", "failures": 2 }));
+    assert!(result["status"].is_null());
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    thread::sleep(Duration::from_millis(120));
+    let result = run_compose(&mut ctx, &breaker(false, false)?, json!({}))?;
+    assert_eq!(result["status"], json!(200));
+    assert_eq!(
+        result["circuit"],
+        json!({ "state": "closed", "failures": 0 })
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    Ok(())
+}
+
+#[test]
+fn failed_trial_reopens_the_circuit() -> Result<()> {
+    let (registry, calls) = service_registry();
+    let mut ctx = registry.context();
+    let steps = parse_compose(&json!([{
+        "call": "lcod://flow/circuit_breaker@1",
+        "in": { "key": "mirror", "failureThreshold": 1, "cooldownMs": 50 },
+        "slots": { "body": [{ "call": "lcod://test/service@1", "in": { "fail": true } }] }
+    }]))?;
+    run_compose(&mut ctx, &steps, json!({})).expect_err("first failure opens");
+    thread::sleep(Duration::from_millis(70));
+    let err = run_compose(&mut ctx, &steps, json!({})).expect_err("trial fails");
+    assert!(err.to_string().contains("503"));
+    let err = run_compose(&mut ctx, &steps, json!({})).expect_err("open again");
+    assert!(err.is::<CircuitOpenError>());
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+use lcod_kernel_rs::compose::{parse_compose, Step};
+use lcod_kernel_rs::{run_compose, Registry};
+
+mod common;
+
+use common::{flow_registry, register_mock, work_for};
+
+/// `lcod://test/mirror@1` answers `value` after `ms` milliseconds, or fails
+/// with `error`; cancelled calls are counted. `stubborn` calls sleep through
+/// cancellation.
+fn mirror_registry() -> (Registry, Arc<AtomicUsize>) {
+    let registry = flow_registry();
+    let cancelled = Arc::new(AtomicUsize::new(0));
+    let cancelled_clone = Arc::clone(&cancelled);
+    register_mock(
+        &registry,
+        "lcod://test/mirror@1",
+        move |ctx, input, _call| {
+            let ms = input.get("ms").and_then(Value::as_u64).unwrap_or(0);
+            if input.get("stubborn").and_then(Value::as_bool) == Some(true) {
+                thread::sleep(Duration::from_millis(ms));
+                return Ok(json!({ "value": input["value"] }));
+            }
+            if let Err(err) = work_for(ctx, ms) {
+                cancelled_clone.fetch_add(1, Ordering::SeqCst);
+                return Err(err);
+            }
+            if let Some(message) = input.get("error").and_then(Value::as_str) {
+                return Err(anyhow!("{message}"));
+            }
+            Ok(json!({ "value": input["value"] }))
+        },
+    );
+    (registry, cancelled)
+}
+
+/// Losing branches are detached, so they may still be unwinding when the
+/// block returns; give them a moment to reach `expected`.
+fn settled_count(counter: &AtomicUsize, expected: usize) -> usize {
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while counter.load(Ordering::SeqCst) < expected && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(5));
+    }
+    counter.load(Ordering::SeqCst)
+}
+
+fn branches_step(block: &str, branches: Value) -> Result<Vec<Step>> {
+    let mut slots = serde_json::Map::new();
+    for (name, input) in branches.as_object().expect("branches object") {
+        slots.insert(
+            name.clone(),
+            json!([{ "call": "lcod://test/mirror@1", "in": input, "out": { "value": "value" } }]),
+        );
+    }
+    parse_compose(&json!([{
+        "call": block,
+        "slots": slots,
+        "out": { "value": "value", "winner": "winner" }
+    }]))
+}
+
+#[test]
+fn any_returns_first_success_and_cancels_the_rest() -> Result<()> {
+    let (registry, cancelled) = mirror_registry();
+    let mut ctx = registry.context();
+    let steps = branches_step(
+        "lcod://flow/any@1",
+        json!({
+            "git": { "ms": 5, "error": "git clone failed" },
+            "http": { "ms": 40, "value": "from-http" },
+            "slow": { "ms": 5_000, "value": "from-slow" }
+        }),
+    )?;
+    let started = Instant::now();
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(result["winner"], json!("http"));
+    assert_eq!(result["value"], json!("from-http"));
+    assert_eq!(settled_count(&cancelled, 1), 1);
+    assert!(!ctx.is_cancelled());
+    Ok(())
+}
+
+#[test]
+fn race_settles_on_first_completion_even_when_it_fails() -> Result<()> {
+    let (registry, cancelled) = mirror_registry();
+    let mut ctx = registry.context();
+    let steps = branches_step(
+        "lcod://flow/race@1",
+        json!({
+            "fast": { "ms": 5, "error": "mirror offline" },
+            "slow": { "ms": 5_000, "value": "late" }
+        }),
+    )?;
+    let err = run_compose(&mut ctx, &steps, json!({})).expect_err("fast branch failed");
+    assert!(err.to_string().contains("mirror offline"));
+    assert_eq!(settled_count(&cancelled, 1), 1);
+
+    let steps = branches_step(
+        "lcod://flow/race@1",
+        json!({
+            "fast": { "ms": 5, "value": "first" },
+            "slow": { "ms": 5_000, "value": "late" }
+        }),
+    )?;
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    assert_eq!(result["winner"], json!("fast"));
+    assert_eq!(result["value"], json!("first"));
+    Ok(())
+}
+
+#[test]
+fn any_reports_every_error_when_all_branches_fail() -> Result<()> {
+    let (registry, _) = mirror_registry();
+    let mut ctx = registry.context();
+    let steps = branches_step(
+        "lcod://flow/any@1",
+        json!({
+            "primary": { "ms": 20, "error": "registry unreachable" },
+            "secondary": { "ms": 5, "error": "checksum mismatch" }
+        }),
+    )?;
+    let err = run_compose(&mut ctx, &steps, json!({})).expect_err("no mirror answered");
+    let message = err.to_string();
+    assert!(message.contains("all 2 branches failed"), "{message}");
+    assert!(message.contains("primary: registry unreachable"));
+    assert!(message.contains("secondary: checksum mismatch"));
+    Ok(())
+}
+
+#[test]
+fn race_returns_without_waiting_for_branches_that_ignore_cancellation() -> Result<()> {
+    let (registry, _) = mirror_registry();
+    let mut ctx = registry.context();
+    let steps = branches_step(
+        "lcod://flow/race@1",
+        json!({
+            "fast": { "ms": 5, "value": "first" },
+            "stuck": { "ms": 3_000, "stubborn": true, "value": "late" }
+        }),
+    )?;
+    let started = Instant::now();
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert_eq!(result["winner"], json!("fast"));
+    Ok(())
+}
+
+#[test]
+fn branch_named_body_runs_once() -> Result<()> {
+    let (registry, _) = mirror_registry();
+    let calls = register_mock(&registry, "lcod://test/count@1", |_ctx, _input, _call| {
+        Err(anyhow!("mirror offline"))
+    });
+    let mut ctx = registry.context();
+    let steps = parse_compose(&json!([{
+        "call": "lcod://flow/any@1",
+        "slots": {
+            "body": [{ "call": "lcod://test/count@1" }]
+        }
+    }]))?;
+    let err = run_compose(&mut ctx, &steps, json!({})).expect_err("the only branch failed");
+    assert_eq!(err.to_string(), "mirror offline");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+use lcod_kernel_rs::compose::parse_compose;
+use lcod_kernel_rs::{run_compose, CancelledError, Registry};
+
+mod common;
+
+use common::{flow_registry, register_mock};
+
+fn flaky_registry(failures: usize, message: &'static str) -> (Registry, Arc<AtomicUsize>) {
+    let registry = flow_registry();
+    let calls = register_mock(
+        &registry,
+        "lcod://test/flaky@1",
+        move |_ctx, input, call| {
+            if call <= failures {
+                return Err(anyhow!("{message} (call {call})"));
+            }
+            Ok(json!({ "attempt": input["attempt"], "previous": input["previous"] }))
+        },
+    );
+    (registry, calls)
+}
+
+fn retry_compose(retry_in: Value) -> Result<Vec<lcod_kernel_rs::compose::Step>> {
+    parse_compose(&json!([{
+        "call": "lcod://flow/retry@1",
+        "in": retry_in,
+        "slots": {
+            "body": [{
+                "call": "lcod://test/flaky@1",
+                "in": { "attempt": "$slot.attempt", "previous": "$slot.error.message" },
+                "out": { "attempt": "attempt", "previous": "previous" }
+            }]
+        },
+        "out": { "attempt": "attempt", "previous": "previous", "attempts": "attempts" }
+    }]))
+}
+
+#[test]
+fn retry_reruns_body_until_success() -> Result<()> {
+    let (registry, calls) = flaky_registry(2, "service unavailable");
+    let mut ctx = registry.context();
+    let steps = retry_compose(json!({ "attempts": 5, "delayMs": 5, "jitter": 0.5 }))?;
+
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    assert_eq!(result["attempts"], json!(3));
+    assert_eq!(result["attempt"], json!(3));
+    assert_eq!(result["previous"], json!("service unavailable (call 2)"));
+    Ok(())
+}
+
+#[test]
+fn retry_gives_up_after_attempts_and_honours_filters() -> Result<()> {
+    let (registry, calls) = flaky_registry(10, "service unavailable");
+    let mut ctx = registry.context();
+    let steps = retry_compose(json!({ "attempts": 3 }))?;
+    let err = run_compose(&mut ctx, &steps, json!({})).expect_err("always failing");
+    assert!(err.to_string().contains("call 3"));
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    let (registry, calls) = flaky_registry(10, "invalid credentials");
+    let mut ctx = registry.context();
+    let steps = retry_compose(json!({
+        "attempts": 5,
+        "retryOn": { "messages": ["unavailable", "timeout"] }
+    }))?;
+    run_compose(&mut ctx, &steps, json!({})).expect_err("not retryable");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let (registry, calls) = flaky_registry(10, "invalid credentials");
+    let mut ctx = registry.context();
+    let steps = retry_compose(json!({
+        "attempts": 5,
+        "abortOn": { "messages": ["credentials"] }
+    }))?;
+    run_compose(&mut ctx, &steps, json!({})).expect_err("aborted");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    Ok(())
+}
+
+#[test]
+fn retry_respects_max_duration() -> Result<()> {
+    let (registry, calls) = flaky_registry(10, "busy");
+    let mut ctx = registry.context();
+    let steps = retry_compose(json!({
+        "attempts": 10,
+        "delayMs": 40,
+        "maxDurationMs": 100
+    }))?;
+    let err = run_compose(&mut ctx, &steps, json!({})).expect_err("duration exceeded");
+    assert!(format!("{err:#}").contains("maxDurationMs exceeded"));
+    assert!(calls.load(Ordering::SeqCst) < 10);
+    Ok(())
+}
+
+#[test]
+fn retry_backoff_sleep_is_cancellable() -> Result<()> {
+    let (registry, calls) = flaky_registry(10, "busy");
+    let mut ctx = registry.context();
+    let token = ctx.cancellation_token();
+    let steps = retry_compose(json!({ "attempts": 3, "delayMs": 10_000 }))?;
+
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        token.store(true, Ordering::SeqCst);
+    });
+    let started = Instant::now();
+    let err = run_compose(&mut ctx, &steps, json!({})).expect_err("cancelled");
+    canceller.join().unwrap();
+
+    assert!(err.is::<CancelledError>());
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+use lcod_kernel_rs::compose::{parse_compose, Step};
+use lcod_kernel_rs::flow::SagaError;
+use lcod_kernel_rs::{run_compose, Registry};
+
+mod common;
+
+use common::{flow_registry, register_mock};
+
+/// `lcod://test/record@1` appends `label` to a journal, failing when `fail`
+/// is set.
+fn journal_registry() -> (Registry, Arc<Mutex<Vec<String>>>) {
+    let registry = flow_registry();
+    let journal = Arc::new(Mutex::new(Vec::new()));
+    let journal_clone = Arc::clone(&journal);
+    register_mock(
+        &registry,
+        "lcod://test/record@1",
+        move |_ctx, input, _call| {
+            let label = input["label"].as_str().unwrap_or_default().to_string();
+            if input["fail"].as_bool().unwrap_or(false) {
+                return Err(anyhow!("{label} failed"));
+            }
+            journal_clone.lock().unwrap().push(label.clone());
+            Ok(json!({ "label": label }))
+        },
+    );
+    (registry, journal)
+}
+
+fn record(label: &str, fail: bool) -> Value {
+    json!([{
+        "call": "lcod://test/record@1",
+        "in": { "label": label, "fail": fail },
+        "out": { label: "label" }
+    }])
+}
+
+fn saga_step(failing: &[&str]) -> Result<Vec<Step>> {
+    let slot = |label: &str| record(label, failing.contains(&label));
+    parse_compose(&json!([{
+        "call": "lcod://flow/saga@1",
+        "in": {
+            "steps": [
+                { "slot": "writeFiles", "compensate": "removeFiles" },
+                "validate",
+                { "slot": "publish", "compensate": "unpublish" },
+                { "slot": "register", "compensate": "unregister" }
+            ]
+        },
+        "slots": {
+            "writeFiles": slot("writeFiles"),
+            "removeFiles": slot("removeFiles"),
+            "validate": slot("validate"),
+            "publish": slot("publish"),
+            "unpublish": slot("unpublish"),
+            "register": slot("register"),
+            "unregister": slot("unregister")
+        },
+        "out": { "completed": "completed", "publish": "publish" }
+    }]))
+}
+
+#[test]
+fn saga_runs_all_steps_when_nothing_fails() -> Result<()> {
+    let (registry, journal) = journal_registry();
+    let mut ctx = registry.context();
+    let result = run_compose(&mut ctx, &saga_step(&[])?, json!({}))?;
+    assert_eq!(
+        result["completed"],
+        json!(["writeFiles", "validate", "publish", "register"])
+    );
+    assert_eq!(result["publish"], json!("publish"));
+    assert_eq!(journal.lock().unwrap().len(), 4);
+    Ok(())
+}
+
+#[test]
+fn saga_compensates_completed_steps_in_reverse_order() -> Result<()> {
+    let (registry, journal) = journal_registry();
+    let mut ctx = registry.context();
+    let err =
+        run_compose(&mut ctx, &saga_step(&["register"])?, json!({})).expect_err("register fails");
+    let saga = err.downcast_ref::<SagaError>().expect("saga error");
+    assert_eq!(saga.step, "register");
+    assert_eq!(saga.compensated, vec!["publish", "writeFiles"]);
+    assert!(saga.failed_compensations.is_empty());
+    assert_eq!(
+        *journal.lock().unwrap(),
+        vec![
+            "writeFiles",
+            "validate",
+            "publish",
+            "unpublish",
+            "removeFiles"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn saga_reports_failed_compensations_and_keeps_going() -> Result<()> {
+    let (registry, journal) = journal_registry();
+    let mut ctx = registry.context();
+    let err = run_compose(&mut ctx, &saga_step(&["register", "unpublish"])?, json!({}))
+        .expect_err("register fails");
+    let message = err.to_string();
+    assert!(message.contains("step `register` failed: register failed"));
+    assert!(message.contains("compensation for `publish` failed: unpublish failed"));
+    let saga = err.downcast_ref::<SagaError>().expect("saga error");
+    assert_eq!(saga.compensated, vec!["writeFiles"]);
+    assert_eq!(journal.lock().unwrap().last().unwrap(), "removeFiles");
+    Ok(())
+}
+
+#[test]
+fn saga_rejects_undefined_slots_before_running_any_step() -> Result<()> {
+    let (registry, journal) = journal_registry();
+    let mut ctx = registry.context();
+    let steps = parse_compose(&json!([{
+        "call": "lcod://flow/saga@1",
+        "in": {
+            "steps": [
+                { "slot": "writeFiles", "compensate": "removeFlies" },
+                "validate"
+            ]
+        },
+        "slots": {
+            "writeFiles": record("writeFiles", false),
+            "removeFiles": record("removeFiles", false),
+            "validate": record("validate", false)
+        }
+    }]))?;
+    let err = run_compose(&mut ctx, &steps, json!({})).expect_err("typo in compensate");
+    assert!(
+        err.to_string()
+            .contains("slot `removeFlies` is not defined"),
+        "{err}"
+    );
+    assert!(journal.lock().unwrap().is_empty());
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use lcod_kernel_rs::compose::{parse_compose, Step};
+use lcod_kernel_rs::flow::TimeoutError;
+use lcod_kernel_rs::{run_compose, CancelledError, Registry};
+
+mod common;
+
+use common::{flow_registry, register_mock, work_for};
+
+/// `lcod://test/slow@1` works for `ms` milliseconds.
+fn slow_registry() -> (Registry, Arc<AtomicUsize>) {
+    let registry = flow_registry();
+    let calls = register_mock(&registry, "lcod://test/slow@1", |ctx, input, _call| {
+        work_for(ctx, input.get("ms").and_then(Value::as_u64).unwrap_or(0))?;
+        Ok(json!({ "done": true }))
+    });
+    (registry, calls)
+}
+
+fn timeout_step(timeout_ms: u64, work_ms: u64, on_timeout: bool) -> Result<Vec<Step>> {
+    let mut slots = json!({
+        "body": [{ "call": "lcod://test/slow@1", "in": { "ms": work_ms }, "out": { "done": "done" } }]
+    });
+    if on_timeout {
+        slots["onTimeout"] = json!([{ "call": "lcod://flow/check_abort@1" }]);
+    }
+    parse_compose(&json!([{
+        "call": "lcod://flow/timeout@1",
+        "in": { "timeoutMs": timeout_ms },
+        "slots": slots,
+        "out": { "done": "done", "timedOut": "timedOut" }
+    }]))
+}
+
+#[test]
+fn timeout_lets_fast_bodies_complete() -> Result<()> {
+    let (registry, _) = slow_registry();
+    let mut ctx = registry.context();
+    let result = run_compose(&mut ctx, &timeout_step(500, 10, false)?, json!({}))?;
+    assert_eq!(result["done"], json!(true));
+    assert_eq!(result["timedOut"], json!(false));
+    assert!(!ctx.is_cancelled());
+    Ok(())
+}
+
+#[test]
+fn timeout_cancels_slow_bodies_and_throws() -> Result<()> {
+    let (registry, _) = slow_registry();
+    let mut ctx = registry.context();
+    let started = Instant::now();
+    let err = run_compose(&mut ctx, &timeout_step(50, 5_000, false)?, json!({}))
+        .expect_err("deadline exceeded");
+    assert!(started.elapsed() < Duration::from_secs(2));
+    let timeout = err.downcast_ref::<TimeoutError>().expect("timeout error");
+    assert_eq!(timeout.timeout_ms, 50);
+    // The parent context keeps running.
+    assert!(!ctx.is_cancelled());
+    Ok(())
+}
+
+#[test]
+fn timeout_runs_on_timeout_slot() -> Result<()> {
+    let (registry, _) = slow_registry();
+    let mut ctx = registry.context();
+    let result = run_compose(&mut ctx, &timeout_step(30, 5_000, true)?, json!({}))?;
+    assert_eq!(result["timedOut"], json!(true));
+    assert!(result["done"].is_null());
+    Ok(())
+}
+
+#[test]
+fn parent_cancellation_is_not_reported_as_timeout() -> Result<()> {
+    let (registry, _) = slow_registry();
+    let mut ctx = registry.context();
+    let token = ctx.cancellation_token();
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(30));
+        token.store(true, Ordering::SeqCst);
+    });
+    let err = run_compose(&mut ctx, &timeout_step(5_000, 5_000, true)?, json!({}))
+        .expect_err("cancelled");
+    canceller.join().unwrap();
+    assert!(err.is::<CancelledError>());
+    Ok(())
+}
+
+#[test]
+fn timeouts_can_be_retried_by_code() -> Result<()> {
+    let (registry, calls) = slow_registry();
+    let mut ctx = registry.context();
+    let steps = parse_compose(&json!([{
+        "call": "lcod://flow/retry@1",
+        "in": { "attempts": 3, "retryOn": { "codes": ["timeout"] } },
+        "slots": {
+            "body": [{
+                "call": "lcod://flow/timeout@1",
+                "in": { "timeoutMs": 20 },
+                "slots": { "body": [{ "call": "lcod://test/slow@1", "in": { "ms": 1_000 } }] }
+            }]
+        }
+    }]))?;
+    let err = run_compose(&mut ctx, &steps, json!({})).expect_err("always too slow");
+    assert!(err.is::<TimeoutError>());
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use lcod_kernel_rs::compose::{parse_compose, Step};
+use lcod_kernel_rs::flow::{FlowError, TimeoutError};
+use lcod_kernel_rs::{run_compose, CancelledError, Context, Registry};
+
+mod common;
+
+use common::{flow_registry, register_mock};
+
+/// Registers `lcod://test/cancel@1` (cancels its context) and
+/// `lcod://test/cleanup@1` (counts its calls).
+fn try_registry() -> (Registry, Arc<AtomicUsize>) {
+    let registry = flow_registry();
+    register_mock(&registry, "lcod://test/cancel@1", |ctx, _input, _call| {
+        ctx.cancel();
+        ctx.ensure_not_cancelled()?;
+        Ok(json!({}))
+    });
+    let cleanups = register_mock(&registry, "lcod://test/cleanup@1", |_ctx, input, _call| {
+        Ok(json!({ "clause": input["clause"], "code": input["code"] }))
+    });
+    (registry, cleanups)
+}
+
+fn try_step(body: Value, catches: Value, slots: Value) -> Result<Vec<Step>> {
+    let mut slots = slots;
+    slots["children"] = body;
+    slots["finally"] = json!([{ "call": "lcod://test/cleanup@1" }]);
+    parse_compose(&json!([{
+        "call": "lcod://flow/try@1",
+        "in": { "catches": catches },
+        "slots": slots,
+        "out": { "handledBy": "handledBy", "code": "code" }
+    }]))
+}
+
+/// Catch clause recording which slot handled the error.
+fn handler() -> Value {
+    json!([{
+        "call": "lcod://test/cleanup@1",
+        "in": { "clause": "$slot.clause", "code": "$slot.error.code" },
+        "out": { "handledBy": "clause", "code": "code" }
+    }])
+}
+
+#[test]
+fn try_selects_catch_clause_by_code() -> Result<()> {
+    let (registry, cleanups) = try_registry();
+    let mut ctx = registry.context();
+    let catches = json!([
+        { "code": ["not_found", "gone"], "slot": "missing" },
+        { "kind": "timeout", "slot": "slow" }
+    ]);
+    let slots = json!({ "missing": handler(), "catch": handler() });
+
+    let throw = |code: &str| json!([{ "call": "lcod://flow/throw@1", "in": { "code": code, "message": "no such package" } }]);
+    let steps = try_step(throw("not_found"), catches.clone(), slots.clone())?;
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    assert_eq!(result["handledBy"], json!("missing"));
+    assert_eq!(result["code"], json!("not_found"));
+
+    let steps = try_step(throw("denied"), catches, slots)?;
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    assert_eq!(result["handledBy"], json!("catch"));
+    assert_eq!(result["code"], json!("denied"));
+    assert_eq!(cleanups.load(Ordering::SeqCst), 4);
+    Ok(())
+}
+
+#[test]
+fn try_rejects_catch_clause_with_undefined_slot() -> Result<()> {
+    let (registry, cleanups) = try_registry();
+    let mut ctx = registry.context();
+    let steps = try_step(
+        json!([
+            { "call": "lcod://test/cleanup@1" },
+            { "call": "lcod://flow/throw@1", "in": { "code": "not_found", "message": "gone" } }
+        ]),
+        json!([{ "code": "not_found", "slot": "misssing" }]),
+        json!({ "missing": handler() }),
+    )?;
+    let err = run_compose(&mut ctx, &steps, json!({})).expect_err("undefined slot");
+    assert!(err.to_string().contains("`misssing` is not defined"));
+    // The body ran once, then `finally`; it was not rerun as the handler.
+    assert_eq!(cleanups.load(Ordering::SeqCst), 2);
+    Ok(())
+}
+
+#[test]
+fn try_rethrows_from_catch_and_still_runs_finally() -> Result<()> {
+    let (registry, cleanups) = try_registry();
+    let mut ctx = registry.context();
+    let steps = try_step(
+        json!([{ "call": "lcod://flow/throw@1", "in": { "code": "conflict", "message": "version taken", "data": { "version": "1.0.0" } } }]),
+        json!([]),
+        json!({ "catch": [{ "call": "lcod://flow/rethrow@1" }] }),
+    )?;
+    let err = run_compose(&mut ctx, &steps, json!({})).expect_err("rethrown");
+    let flow_error = err.downcast_ref::<FlowError>().expect("original error");
+    assert_eq!(flow_error.code, "conflict");
+    assert_eq!(flow_error.data, Some(json!({ "version": "1.0.0" })));
+    assert_eq!(cleanups.load(Ordering::SeqCst), 1);
+    Ok(())
+}
+
+#[test]
+fn cancellation_skips_plain_catch_but_runs_finally() -> Result<()> {
+    let (registry, cleanups) = try_registry();
+    let mut ctx = registry.context();
+    let body = json!([{ "call": "lcod://test/cancel@1" }]);
+    let steps = try_step(body.clone(), json!([]), json!({ "catch": handler() }))?;
+    let err = run_compose(&mut ctx, &steps, json!({})).expect_err("cancelled");
+    assert!(err.is::<CancelledError>());
+    // Only `finally` ran.
+    assert_eq!(cleanups.load(Ordering::SeqCst), 1);
+
+    let mut ctx = registry.context();
+    let steps = try_step(
+        body,
+        json!([{ "kind": "cancelled", "slot": "aborted" }]),
+        json!({ "aborted": handler() }),
+    )?;
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    assert_eq!(result["handledBy"], json!("aborted"));
+    assert_eq!(result["code"], json!("cancelled"));
+    assert_eq!(cleanups.load(Ordering::SeqCst), 3);
+    Ok(())
+}
+
+#[test]
+fn timeouts_can_be_excluded_from_catch() -> Result<()> {
+    let (registry, _) = try_registry();
+    let mut ctx = registry.context();
+    let body = json!([{
+        "call": "lcod://flow/timeout@1",
+        "in": { "timeoutMs": 20 },
+        "slots": { "body": [{ "call": "lcod://impl/never@1" }] }
+    }]);
+    registry.register(
+        "lcod://impl/never@1",
+        |ctx: &mut Context, _input: Value, _meta: Option<Value>| loop {
+            ctx.ensure_not_cancelled()?;
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        },
+    );
+    let steps = try_step(
+        body,
+        json!([{ "kind": "timeout", "rethrow": true }]),
+        json!({ "catch": handler() }),
+    )?;
+    let err = run_compose(&mut ctx, &steps, json!({})).expect_err("timeout propagates");
+    assert!(err.is::<TimeoutError>());
+    Ok(())
+}
@@ -0,0 +1,239 @@
+use std::fs;
+
+use anyhow::Result;
+use serde_json::json;
+use tempfile::tempdir;
+
+use lcod_kernel_rs::core::register_core;
+use lcod_kernel_rs::fs_policy::{FsAccess, FsPolicy, PermissionError};
+use lcod_kernel_rs::{register_resolver_axioms, register_tooling, Registry};
+
+fn registry_with_policy(policy: FsPolicy) -> Registry {
+    let registry = Registry::new();
+    register_core(&registry);
+    registry.set_fs_policy(policy);
+    registry
+}
+
+#[test]
+fn contracts_reject_paths_outside_allowed_roots() -> Result<()> {
+    let sandbox = tempdir()?;
+    let outside = tempdir()?;
+    let readonly = sandbox.path().join("readonly");
+    let writable = sandbox.path().join("writable");
+    fs::create_dir_all(&readonly)?;
+    fs::create_dir_all(&writable)?;
+    fs::write(readonly.join("in.txt"), "inside")?;
+    fs::write(outside.path().join("secret.txt"), "secret")?;
+
+    let registry = registry_with_policy(
+        FsPolicy::unrestricted()
+            .allow_read(&readonly)
+            .allow_write(&writable),
+    );
+    let mut ctx = registry.context();
+
+    let read = ctx.call(
+        "lcod://contract/core/fs/read-file@1",
+        json!({ "path": readonly.join("in.txt").to_string_lossy() }),
+        None,
+    )?;
+    assert_eq!(read["data"], json!("inside"));
+
+    let err = ctx
+        .call(
+            "lcod://contract/core/fs/read-file@1",
+            json!({ "path": outside.path().join("secret.txt").to_string_lossy() }),
+            None,
+        )
+        .unwrap_err();
+    let denied = err
+        .downcast_ref::<PermissionError>()
+        .expect("permission error");
+    assert_eq!(denied.access, FsAccess::Read);
+
+    let err = ctx
+        .call(
+            "lcod://contract/core/fs/write-file@1",
+            json!({ "path": readonly.join("out.txt").to_string_lossy(), "data": "x" }),
+            None,
+        )
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("permission denied: write access"));
+
+    ctx.call(
+        "lcod://contract/core/fs/copy@1",
+        json!({
+            "from": readonly.join("in.txt").to_string_lossy(),
+            "to": writable.join("copy.txt").to_string_lossy()
+        }),
+        None,
+    )?;
+    assert_eq!(fs::read_to_string(writable.join("copy.txt"))?, "inside");
+
+    let err = ctx
+        .call(
+            "lcod://contract/core/hash/sha256@1",
+            json!({ "path": outside.path().join("secret.txt").to_string_lossy() }),
+            None,
+        )
+        .unwrap_err();
+    assert!(err.is::<PermissionError>());
+
+    let traversal = writable.join("..").join("..").join("escape.txt");
+    let err = ctx
+        .call(
+            "lcod://contract/core/fs/write-file@1",
+            json!({ "path": traversal.to_string_lossy(), "data": "x" }),
+            None,
+        )
+        .unwrap_err();
+    assert!(err.is::<PermissionError>());
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_escapes_are_denied_when_configured() -> Result<()> {
+    let sandbox = tempdir()?;
+    let outside = tempdir()?;
+    fs::write(outside.path().join("secret.txt"), "secret")?;
+    std::os::unix::fs::symlink(outside.path(), sandbox.path().join("link"))?;
+    let through_link = sandbox.path().join("link").join("secret.txt");
+    let input = json!({ "path": through_link.to_string_lossy() });
+
+    let lenient = registry_with_policy(FsPolicy::unrestricted().allow_read(sandbox.path()));
+    let read =
+        lenient
+            .context()
+            .call("lcod://contract/core/fs/read-file@1", input.clone(), None)?;
+    assert_eq!(read["data"], json!("secret"));
+
+    let strict = registry_with_policy(
+        FsPolicy::unrestricted()
+            .allow_read(sandbox.path())
+            .deny_symlink_escapes(true),
+    );
+    let mut ctx = strict.context();
+    let err = ctx
+        .call("lcod://contract/core/fs/read-file@1", input, None)
+        .unwrap_err();
+    assert!(err.to_string().contains("through a symlink"));
+
+    let listing = ctx.call(
+        "lcod://contract/core/fs/glob@1",
+        json!({
+            "cwd": sandbox.path().to_string_lossy(),
+            "patterns": ["**/*"],
+            "followSymlinks": true
+        }),
+        None,
+    )?;
+    assert_eq!(listing["paths"], json!([]));
+    Ok(())
+}
+
+#[test]
+fn parse_secret_tooling_and_download_contracts_honour_the_policy() -> Result<()> {
+    let sandbox = tempdir()?;
+    let outside = tempdir()?;
+    let secret = outside.path().join("secret.json");
+    fs::write(&secret, "{\"token\": \"abc\"}")?;
+    let target = outside.path().join("written.txt");
+
+    let registry = registry_with_policy(
+        FsPolicy::unrestricted()
+            .allow_read(sandbox.path())
+            .allow_write(sandbox.path()),
+    );
+    register_tooling(&registry);
+    register_resolver_axioms(&registry);
+    let mut ctx = registry.context();
+
+    let secret_path = secret.to_string_lossy();
+    let target_path = target.to_string_lossy();
+    let cases = [
+        (
+            "lcod://contract/core/parse/json@1",
+            json!({ "path": secret_path }),
+        ),
+        (
+            "lcod://contract/core/parse/toml@1",
+            json!({ "path": secret_path }),
+        ),
+        (
+            "lcod://contract/core/parse/yaml@1",
+            json!({ "path": secret_path }),
+        ),
+        (
+            "lcod://contract/core/parse/csv@1",
+            json!({ "path": secret_path }),
+        ),
+        (
+            "lcod://contract/core/parse/ndjson@1",
+            json!({ "path": secret_path }),
+        ),
+        (
+            "lcod://contract/core/secret/get@1",
+            json!({ "path": secret_path }),
+        ),
+        (
+            "lcod://contract/tooling/fs/read_optional@1",
+            json!({ "path": secret_path, "fallback": "none" }),
+        ),
+        (
+            "lcod://contract/tooling/fs/write_if_changed@1",
+            json!({ "path": target_path, "content": "x" }),
+        ),
+        (
+            "lcod://axiom/http/download@1",
+            json!({ "url": "http://127.0.0.1:9/file", "path": target_path }),
+        ),
+    ];
+    for (contract, input) in cases {
+        let err = ctx.call(contract, input, None).unwrap_err();
+        assert!(err.is::<PermissionError>(), "{contract}: {err}");
+    }
+    assert!(!target.exists());
+
+    let inside = sandbox.path().join("written.txt");
+    ctx.call(
+        "lcod://contract/tooling/fs/write_if_changed@1",
+        json!({ "path": inside.to_string_lossy(), "content": "x" }),
+        None,
+    )?;
+    assert_eq!(fs::read_to_string(inside)?, "x");
+    Ok(())
+}
+
+#[test]
+fn policy_loads_from_manifest_sandbox_table() -> Result<()> {
+    let base = tempdir()?;
+    let manifest: toml::Value = r#"
+[sandbox.fs]
+read = ["data"]
+write = ["out"]
+denySymlinkEscapes = true
+"#
+    .parse()?;
+    let policy = FsPolicy::from_manifest(&manifest, base.path()).expect("sandbox table");
+    assert!(policy.denies_symlink_escapes());
+    assert!(policy
+        .check(&base.path().join("data/input.json"), FsAccess::Read)
+        .is_ok());
+    assert!(policy
+        .check(&base.path().join("out/report.json"), FsAccess::Read)
+        .is_ok());
+    assert!(policy
+        .check(&base.path().join("data/input.json"), FsAccess::Write)
+        .is_err());
+    assert!(policy
+        .check(&base.path().join("lcp.toml"), FsAccess::Read)
+        .is_err());
+
+    let plain: toml::Value = "[inputs]\n".parse()?;
+    assert!(FsPolicy::from_manifest(&plain, base.path()).is_none());
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use tempfile::tempdir;
+
+use lcod_kernel_rs::compose::parse_compose;
+use lcod_kernel_rs::core::register_core;
+use lcod_kernel_rs::{run_compose, Context, Registry};
+
+fn event_types(events: &[Value]) -> Vec<(String, String)> {
+    events
+        .iter()
+        .map(|event| {
+            let path = event["path"].as_str().unwrap_or_default();
+            let name = path.rsplit('/').next().unwrap_or_default().to_string();
+            (event["type"].as_str().unwrap_or_default().to_string(), name)
+        })
+        .collect()
+}
+
+#[test]
+fn watch_stream_emits_debounced_ndjson_batches() -> Result<()> {
+    let registry = Registry::new();
+    register_core(&registry);
+    let mut ctx = registry.context();
+    let dir = tempdir()?;
+    fs::write(dir.path().join("keep.txt"), "v1")?;
+    fs::write(dir.path().join("old.txt"), "x")?;
+
+    let watch = ctx.call(
+        "lcod://contract/core/fs/watch@1",
+        json!({
+            "paths": [dir.path().to_string_lossy()],
+            "intervalMs": 20,
+            "debounceMs": 80,
+            "maxBatches": 1
+        }),
+        None,
+    )?;
+    fs::write(dir.path().join("new.txt"), "hello")?;
+    fs::write(dir.path().join("keep.txt"), "v2 longer")?;
+    fs::rename(dir.path().join("old.txt"), dir.path().join("moved.txt"))?;
+
+    let mut text = String::new();
+    loop {
+        let chunk = ctx.call(
+            "lcod://contract/core/stream/read@1",
+            json!({ "stream": watch["stream"] }),
+            None,
+        )?;
+        if chunk["done"].as_bool().unwrap_or(false) {
+            break;
+        }
+        text.push_str(chunk["chunk"].as_str().unwrap_or_default());
+    }
+    let events: Vec<Value> = text
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    let mut seen = event_types(&events);
+    seen.sort();
+    assert_eq!(
+        seen,
+        vec![
+            ("created".to_string(), "new.txt".to_string()),
+            ("modified".to_string(), "keep.txt".to_string()),
+            ("renamed".to_string(), "moved.txt".to_string()),
+        ]
+    );
+    let renamed = events.iter().find(|e| e["type"] == "renamed").unwrap();
+    assert!(renamed["from"].as_str().unwrap().ends_with("/old.txt"));
+    Ok(())
+}
+
+#[test]
+fn watch_runs_on_change_slot_until_max_batches() -> Result<()> {
+    let registry = Registry::new();
+    register_core(&registry);
+    let batches: Arc<Mutex<Vec<Vec<Value>>>> = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&batches);
+    registry.register(
+        "lcod://test/record@1",
+        move |_ctx: &mut Context, input: Value, _meta: Option<Value>| {
+            let events = input["events"].as_array().cloned().unwrap_or_default();
+            sink.lock().unwrap().push(events);
+            Ok(json!({}))
+        },
+    );
+    let dir = tempdir()?;
+    let target = dir.path().join("config.yaml");
+    fs::write(&target, "a: 1")?;
+
+    let steps = parse_compose(&json!([{
+        "call": "lcod://contract/core/fs/watch@1",
+        "in": {
+            "paths": target.to_string_lossy(),
+            "intervalMs": 20,
+            "debounceMs": 60,
+            "maxBatches": 2
+        },
+        "slots": {
+            "onChange": [{ "call": "lcod://test/record@1", "in": { "events": "$slot.events" } }]
+        },
+        "out": { "batches": "batches" }
+    }]))?;
+
+    let writer = {
+        let target = target.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            fs::write(&target, "a: 2, b: 3").unwrap();
+            thread::sleep(Duration::from_millis(300));
+            fs::remove_file(&target).unwrap();
+        })
+    };
+    let mut ctx = registry.context();
+    let result = run_compose(&mut ctx, &steps, json!({}))?;
+    writer.join().unwrap();
+
+    assert_eq!(result["batches"], json!(2));
+    let recorded = batches.lock().unwrap();
+    assert_eq!(
+        event_types(&recorded[0]),
+        vec![("modified".to_string(), "config.yaml".to_string())]
+    );
+    assert_eq!(
+        event_types(&recorded[1]),
+        vec![("removed".to_string(), "config.yaml".to_string())]
+    );
+    Ok(())
+}
+
+#[test]
+fn closing_or_dropping_a_channel_stream_stops_its_producer() -> Result<()> {
+    let registry = Registry::new();
+    register_core(&registry);
+    let mut ctx = registry.context();
+
+    let (_sender, receiver) = mpsc::channel::<Vec<u8>>();
+    let stop = Arc::new(AtomicBool::new(false));
+    let stream = ctx
+        .streams_mut()
+        .register_channel(receiver, Arc::clone(&stop), "utf-8");
+    assert!(!stop.load(Ordering::SeqCst));
+    ctx.call(
+        "lcod://contract/core/stream/close@1",
+        json!({ "stream": stream }),
+        None,
+    )?;
+    assert!(stop.load(Ordering::SeqCst));
+
+    let (_sender, receiver) = mpsc::channel::<Vec<u8>>();
+    let stop = Arc::new(AtomicBool::new(false));
+    ctx.streams_mut()
+        .register_channel(receiver, Arc::clone(&stop), "utf-8");
+    drop(ctx);
+    assert!(stop.load(Ordering::SeqCst));
+    Ok(())
+}
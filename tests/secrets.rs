@@ -0,0 +1,109 @@
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use tempfile::tempdir;
+
+use lcod_kernel_rs::compose::{parse_compose, run_compose};
+use lcod_kernel_rs::secrets::{SecretStore, REDACTED};
+use lcod_kernel_rs::{register_core, register_flow, register_tooling, Context, Registry};
+
+fn setup() -> Registry {
+    let registry = Registry::new();
+    register_core(&registry);
+    register_flow(&registry);
+    register_tooling(&registry);
+    registry
+}
+
+#[test]
+fn secret_env_values_are_redacted_from_logs() -> Result<()> {
+    std::env::set_var("LCOD_TEST_SECRET_TOKEN", "tok-5f2a91c7");
+    let registry = setup();
+    let captured = Arc::new(Mutex::new(Vec::<Value>::new()));
+    let capture_clone = Arc::clone(&captured);
+    registry.register(
+        "lcod://impl/testing/log-capture@1",
+        move |_ctx: &mut Context, input: Value, _meta: Option<Value>| {
+            capture_clone.lock().unwrap().push(input.clone());
+            Ok(input)
+        },
+    );
+    registry.set_binding(
+        "lcod://contract/tooling/log@1",
+        "lcod://impl/testing/log-capture@1",
+    );
+
+    let steps = parse_compose(&json!([
+        {
+            "call": "lcod://contract/core/env/get@1",
+            "in": { "name": "LCOD_TEST_SECRET_TOKEN", "secret": true },
+            "out": { "token": "value" }
+        },
+        {
+            "call": "lcod://contract/tooling/log@1",
+            "in": {
+                "level": "info",
+                "message": "calling api",
+                "data": { "header": "Bearer tok-5f2a91c7", "token": "$.token" }
+            }
+        }
+    ]))?;
+    let mut ctx = registry.context();
+    let state = run_compose(&mut ctx, &steps, json!({}))?;
+    assert_eq!(state["token"], json!("tok-5f2a91c7"));
+
+    let captured = captured.lock().unwrap();
+    let entry = captured.last().expect("log captured");
+    assert_eq!(entry["data"]["token"], json!(REDACTED));
+    assert_eq!(entry["data"]["header"], json!(format!("Bearer {REDACTED}")));
+    Ok(())
+}
+
+#[test]
+fn secret_files_are_redacted_from_test_checker_reports() -> Result<()> {
+    let dir = tempdir()?;
+    let secret_path = dir.path().join("db-password");
+    fs::write(&secret_path, "hunter2-xyz\n")?;
+    let registry = setup();
+    let mut ctx = registry.context();
+
+    let report = ctx.call(
+        "lcod://tooling/test_checker@1",
+        json!({
+            "compose": [{
+                "call": "lcod://contract/core/secret/get@1",
+                "in": { "path": secret_path.to_string_lossy(), "required": true },
+                "out": { "password": "value" }
+            }],
+            "expected": { "password": "hunter2-xyz" }
+        }),
+        None,
+    )?;
+    assert_eq!(report["success"], json!(true));
+    assert_eq!(report["actual"]["password"], json!(REDACTED));
+    assert_eq!(report["expected"]["password"], json!(REDACTED));
+
+    let err = ctx
+        .call(
+            "lcod://contract/core/secret/get@1",
+            json!({ "env": "LCOD_TEST_SECRET_MISSING", "required": true }),
+            None,
+        )
+        .expect_err("missing secret");
+    assert!(err.to_string().contains("LCOD_TEST_SECRET_MISSING"));
+    Ok(())
+}
+
+#[test]
+fn short_values_are_not_masked() {
+    let store = SecretStore::new();
+    assert!(!store.register("abc"));
+    assert!(store.register("abcd-long"));
+    assert!(store.register("abcd"));
+    assert_eq!(
+        store.redact_value(&json!({ "list": ["x abcd-long y", "abc"] })),
+        json!({ "list": [format!("x {REDACTED} y"), "abc"] })
+    );
+}
@@ -244,3 +244,33 @@ fn script_console_routes_to_logging_contract() {
         .map(|msg| msg.contains("oops"))
         .unwrap_or(false));
 }
+
+#[test]
+fn script_process_env_is_restricted_only_when_env_is_listed() {
+    std::env::set_var("LCOD_SCRIPT_VISIBLE", "shown");
+    std::env::set_var("LCOD_SCRIPT_HIDDEN", "hidden");
+    let registry = Registry::new();
+    register_tooling(&registry);
+    let mut ctx = registry.context();
+
+    let request = json!({
+        "source": "async () => ({ visible: process.env.LCOD_SCRIPT_VISIBLE ?? null, hidden: process.env.LCOD_SCRIPT_HIDDEN ?? null })",
+        "env": ["LCOD_SCRIPT_VISIBLE"]
+    });
+    let result = ctx
+        .call("lcod://tooling/script@1", request, None)
+        .expect("script execution");
+
+    assert_eq!(result.get("visible"), Some(&json!("shown")));
+    assert_eq!(result.get("hidden"), Some(&Value::Null));
+
+    let request = json!({
+        "source": "async () => ({ visible: process.env.LCOD_SCRIPT_VISIBLE ?? null, hidden: process.env.LCOD_SCRIPT_HIDDEN ?? null })"
+    });
+    let result = ctx
+        .call("lcod://tooling/script@1", request, None)
+        .expect("script execution");
+
+    assert_eq!(result.get("visible"), Some(&json!("shown")));
+    assert_eq!(result.get("hidden"), Some(&json!("hidden")));
+}